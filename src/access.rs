@@ -0,0 +1,259 @@
+//! DNS-rebinding protection and the client IP allowlist.
+//!
+//! Requests must name an allowed host in their `Host` header: `localhost` (and its
+//! subdomains), any IP literal, or a name passed with `--allow-host`. A rebinding attack needs
+//! a hostname the attacker controls, so IP literals are always safe to accept. When
+//! `--allow-ip` is given, only loopback clients and the listed addresses or ranges may connect.
+//! The live-reload channels additionally refuse browsers whose `Origin` is another site.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use actix_web::{
+    Error, HttpRequest, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web,
+};
+
+use crate::startup::AppState;
+
+/// An address or CIDR range such as `192.168.1.0/24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => prefix_eq(
+                u32::from(range).into(),
+                u32::from(ip).into(),
+                self.prefix,
+                32,
+            ),
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                prefix_eq(u128::from(range), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(addr: IpAddr) -> Self {
+        let addr = addr.to_canonical();
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        Self { addr, prefix }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let parsed: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("`{value}` is not an IP address or CIDR range"))?;
+        // `::ffff:10.0.0.0/104` is matched as `10.0.0.0/8`, so its prefix counts the 96
+        // mapping bits that the canonical IPv4 form drops.
+        let addr = parsed.to_canonical();
+        let (max, mapped_bits) = match (parsed, addr) {
+            (IpAddr::V6(_), IpAddr::V4(_)) => (32, 96),
+            (_, IpAddr::V4(_)) => (32, 0),
+            (_, IpAddr::V6(_)) => (128, 0),
+        };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .and_then(|prefix| prefix.checked_sub(mapped_bits))
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("invalid prefix length in `{value}`"))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+fn prefix_eq(a: u128, b: u128, prefix: u8, bits: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = u32::from(bits - prefix);
+    (a >> shift) == (b >> shift)
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    if !state.allowed_ips.is_empty() {
+        // Unix socket connections have no peer address and are always local.
+        let allowed = req
+            .peer_addr()
+            .map(|peer| peer.ip().to_canonical())
+            .is_none_or(|ip| {
+                ip.is_loopback() || state.allowed_ips.iter().any(|range| range.contains(ip))
+            });
+        if !allowed {
+            let response = HttpResponse::Forbidden().body("client address not allowed");
+            return Ok(req.into_response(response));
+        }
+    }
+
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| req.uri().authority().map(|authority| authority.as_str()));
+    if let Some(host) = host
+        && !is_allowed_host(host, &state.allowed_hosts)
+    {
+        tracing::warn!(host, "rejected request with unknown Host header");
+        let response = HttpResponse::Forbidden()
+            .body("invalid Host header; pass --allow-host to accept this hostname");
+        return Ok(req.into_response(response));
+    }
+
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Whether a browser request comes from a page served here: `Origin` is absent (not a
+/// browser, or a same-origin `GET`), names the request's own `Host`, or names a host passed
+/// with `--allow-host`. Live-reload channels check this because browsers let any site open
+/// WebSockets and event streams to localhost.
+pub fn is_own_origin(req: &HttpRequest, allowed: &[String]) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN) else {
+        return true;
+    };
+    let Some(authority) = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+        .map(|(_, authority)| authority)
+    else {
+        return false;
+    };
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok());
+    host.is_some_and(|host| host.eq_ignore_ascii_case(authority))
+        || matches_allowlist(authority, allowed)
+}
+
+/// `allowed` entries match exactly; a leading `.` also matches subdomains and `*` matches
+/// everything.
+fn is_allowed_host(host: &str, allowed: &[String]) -> bool {
+    let name = strip_port(host).to_ascii_lowercase();
+    let name = name.trim_end_matches('.');
+
+    if name == "localhost" || name.ends_with(".localhost") {
+        return true;
+    }
+    let literal = name.trim_start_matches('[').trim_end_matches(']');
+    if literal.parse::<Ipv4Addr>().is_ok() || literal.parse::<Ipv6Addr>().is_ok() {
+        return true;
+    }
+
+    matches_allowlist(name, allowed)
+}
+
+/// Whether `host` matches one of the `--allow-host` patterns.
+fn matches_allowlist(host: &str, allowed: &[String]) -> bool {
+    let name = strip_port(host).to_ascii_lowercase();
+    let name = name.trim_end_matches('.');
+    allowed.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_prefix('.') {
+            _ if pattern == "*" => true,
+            Some(domain) => name == domain || name.ends_with(&pattern),
+            None => name == pattern,
+        }
+    })
+}
+
+fn strip_port(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        // `[::1]:3000`
+        return rest.split_once(']').map_or(host, |(addr, _)| addr);
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_are_checked_against_the_allowlist() {
+        let allowed = vec![String::from("dev.test"), String::from(".preview.test")];
+
+        for host in [
+            "localhost:3000",
+            "app.localhost",
+            "127.0.0.1:3000",
+            "192.168.1.20",
+            "[::1]:3000",
+            "dev.test:3000",
+            "preview.test",
+            "a.preview.test",
+        ] {
+            assert!(is_allowed_host(host, &allowed), "{host} should be allowed");
+        }
+        for host in ["evil.example", "dev.test.evil.example", "xpreview.test"] {
+            assert!(
+                !is_allowed_host(host, &allowed),
+                "{host} should be rejected"
+            );
+        }
+        assert!(is_allowed_host("evil.example", &[String::from("*")]));
+    }
+
+    #[test]
+    fn ip_ranges_match_by_prefix() {
+        let lan: IpRange = "192.168.1.0/24".parse().unwrap();
+        assert!(lan.contains("192.168.1.77".parse().unwrap()));
+        assert!(lan.contains("::ffff:192.168.1.77".parse().unwrap()));
+        assert!(!lan.contains("192.168.2.1".parse().unwrap()));
+
+        let single: IpRange = "fd00::1".parse().unwrap();
+        assert!(single.contains("fd00::1".parse().unwrap()));
+        assert!(!single.contains("fd00::2".parse().unwrap()));
+
+        let mapped: IpRange = "::ffff:10.0.0.0/104".parse().unwrap();
+        assert_eq!(mapped, "10.0.0.0/8".parse().unwrap());
+        assert!(mapped.contains("10.20.30.40".parse().unwrap()));
+        assert!(!mapped.contains("11.0.0.1".parse().unwrap()));
+        let mapped_single: IpRange = "::ffff:10.0.0.1".parse().unwrap();
+        assert!(mapped_single.contains("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(!mapped_single.contains("10.0.0.2".parse().unwrap()));
+        assert_eq!(
+            IpRange::from("::ffff:10.0.0.1".parse::<IpAddr>().unwrap()),
+            mapped_single
+        );
+        assert!("::ffff:10.0.0.0/95".parse::<IpRange>().is_err());
+        assert!("::ffff:10.0.0.0/129".parse::<IpRange>().is_err());
+
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("nonsense".parse::<IpRange>().is_err());
+    }
+}
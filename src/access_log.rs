@@ -0,0 +1,250 @@
+//! Apache-style access log behind `--access-log`, written next to (not instead of) the
+//! terminal log so tools such as goaccess can read local traffic.
+//!
+//! `combined` (the default) writes
+//! `host - user [10/Oct/2000:13:55:36 +0000] "GET /a.png HTTP/1.1" 200 2326 "referer" "agent"`
+//! and `common` drops the last two fields. Times are in UTC, the request line is the one the
+//! browser sent before rewrites or `--base-path` touch it, and the size is `-` for empty or
+//! streamed bodies such as compressed responses.
+
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use actix_web::{
+    Error,
+    body::{BodySize, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{StatusCode, header},
+    middleware::Next,
+    web,
+};
+use anyhow::Context;
+
+use crate::{auth, logging::UtcDateTime, startup::AppState};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AccessLogFormat {
+    Common,
+    #[default]
+    Combined,
+}
+
+pub struct AccessLog {
+    path: PathBuf,
+    format: AccessLogFormat,
+    file: Mutex<File>,
+}
+
+impl AccessLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path, format: AccessLogFormat) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open access log {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> AccessLogFormat {
+        self.format
+    }
+
+    fn write(&self, entry: &Entry, status: StatusCode, size: Option<u64>) {
+        let line = entry.format(self.format, status, size);
+        // One write per line, so concurrent requests do not interleave.
+        if let Ok(mut file) = self.file.lock()
+            && let Err(error) = file.write_all(line.as_bytes())
+        {
+            tracing::warn!(path = %self.path.display(), "failed to write access log: {error}");
+        }
+    }
+}
+
+/// The request half of a log line, taken before inner middleware can change the request.
+struct Entry {
+    host: String,
+    user: Option<String>,
+    time: SystemTime,
+    request_line: String,
+    referer: Option<Vec<u8>>,
+    user_agent: Option<Vec<u8>>,
+}
+
+impl Entry {
+    fn new(req: &ServiceRequest) -> Self {
+        let header_bytes = |name| {
+            req.headers()
+                .get(name)
+                .map(|value: &header::HeaderValue| value.as_bytes().to_vec())
+        };
+        let target = req
+            .uri()
+            .path_and_query()
+            .map_or_else(|| req.path(), |target| target.as_str());
+        Self {
+            host: req
+                .peer_addr()
+                .map_or_else(|| String::from("-"), |addr| addr.ip().to_string()),
+            user: auth::basic_user(req),
+            time: SystemTime::now(),
+            request_line: format!("{} {target} {:?}", req.method(), req.version()),
+            referer: header_bytes(header::REFERER),
+            user_agent: header_bytes(header::USER_AGENT),
+        }
+    }
+
+    fn format(&self, format: AccessLogFormat, status: StatusCode, size: Option<u64>) -> String {
+        let mut line = format!(
+            "{} - {} [{}] \"{}\" {} ",
+            self.host,
+            self.user
+                .as_deref()
+                .map_or_else(|| String::from("-"), |user| escape(user.as_bytes())),
+            clf_time(self.time),
+            escape(self.request_line.as_bytes()),
+            status.as_u16(),
+        );
+        match size.filter(|&size| size > 0) {
+            Some(size) => {
+                let _ = write!(line, "{size}");
+            }
+            None => line.push('-'),
+        }
+        if format == AccessLogFormat::Combined {
+            for value in [&self.referer, &self.user_agent] {
+                let value = value.as_deref().filter(|value| !value.is_empty());
+                let _ = write!(
+                    line,
+                    " \"{}\"",
+                    value.map_or_else(|| String::from("-"), escape)
+                );
+            }
+        }
+        line.push('\n');
+        line
+    }
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(log) = req
+        .app_data::<web::Data<AppState>>()
+        .and_then(|state| state.access_log.clone())
+    else {
+        return next.call(req).await;
+    };
+
+    let entry = Entry::new(&req);
+    match next.call(req).await {
+        Ok(res) => {
+            let size = match res.response().body().size() {
+                BodySize::Sized(size) => Some(size),
+                BodySize::None => Some(0),
+                BodySize::Stream => None,
+            };
+            log.write(&entry, res.status(), size);
+            Ok(res)
+        }
+        Err(error) => {
+            log.write(&entry, error.as_response_error().status_code(), None);
+            Err(error)
+        }
+    }
+}
+
+/// `%t` as Apache writes it, e.g. `10/Oct/2000:13:55:36 +0000`.
+fn clf_time(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let utc = UtcDateTime::from(time);
+    format!(
+        "{:02}/{}/{:04}:{:02}:{:02}:{:02} +0000",
+        utc.day,
+        MONTHS[(utc.month - 1) as usize],
+        utc.year,
+        utc.hour,
+        utc.minute,
+        utc.second
+    )
+}
+
+/// Escapes quotes, backslashes, and unprintable bytes the way Apache does, so every entry
+/// stays on one line and each quoted field can be split reliably.
+fn escape(value: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for &byte in value {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => {
+                let _ = write!(escaped, "\\x{byte:02x}");
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    fn entry() -> Entry {
+        Entry {
+            host: String::from("127.0.0.1"),
+            user: None,
+            time: UNIX_EPOCH + Duration::from_secs(971_186_136),
+            request_line: String::from("GET /apache_pb.gif HTTP/1.0"),
+            referer: Some(b"http://www.example.com/start.html".to_vec()),
+            user_agent: Some(b"Mozilla/4.08 [en] (Win98; I ;Nav)".to_vec()),
+        }
+    }
+
+    #[test]
+    fn formats_common_and_combined_lines() {
+        let entry = entry();
+        assert_eq!(
+            entry.format(AccessLogFormat::Common, StatusCode::OK, Some(2326)),
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /apache_pb.gif HTTP/1.0\" 200 2326\n"
+        );
+        assert_eq!(
+            entry.format(AccessLogFormat::Combined, StatusCode::NOT_MODIFIED, Some(0)),
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /apache_pb.gif HTTP/1.0\" 304 - \
+             \"http://www.example.com/start.html\" \"Mozilla/4.08 [en] (Win98; I ;Nav)\"\n"
+        );
+    }
+
+    #[test]
+    fn escapes_quotes_and_control_bytes() {
+        let mut entry = entry();
+        entry.user = Some(String::from("dev"));
+        entry.user_agent = Some(b"evil\" agent\\\n".to_vec());
+        entry.referer = None;
+        let line = entry.format(AccessLogFormat::Combined, StatusCode::OK, None);
+        assert!(line.starts_with("127.0.0.1 - dev ["), "got {line}");
+        assert!(
+            line.ends_with(" 200 - \"-\" \"evil\\\" agent\\\\\\x0a\"\n"),
+            "got {line}"
+        );
+    }
+}
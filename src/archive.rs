@@ -0,0 +1,464 @@
+//! Serving a `.zip`, `.tar.gz`/`.tgz`, or `.tar` bundle given as the base directory.
+//!
+//! The archive is unpacked into a private temporary directory that is served like any other
+//! base directory, so MIME types, Markdown, and index files behave the same. When every entry
+//! sits under one top-level folder (as zipping a folder produces), that folder's contents are
+//! served. The archive is watched and unpacked again when it changes; the directory is
+//! removed on drop.
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Context, bail};
+use flate2::read::{DeflateDecoder, GzDecoder};
+
+/// Unpacked copy of an archive.
+#[derive(Debug)]
+pub struct Extracted {
+    archive: PathBuf,
+    dir: PathBuf,
+    root: PathBuf,
+}
+
+impl Extracted {
+    /// Unpacks `archive` into a fresh temporary directory.
+    pub fn open(archive: &Path) -> anyhow::Result<Self> {
+        let archive = archive
+            .canonicalize()
+            .with_context(|| format!("failed to resolve archive {}", archive.display()))?;
+        let dir = private_temp_dir()?;
+        let root = dir.join("site");
+        fs::create_dir(&root).with_context(|| format!("failed to create {}", root.display()))?;
+
+        let extracted = Self { archive, dir, root };
+        extracted.refresh()?;
+        Ok(extracted)
+    }
+
+    /// The archive file being served.
+    pub fn archive(&self) -> &Path {
+        &self.archive
+    }
+
+    /// Directory to serve. It stays the same across refreshes.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Unpacks the archive again after it changed on disk.
+    ///
+    /// The new tree is unpacked next to the served one and its entries are then renamed into
+    /// place, so requests during the unpacking still find the old files instead of a `404`.
+    pub fn refresh(&self) -> anyhow::Result<()> {
+        let staging = self.scratch_dir("staging")?;
+        let result = self.unpack_and_swap(&staging);
+        let _ = fs::remove_dir_all(&staging);
+        result
+    }
+
+    fn unpack_and_swap(&self, staging: &Path) -> anyhow::Result<()> {
+        let entries = unpack(&self.archive, staging)?;
+        let tree = match single_top_level_dir(&entries) {
+            Some(top) => staging.join(top),
+            None => staging.to_path_buf(),
+        };
+
+        // Files are replaced in one rename; directories and removed entries go through a
+        // trash directory because a rename cannot replace a directory that has contents.
+        let trash = self.scratch_dir("old")?;
+        let mut new_names = Vec::new();
+        for entry in fs::read_dir(&tree)? {
+            let entry = entry?;
+            let target = self.root.join(entry.file_name());
+            if entry.file_type()?.is_dir() && target.exists() {
+                fs::rename(&target, trash.join(entry.file_name()))?;
+            }
+            fs::rename(entry.path(), &target)?;
+            new_names.push(entry.file_name());
+        }
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if !new_names.contains(&entry.file_name()) {
+                fs::rename(entry.path(), trash.join(entry.file_name()))?;
+            }
+        }
+        let _ = fs::remove_dir_all(&trash);
+
+        tracing::info!(
+            archive = %self.archive.display(),
+            entries = entries.len(),
+            "unpacked archive"
+        );
+        Ok(())
+    }
+
+    /// A new empty directory next to the served tree, on the same file system so its entries
+    /// can be renamed into place.
+    fn scratch_dir(&self, kind: &str) -> anyhow::Result<PathBuf> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let dir = self
+            .dir
+            .join(format!("{kind}-{}", NEXT.fetch_add(1, Ordering::Relaxed)));
+        fs::create_dir(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// Deletes the unpacked files. Runs on drop, and on shutdown since server workers may
+    /// still hold a reference at that point.
+    pub fn remove(&self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+impl Drop for Extracted {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// Creates a new directory only the current user can read, failing rather than reusing a
+/// path that already exists, since another user could have created it in the shared
+/// temporary directory to read or swap the unpacked files.
+fn private_temp_dir() -> anyhow::Result<PathBuf> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let temp = std::env::temp_dir();
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    loop {
+        let dir = temp.join(format!(
+            "web-dev-server-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to create {}", dir.display()));
+            }
+        }
+    }
+}
+
+/// Whether `path` names a supported archive file rather than a directory.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && Format::of(path).is_some()
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Zip,
+    TarGz,
+    Tar,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes every file in `archive` below `dest`, returning the relative paths written.
+fn unpack(archive: &Path, dest: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let format = Format::of(archive).context("unsupported archive type")?;
+    let file = fs::File::open(archive)
+        .with_context(|| format!("failed to open archive {}", archive.display()))?;
+    fs::create_dir_all(dest)?;
+
+    let mut written = Vec::new();
+    let mut write = |name: &str, contents: Option<Vec<u8>>| -> anyhow::Result<()> {
+        let Some(relative) = entry_path(name) else {
+            tracing::warn!(entry = name, "skipped archive entry with an unsafe path");
+            return Ok(());
+        };
+        let target = dest.join(&relative);
+        match contents {
+            Some(contents) => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&target, contents)?;
+                written.push(relative);
+            }
+            None => fs::create_dir_all(&target)?,
+        }
+        Ok(())
+    };
+
+    let result = match format {
+        Format::Zip => {
+            let mut data = Vec::new();
+            io::BufReader::new(file).read_to_end(&mut data)?;
+            read_zip(&data, &mut write)
+        }
+        Format::TarGz => read_tar(GzDecoder::new(io::BufReader::new(file)), &mut write),
+        Format::Tar => read_tar(io::BufReader::new(file), &mut write),
+    };
+    result.with_context(|| format!("failed to read archive {}", archive.display()))?;
+    Ok(written)
+}
+
+/// Relative path for an entry name, or `None` if it would leave the destination. macOS
+/// resource forks (`__MACOSX/`) are dropped as well.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    let hidden_fork = path.starts_with("__MACOSX");
+    (!path.as_os_str().is_empty() && !hidden_fork).then_some(path)
+}
+
+fn single_top_level_dir(entries: &[PathBuf]) -> Option<PathBuf> {
+    let mut tops = entries.iter().map(|entry| {
+        let mut components = entry.components();
+        let top = components.next()?;
+        // A file directly at the top level means there is no wrapping folder.
+        components.next()?;
+        Some(PathBuf::from(top.as_os_str()))
+    });
+    let first = tops.next()??;
+    tops.all(|top| top.as_ref() == Some(&first))
+        .then_some(first)
+}
+
+type Sink<'a> = dyn FnMut(&str, Option<Vec<u8>>) -> anyhow::Result<()> + 'a;
+
+fn read_zip(data: &[u8], sink: &mut Sink<'_>) -> anyhow::Result<()> {
+    const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+    const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+    const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+    // The end record is 22 bytes plus a comment of up to 64 KiB.
+    let search_from = data.len().saturating_sub(22 + usize::from(u16::MAX));
+    let end = (search_from..data.len().saturating_sub(21))
+        .rev()
+        .find(|&at| u32_at(data, at) == Some(END_OF_DIRECTORY))
+        .context("not a zip file")?;
+    let count = u16_at(data, end + 10).context("truncated zip")?;
+    let mut at = u32_at(data, end + 16).context("truncated zip")? as usize;
+    if at == 0xFFFF_FFFF {
+        bail!("zip64 archives are not supported");
+    }
+
+    for _ in 0..count {
+        if u32_at(data, at) != Some(DIRECTORY_ENTRY) {
+            bail!("corrupt zip central directory");
+        }
+        let field = |offset| u16_at(data, at + offset).context("truncated zip");
+        let method = field(10)?;
+        let compressed = u32_at(data, at + 20).context("truncated zip")? as usize;
+        let (name_len, extra_len, comment_len) = (
+            field(28)? as usize,
+            field(30)? as usize,
+            field(32)? as usize,
+        );
+        let local = u32_at(data, at + 42).context("truncated zip")? as usize;
+        let name = data
+            .get(at + 46..at + 46 + name_len)
+            .context("truncated zip")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        at += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            sink(&name, None)?;
+            continue;
+        }
+        if u32_at(data, local) != Some(LOCAL_HEADER) {
+            bail!("corrupt zip entry {name}");
+        }
+        let start = local
+            + 30
+            + u16_at(data, local + 26).context("truncated zip")? as usize
+            + u16_at(data, local + 28).context("truncated zip")? as usize;
+        let raw = data
+            .get(start..start + compressed)
+            .with_context(|| format!("truncated zip entry {name}"))?;
+        let contents = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut contents = Vec::new();
+                DeflateDecoder::new(raw).read_to_end(&mut contents)?;
+                contents
+            }
+            other => bail!("zip entry {name} uses unsupported compression method {other}"),
+        };
+        sink(&name, Some(contents))?;
+    }
+    Ok(())
+}
+
+fn read_tar(mut reader: impl Read, sink: &mut Sink<'_>) -> anyhow::Result<()> {
+    let mut header = [0u8; 512];
+    // Name from a preceding GNU long-name or pax header.
+    let mut long_name: Option<String> = None;
+
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+        let size = parse_octal(&header[124..136]).context("corrupt tar header")? as usize;
+        let mut data = vec![0; size];
+        reader.read_exact(&mut data)?;
+        let padding = (512 - size % 512) % 512;
+        io::copy(&mut (&mut reader).take(padding as u64), &mut io::sink())?;
+
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = c_str(&header[0..100]);
+            let prefix = c_str(&header[345..500]);
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{prefix}/{name}")
+            } else {
+                name
+            }
+        });
+        match header[156] {
+            b'0' | 0 | b'7' => sink(&name, Some(data))?,
+            b'5' => sink(&name, None)?,
+            b'L' => long_name = Some(c_str(&data)),
+            b'x' => long_name = pax_path(&data),
+            // Links, devices, and global pax headers are not needed to serve a site.
+            _ => {}
+        }
+    }
+}
+
+fn read_block(reader: &mut impl Read, block: &mut [u8; 512]) -> io::Result<bool> {
+    match reader.read_exact(block) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+fn c_str(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// The `path` record of a pax extended header (`<len> path=<value>\n`).
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        pair.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    fn tar_entry(out: &mut Vec<u8>, name: &str, kind: u8, data: &[u8]) {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..262].copy_from_slice(b"ustar");
+        out.extend_from_slice(&header);
+        out.extend_from_slice(data);
+        out.resize(out.len().div_ceil(512) * 512, 0);
+    }
+
+    #[test]
+    fn unpacks_tar_gz_into_a_single_root() {
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "demo/", b'5', b"");
+        tar_entry(&mut tar, "demo/index.html", b'0', b"<h1>demo</h1>");
+        let long = format!("demo/{}/deep.css", "nested".repeat(20));
+        tar_entry(&mut tar, "././@LongLink", b'L', long.as_bytes());
+        tar_entry(&mut tar, "ignored", b'0', b"body{}");
+        tar_entry(&mut tar, "../escape.txt", b'0', b"nope");
+        tar.extend_from_slice(&[0; 1024]);
+
+        let dir = std::env::temp_dir().join(format!("wds_archive_unit_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("demo.tar.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&tar).unwrap();
+        fs::write(&archive, encoder.finish().unwrap()).unwrap();
+
+        let extracted = Extracted::open(&archive).unwrap();
+        assert!(!extracted.root().join("demo").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&extracted.dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        assert_eq!(
+            fs::read_to_string(extracted.root().join("index.html")).unwrap(),
+            "<h1>demo</h1>"
+        );
+        let deep = extracted.root().join(&long["demo/".len()..]);
+        assert_eq!(fs::read_to_string(deep).unwrap(), "body{}");
+        assert!(!extracted.dir.join("..").join("escape.txt").exists());
+
+        // A refresh replaces the tree in place and drops entries the archive no longer has.
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "index.html", b'0', b"<h1>v2</h1>");
+        tar.extend_from_slice(&[0; 1024]);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&tar).unwrap();
+        fs::write(&archive, encoder.finish().unwrap()).unwrap();
+        let root = extracted.root().to_path_buf();
+        extracted.refresh().unwrap();
+        assert_eq!(extracted.root(), root);
+        assert_eq!(
+            fs::read_to_string(root.join("index.html")).unwrap(),
+            "<h1>v2</h1>"
+        );
+        let names: Vec<_> = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["index.html"]);
+        let scratch: Vec<_> = fs::read_dir(&extracted.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(scratch, ["site"]);
+
+        let unpacked = extracted.dir.clone();
+        drop(extracted);
+        assert!(!unpacked.exists());
+    }
+}
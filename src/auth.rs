@@ -0,0 +1,99 @@
+//! HTTP basic authentication behind `--auth user:pass`.
+//!
+//! Every route requires the credentials except `/_live/health`, and the control endpoints
+//! when `--control-token` is set, since those already check their own bearer token.
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::{
+    internal_scope::{CONTROL_PATHS, constant_time_eq},
+    startup::AppState,
+};
+
+const CHALLENGE: &str = "Basic realm=\"web-dev-server\", charset=\"UTF-8\"";
+
+/// Validates a `user:pass` argument.
+pub fn parse_credentials(value: &str) -> Result<String, String> {
+    match value.split_once(':') {
+        Some((user, _)) if !user.is_empty() => Ok(value.to_owned()),
+        _ => Err(String::from("expected USER:PASS")),
+    }
+}
+
+/// Base64 form of `user:pass` as it appears after `Basic ` in the `Authorization` header.
+pub fn encode_credentials(credentials: &str) -> String {
+    STANDARD.encode(credentials)
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let Some(expected) = state.basic_auth.as_deref() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let path = req.path();
+    let exempt =
+        path == "/_live/health" || (state.control_token.is_some() && CONTROL_PATHS.contains(&path));
+    if exempt || is_authorized(&req, expected) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let response = HttpResponse::Unauthorized()
+        .append_header((header::WWW_AUTHENTICATE, CHALLENGE))
+        .body("authentication required");
+    Ok(req.into_response(response))
+}
+
+fn is_authorized(req: &ServiceRequest, expected: &str) -> bool {
+    let provided = basic_token(req).unwrap_or_default();
+    constant_time_eq(provided.as_bytes(), expected.as_bytes())
+}
+
+/// User name sent with basic authentication, whether or not the password is right.
+pub fn basic_user(req: &ServiceRequest) -> Option<String> {
+    let decoded = STANDARD.decode(basic_token(req)?).ok()?;
+    let credentials = String::from_utf8(decoded).ok()?;
+    let (user, _) = credentials.split_once(':')?;
+    Some(user.to_owned())
+}
+
+fn basic_token(req: &ServiceRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("basic"))
+        .map(|(_, token)| token.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_need_a_user_and_separator() {
+        assert_eq!(
+            parse_credentials("dev:s3cret:x").as_deref(),
+            Ok("dev:s3cret:x")
+        );
+        assert!(parse_credentials("dev").is_err());
+        assert!(parse_credentials(":s3cret").is_err());
+        assert_eq!(
+            encode_credentials("Aladdin:open sesame"),
+            "QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+}
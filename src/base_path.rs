@@ -0,0 +1,86 @@
+//! Serving the site under a URL prefix behind `--base-path`, as GitHub Pages does for
+//! project sites (`https://user.github.io/my-repo/`).
+//!
+//! Requests under the prefix are served as if the prefix were the root; `/` redirects into
+//! the prefix and everything else outside it is `404`. `/_live` stays at the root.
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{Uri, header},
+    middleware::Next,
+    web,
+};
+
+use crate::{rewrites::rewrite_uri, startup::AppState};
+
+/// Normalizes `/my-repo/`, `my-repo`, or `/my-repo` to `/my-repo`; `/` means no prefix.
+pub fn normalize(prefix: &str) -> Option<String> {
+    let trimmed = prefix.trim_matches('/');
+    (!trimmed.is_empty()).then(|| format!("/{trimmed}"))
+}
+
+/// Path relative to the site root, or `None` when `path` lies outside `base_path`.
+fn strip<'a>(base_path: &str, path: &'a str) -> Option<&'a str> {
+    match path.strip_prefix(base_path)? {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+pub async fn middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let base_path = req
+        .app_data::<web::Data<AppState>>()
+        .and_then(|state| state.base_path.clone());
+    let Some(base_path) = base_path else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if req.path().starts_with("/_live/") {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let path = req.path().to_owned();
+    if path == "/" || path == base_path {
+        let response = HttpResponse::Found()
+            .append_header((header::LOCATION, format!("{base_path}/")))
+            .finish();
+        return Ok(req.into_response(response));
+    }
+    let Some(stripped) = strip(&base_path, &path) else {
+        let response = HttpResponse::NotFound()
+            .body(format!("not found; this site is served under {base_path}/"));
+        return Ok(req.into_response(response));
+    };
+
+    let target = match req.query_string() {
+        "" => stripped.to_owned(),
+        query => format!("{stripped}?{query}"),
+    };
+    match target.parse::<Uri>() {
+        Ok(uri) => rewrite_uri(&mut req, uri),
+        Err(error) => tracing::warn!(target, %error, "invalid request path"),
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_are_normalized_and_stripped() {
+        assert_eq!(normalize("/my-repo/").as_deref(), Some("/my-repo"));
+        assert_eq!(normalize("docs/v2").as_deref(), Some("/docs/v2"));
+        assert_eq!(normalize("/"), None);
+
+        assert_eq!(strip("/my-repo", "/my-repo/"), Some("/"));
+        assert_eq!(strip("/my-repo", "/my-repo/app.css"), Some("/app.css"));
+        assert_eq!(strip("/my-repo", "/my-repository/app.css"), None);
+        assert_eq!(strip("/my-repo", "/app.css"), None);
+    }
+}
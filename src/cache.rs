@@ -0,0 +1,110 @@
+//! In-memory copies of small static files and of injected HTML, so pages with many assets do
+//! not re-read the disk on every request. Entries are keyed by path and checked against the
+//! file's modification time; watcher events evict them as well.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use actix_web::web::Bytes;
+
+/// Files larger than this are always read from disk.
+pub const MAX_ENTRY_BYTES: u64 = 256 * 1024;
+
+/// Once the cache holds this much, it is emptied before new entries are added.
+const MAX_TOTAL_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct AssetCache {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<PathBuf, Entry>,
+    total: usize,
+}
+
+struct Entry {
+    modified: SystemTime,
+    body: Bytes,
+}
+
+impl AssetCache {
+    /// Cached body for `path` if it was stored for the same modification time.
+    pub fn get(&self, path: &Path, modified: SystemTime) -> Option<Bytes> {
+        let inner = self.inner.lock().ok()?;
+        inner
+            .entries
+            .get(path)
+            .filter(|entry| entry.modified == modified)
+            .map(|entry| entry.body.clone())
+    }
+
+    pub fn insert(&self, path: &Path, modified: SystemTime, body: Bytes) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        if inner.total + body.len() > MAX_TOTAL_BYTES {
+            inner.entries.clear();
+            inner.total = 0;
+        }
+        inner.total += body.len();
+        if let Some(previous) = inner
+            .entries
+            .insert(path.to_path_buf(), Entry { modified, body })
+        {
+            inner.total -= previous.body.len();
+        }
+    }
+
+    /// Drops the entries for `paths` and anything below them (for directory events).
+    pub fn evict(&self, paths: &[PathBuf]) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        let mut freed = 0;
+        inner.entries.retain(|key, entry| {
+            let stale = paths.iter().any(|path| key.starts_with(path));
+            if stale {
+                freed += entry.body.len();
+            }
+            !stale
+        });
+        inner.total -= freed;
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.entries.clear();
+            inner.total = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn entries_are_tied_to_the_modification_time() {
+        let cache = AssetCache::default();
+        let path = Path::new("/site/app.js");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+        cache.insert(path, modified, Bytes::from_static(b"console.log(1)"));
+
+        assert_eq!(
+            cache.get(path, modified).as_deref(),
+            Some(&b"console.log(1)"[..])
+        );
+        assert!(cache.get(path, modified + Duration::from_secs(1)).is_none());
+
+        cache.evict(&[PathBuf::from("/site")]);
+        assert!(cache.get(path, modified).is_none());
+    }
+}
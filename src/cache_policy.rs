@@ -0,0 +1,140 @@
+//! `Cache-Control` for served files, from the `cache_control` section of the config file and
+//! `--no-cache-all`.
+//!
+//! Each rule matches a path glob (`*` spans any characters, including `/`) or a list of file
+//! extensions and sets the header on successful responses:
+//!
+//! ```json
+//! { "cache_control": [
+//!     { "path": "/assets/*", "value": "public, max-age=31536000, immutable" },
+//!     { "extensions": ["png", "svg", "woff2"], "value": "public, max-age=3600" }
+//! ] }
+//! ```
+//!
+//! The first matching rule decides; other responses keep their defaults (`no-cache` for
+//! pages). `--no-cache-all` sends `no-store` for everything instead, rules included.
+//! `/_live` is never affected.
+
+use actix_web::{
+    Error,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        StatusCode,
+        header::{self, HeaderValue},
+    },
+    middleware::Next,
+    web,
+};
+use anyhow::Context;
+
+use crate::{config::CacheRuleConfig, faults::glob_match, startup::AppState};
+
+#[derive(Debug, Clone)]
+pub struct CacheRule {
+    matcher: Matcher,
+    value: HeaderValue,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Path(String),
+    /// Lowercase, without the leading dot.
+    Extensions(Vec<String>),
+}
+
+impl CacheRule {
+    pub fn compile(config: &CacheRuleConfig) -> anyhow::Result<Self> {
+        let matcher = match (&config.path, config.extensions.is_empty()) {
+            (Some(path), true) => Matcher::Path(path.clone()),
+            (None, false) => Matcher::Extensions(
+                config
+                    .extensions
+                    .iter()
+                    .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+                    .collect(),
+            ),
+            _ => anyhow::bail!(
+                "cache_control rule `{}` needs exactly one of path or extensions",
+                config.value
+            ),
+        };
+        let value = HeaderValue::from_str(&config.value)
+            .with_context(|| format!("invalid Cache-Control value `{}`", config.value))?;
+        Ok(Self { matcher, value })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match &self.matcher {
+            Matcher::Path(pattern) => glob_match(pattern, path),
+            Matcher::Extensions(extensions) => path
+                .rsplit('/')
+                .next()
+                .and_then(|name| name.rsplit_once('.'))
+                .is_some_and(|(_, extension)| {
+                    extensions
+                        .iter()
+                        .any(|expected| expected.eq_ignore_ascii_case(extension))
+                }),
+        }
+    }
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let value = req
+        .app_data::<web::Data<AppState>>()
+        .filter(|_| !req.path().starts_with("/_live/"))
+        .and_then(|state| {
+            if state.no_cache_all {
+                Some((HeaderValue::from_static("no-store"), true))
+            } else {
+                state
+                    .cache_rules
+                    .iter()
+                    .find(|rule| rule.matches(req.path()))
+                    .map(|rule| (rule.value.clone(), false))
+            }
+        });
+
+    let mut response = next.call(req).await?.map_into_boxed_body();
+    if let Some((value, always)) = value {
+        let status = response.status();
+        // Errors are not worth caching, or they would outlive the fix.
+        if always || status.is_success() || status == StatusCode::NOT_MODIFIED {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(path: Option<&str>, extensions: &[&str]) -> anyhow::Result<CacheRule> {
+        CacheRule::compile(&CacheRuleConfig {
+            path: path.map(String::from),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            value: String::from("max-age=60"),
+        })
+    }
+
+    #[test]
+    fn rules_match_globs_or_extensions() {
+        let assets = rule(Some("/assets/*"), &[]).unwrap();
+        assert!(assets.matches("/assets/app.3f2a.js"));
+        assert!(!assets.matches("/index.html"));
+
+        let images = rule(None, &[".PNG", "svg"]).unwrap();
+        assert!(images.matches("/img/logo.png"));
+        assert!(images.matches("/icon.SVG"));
+        assert!(!images.matches("/img.png/readme"));
+        assert!(!images.matches("/styles.css"));
+
+        assert!(rule(None, &[]).is_err());
+        assert!(rule(Some("/x"), &["js"]).is_err());
+    }
+}
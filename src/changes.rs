@@ -0,0 +1,185 @@
+//! Checks applied to watcher events before anything is broadcast: waiting until a file has
+//! stopped changing, so large generated files are not reloaded half-written, and dropping
+//! saves that left a file's content as it was.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use tokio::{fs, time::sleep};
+
+/// Gap between the size and modification time samples that must agree.
+const STABILITY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest wait for a file that keeps changing; the event is handled anyway afterwards.
+const STABILITY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Files larger than this are not hashed and always count as changed.
+const MAX_HASHED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Waits until every existing file in `paths` reports the same size and modification time
+/// twice in a row. Removed paths and directories are not waited for.
+pub async fn wait_until_stable(paths: &[PathBuf]) {
+    let deadline = Instant::now() + STABILITY_TIMEOUT;
+    let mut previous = snapshot(paths).await;
+    loop {
+        sleep(STABILITY_INTERVAL).await;
+        let current = snapshot(paths).await;
+        if current == previous {
+            return;
+        }
+        if Instant::now() >= deadline {
+            tracing::debug!(?paths, "files still changing, handling the event anyway");
+            return;
+        }
+        previous = current;
+    }
+}
+
+async fn snapshot(paths: &[PathBuf]) -> Vec<Option<(u64, Option<SystemTime>)>> {
+    let mut snapshot = Vec::with_capacity(paths.len());
+    for path in paths {
+        let metadata = fs::metadata(path).await.ok().filter(|meta| meta.is_file());
+        snapshot.push(metadata.map(|meta| (meta.len(), meta.modified().ok())));
+    }
+    snapshot
+}
+
+/// Content hashes of files as last served or seen by the watcher.
+#[derive(Debug, Default)]
+pub struct ContentHashes {
+    hashes: Mutex<HashMap<PathBuf, u64>>,
+}
+
+impl ContentHashes {
+    /// Remembers the content of `path`, e.g. when it is served.
+    pub fn record(&self, path: &Path, content: &[u8]) {
+        if let Ok(mut hashes) = self.hashes.lock() {
+            hashes.insert(path.to_path_buf(), hash(content));
+        }
+    }
+
+    /// Drops what was recorded for paths that were removed or renamed away.
+    pub fn forget(&self, paths: &[PathBuf]) {
+        if let Ok(mut hashes) = self.hashes.lock() {
+            for path in paths {
+                hashes.remove(path);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.hashes.lock().map(|hashes| hashes.len()).unwrap_or(0)
+    }
+
+    /// Reads every path and records its content, returning `true` when all of them match
+    /// what was recorded before. Anything unreadable, too large, or not seen before counts
+    /// as changed.
+    pub async fn unchanged(&self, paths: &[PathBuf]) -> bool {
+        let mut unchanged = !paths.is_empty();
+        for path in paths {
+            let current = match fs::metadata(path).await {
+                Ok(meta) if meta.is_file() && meta.len() <= MAX_HASHED_BYTES => {
+                    fs::read(path).await.ok().map(|content| hash(&content))
+                }
+                _ => None,
+            };
+            let Ok(mut hashes) = self.hashes.lock() else {
+                return false;
+            };
+            let previous = match current {
+                Some(current) => hashes.insert(path.clone(), current),
+                None => hashes.remove(path),
+            };
+            unchanged &= current.is_some() && previous == current;
+        }
+        unchanged
+    }
+}
+
+fn hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "web_dev_server_changes_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn saves_without_changes_are_detected() {
+        let dir = temp_dir("hashes");
+        let page = dir.join("index.html");
+        std::fs::write(&page, "<p>one</p>").unwrap();
+        let paths = [page.clone()];
+
+        let hashes = ContentHashes::default();
+        assert!(
+            !hashes.unchanged(&paths).await,
+            "first sighting counts as a change"
+        );
+        assert!(hashes.unchanged(&paths).await);
+
+        std::fs::write(&page, "<p>two</p>").unwrap();
+        assert!(!hashes.unchanged(&paths).await);
+
+        hashes.record(&page, b"<p>served</p>");
+        std::fs::write(&page, "<p>served</p>").unwrap();
+        assert!(hashes.unchanged(&paths).await);
+
+        std::fs::remove_file(&page).unwrap();
+        assert!(!hashes.unchanged(&paths).await);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn forgotten_paths_are_dropped() {
+        let hashes = ContentHashes::default();
+        hashes.record(Path::new("/site/a.html"), b"a");
+        hashes.record(Path::new("/site/b.html"), b"b");
+        hashes.forget(&[PathBuf::from("/site/a.html")]);
+        assert_eq!(hashes.len(), 1);
+        hashes.forget(&[PathBuf::from("/site/b.html"), PathBuf::from("/site/c.html")]);
+        assert_eq!(hashes.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn waits_for_a_growing_file() {
+        let dir = temp_dir("stable");
+        let report = dir.join("report.html");
+        std::fs::write(&report, "").unwrap();
+
+        let writer = tokio::spawn({
+            let report = report.clone();
+            async move {
+                for _ in 0..5 {
+                    sleep(Duration::from_millis(40)).await;
+                    let mut content = std::fs::read(&report).unwrap();
+                    content.extend_from_slice(&[b'x'; 1024]);
+                    std::fs::write(&report, content).unwrap();
+                }
+            }
+        });
+        sleep(Duration::from_millis(10)).await;
+        wait_until_stable(std::slice::from_ref(&report)).await;
+        assert_eq!(std::fs::metadata(&report).unwrap().len(), 5 * 1024);
+        writer.await.unwrap();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
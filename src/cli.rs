@@ -1,9 +1,17 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    io::IsTerminal,
+    path::{Component, Path},
+};
 
 use owo_colors::OwoColorize;
 use tokio::task;
 
-use crate::{config::DevServerConfig, startup::Application};
+use crate::{
+    access_log::AccessLogFormat, config::DevServerConfig, dotfiles::DotfilePolicy,
+    hooks::HookEvent, inject::InjectPosition, qr::QrCode, scaffold::Scaffolded,
+    startup::Application, workspace::Workspace,
+};
 
 enum ValueTone {
     Primary,
@@ -15,36 +23,158 @@ enum ValueTone {
 }
 
 pub fn print_startup_summary(config: &DevServerConfig, app: &Application) {
+    print_banner();
+
+    let (address_primary, address_alt) = match app.uds_path() {
+        Some(path) => (
+            format!("unix:{}", path.display()),
+            format!(
+                "curl --unix-socket {} {}",
+                path.display(),
+                app.primary_url()
+            ),
+        ),
+        None => (
+            app.primary_url(),
+            format!("http://localhost:{}{}", app.port(), app.url_suffix()),
+        ),
+    };
+    let network_url = app.network_url();
+
+    let mut rows: Vec<(&str, Cow<'_, str>, ValueTone)> = vec![
+        ("Address", Cow::Owned(address_primary), ValueTone::Primary),
+        ("Alt", Cow::Owned(address_alt), ValueTone::Muted),
+        ("Base Dir", site_dir(app), ValueTone::Accent),
+    ];
+    rows.extend(settings_rows(config, app));
+    if let Some(url) = app.tunnel_url() {
+        rows.insert(2, ("Public", Cow::Borrowed(url), ValueTone::Primary));
+    }
+    if let Some(url) = &network_url {
+        rows.insert(
+            2,
+            ("Network", Cow::Borrowed(url.as_str()), ValueTone::Primary),
+        );
+    }
+    print_rows(rows);
+
+    // The public URL works from anywhere, so it is preferred for the QR code.
+    let (qr_url, qr_hint) = match app.tunnel_url() {
+        Some(url) => (Some(url), "Scan to open the public URL on a phone:"),
+        None => (
+            network_url.as_deref(),
+            "Scan to open on a phone on the same network:",
+        ),
+    };
+    if let Some(qr) = qr_url.and_then(|url| QrCode::encode(url.as_bytes())) {
+        println!();
+        println!("  {}", qr_hint.bright_black());
+        for line in qr.render_half_blocks() {
+            println!("  {}", line.black().on_white());
+        }
+    }
+
+    print_footer(config, &app.base_dir().display().to_string());
+}
+
+/// Summary for a workspace: one row per site, then the settings they share.
+pub fn print_workspace_summary(config: &DevServerConfig, workspace: &Workspace) {
+    print_banner();
+
+    let mut rows: Vec<(&str, Cow<'_, str>, ValueTone)> = workspace
+        .sites()
+        .iter()
+        .map(|site| {
+            let value = format!("{}  {}", site.app.primary_url(), site_dir(&site.app));
+            (site.name.as_str(), Cow::Owned(value), ValueTone::Primary)
+        })
+        .collect();
+    if let Some(first) = workspace.sites().first() {
+        rows.extend(settings_rows(config, &first.app));
+    }
+    print_rows(rows);
+
+    print_footer(config, &format!("{} sites", workspace.sites().len()));
+}
+
+/// Lists the files `init` wrote or kept and how to start serving them.
+pub fn print_init_summary(dir: &Path, files: &[Scaffolded]) {
+    let rows = files
+        .iter()
+        .map(|file| match file {
+            Scaffolded::Written(path) => (
+                "Created",
+                Cow::Owned(path.display().to_string()),
+                ValueTone::Success,
+            ),
+            Scaffolded::Kept(path) => (
+                "Kept",
+                Cow::Owned(format!("{} (exists; --force overwrites)", path.display())),
+                ValueTone::Warning,
+            ),
+        })
+        .collect();
+    print_rows(rows);
+
+    // The config file is read from the working directory, so serve from inside `dir`.
+    let start = if dir.components().all(|part| part == Component::CurDir) {
+        String::from("web-dev-server")
+    } else {
+        format!("cd {} && web-dev-server", dir.display())
+    };
+    println!();
+    println!("  {} {}", "Start it with".bright_black(), start.cyan());
+}
+
+fn print_banner() {
     let title = "WEB DEV SERVER";
     let border = "=".repeat(title.len() + 8);
 
     println!("{}", border.clone().bright_black());
     println!("  {}", title.cyan().bold());
     println!("{}", border.bright_black());
+}
 
-    let address_primary = app.primary_url();
-    let address_alt = format!("http://localhost:{}", app.port());
-    let base_dir = Cow::Owned(app.base_dir().display().to_string());
+fn site_dir(app: &Application) -> Cow<'static, str> {
+    match app.archive() {
+        Some(archive) => Cow::Owned(format!("{} (archive)", archive.display())),
+        None => Cow::Owned(app.base_dir().display().to_string()),
+    }
+}
+
+/// Rows describing how files are watched and served, shared by every site.
+fn settings_rows(
+    config: &DevServerConfig,
+    app: &Application,
+) -> Vec<(&'static str, Cow<'static, str>, ValueTone)> {
     let diff_mode = if app.diff_mode() {
         Cow::Borrowed("ENABLED")
     } else {
         Cow::Borrowed("disabled")
     };
-    let watching = if config.diff_mode {
-        Cow::Borrowed("Diff HTML/CSS updates")
+    let watching = match (config.diff_mode, app.render_markdown()) {
+        (true, true) => Cow::Borrowed("Diff HTML/CSS/Markdown updates"),
+        (true, false) => Cow::Borrowed("Diff HTML/CSS updates"),
+        (false, _) => Cow::Borrowed("Full page reloads"),
+    };
+    let enabled_extras = enabled_extras(config, app);
+    let has_extras = !enabled_extras.is_empty();
+    let extras = if has_extras {
+        Cow::Owned(enabled_extras.join(", "))
     } else {
-        Cow::Borrowed("Full page reloads")
+        Cow::Borrowed("none")
     };
     let browser = if config.no_open_browser {
         Cow::Borrowed("Manual (--no-open-browser)")
-    } else {
+    } else if app.uds_path().is_some() {
+        Cow::Borrowed("Manual (Unix socket)")
+    } else if config.browsers.is_empty() {
         Cow::Borrowed("Auto-open on start")
+    } else {
+        Cow::Owned(format!("Auto-open in {}", config.browsers.join(", ")))
     };
 
-    let rows: Vec<(&str, Cow<'_, str>, ValueTone)> = vec![
-        ("Address", Cow::Owned(address_primary), ValueTone::Primary),
-        ("Alt", Cow::Owned(address_alt), ValueTone::Muted),
-        ("Base Dir", base_dir, ValueTone::Accent),
+    let mut rows = vec![
         (
             "Diff Mode",
             diff_mode,
@@ -55,6 +185,15 @@ pub fn print_startup_summary(config: &DevServerConfig, app: &Application) {
             },
         ),
         ("Watching", watching, ValueTone::Warning),
+        (
+            "Extras",
+            extras,
+            if has_extras {
+                ValueTone::Success
+            } else {
+                ValueTone::Muted
+            },
+        ),
         ("Browser", browser, ValueTone::Accent),
         (
             "Exit",
@@ -62,7 +201,20 @@ pub fn print_startup_summary(config: &DevServerConfig, app: &Application) {
             ValueTone::Accent,
         ),
     ];
+    if std::io::stdin().is_terminal() {
+        rows.insert(
+            rows.len() - 1,
+            (
+                "Keys",
+                Cow::Borrowed("r reload, o open browser, c clear, q quit"),
+                ValueTone::Accent,
+            ),
+        );
+    }
+    rows
+}
 
+fn print_rows(rows: Vec<(&str, Cow<'_, str>, ValueTone)>) {
     let label_width = rows
         .iter()
         .map(|(label, _, _)| label.len())
@@ -77,13 +229,11 @@ pub fn print_startup_summary(config: &DevServerConfig, app: &Application) {
 
         println!("  {} {}", colored_label, colored_value);
     }
+}
 
+fn print_footer(config: &DevServerConfig, serving: &str) {
     println!();
-    println!(
-        "  {} {}",
-        "Serving".bright_black(),
-        app.base_dir().display().to_string().bright_black()
-    );
+    println!("  {} {}", "Serving".bright_black(), serving.bright_black());
     if config.no_open_browser {
         println!(
             "  {}",
@@ -108,6 +258,107 @@ pub fn print_startup_summary(config: &DevServerConfig, app: &Application) {
     println!();
 }
 
+/// Opt-in features that are active, in the order they appear in `--help`.
+fn enabled_extras(config: &DevServerConfig, app: &Application) -> Vec<String> {
+    let mut extras = Vec::new();
+    if app.render_markdown() {
+        extras.push(String::from("markdown"));
+    }
+    if app.clean_urls() {
+        extras.push(String::from("clean URLs"));
+    }
+    if app.sass() {
+        extras.push(format!("sass ({})", config.sass_command));
+    }
+    if app.transpile() {
+        extras.push(format!("transpile ({})", config.esbuild_command));
+    }
+    if app.dotfiles() == DotfilePolicy::Allow {
+        extras.push(String::from("dotfiles allowed"));
+    }
+    if app.follow_symlinks() {
+        extras.push(String::from("follow symlinks"));
+    }
+    if app.includes() {
+        extras.push(String::from("includes"));
+    }
+    if let Some(mocks_dir) = app.mocks_dir() {
+        extras.push(format!("mocks ({})", mocks_dir.display()));
+    }
+    for mount in app.mounts() {
+        extras.push(format!("mount {} ({})", mount.prefix, mount.dir.display()));
+    }
+    for path in app.watch_paths() {
+        extras.push(format!("watch ({})", path.display()));
+    }
+    if app.verbose_watch() {
+        extras.push(String::from("verbose watch"));
+    }
+    if app.control_api() {
+        extras.push(String::from("control API"));
+    }
+    if app.basic_auth() {
+        extras.push(String::from("basic auth"));
+    }
+    if app.ip_allowlist() {
+        extras.push(String::from("IP allowlist"));
+    }
+    if let Some(origin) = app.cors_origin() {
+        extras.push(format!("CORS ({origin})"));
+    }
+    if app.inject_position() == InjectPosition::BodyEnd {
+        extras.push(String::from("client at body end"));
+    }
+    if !app.injection_skips().is_empty() {
+        extras.push(format!("no inject ({})", app.injection_skips().join(", ")));
+    }
+    if let Some(path) = &config.inject_snippet {
+        extras.push(format!("inject snippet ({})", path.display()));
+    }
+    if app.compress() {
+        extras.push(String::from("compression"));
+    }
+    if app.no_cache_all() {
+        extras.push(String::from("no-store for all files"));
+    } else if app.cache_rules() > 0 {
+        extras.push(format!("cache-control ({} rules)", app.cache_rules()));
+    }
+    if app.http2() {
+        extras.push(String::from("http2 (h2c)"));
+    }
+    if app.writable() {
+        extras.push(String::from("writable (PUT/DELETE)"));
+    }
+    if app.faults() > 0 {
+        extras.push(format!("faults ({} rules)", app.faults()));
+    }
+    if let Some(throttle) = app.throttle() {
+        extras.push(format!(
+            "throttle ({} kbps, {} ms)",
+            throttle.kbps,
+            throttle.latency.as_millis()
+        ));
+    }
+    if app.sync() {
+        extras.push(String::from("browser sync"));
+    }
+    if let Some(endpoint) = &config.otel_endpoint {
+        extras.push(format!("otel traces ({endpoint})"));
+    }
+    if let Some(log) = app.access_log() {
+        let format = match log.format() {
+            AccessLogFormat::Common => "common",
+            AccessLogFormat::Combined => "combined",
+        };
+        extras.push(format!("access log ({format}, {})", log.path().display()));
+    }
+    let hooks: Vec<&str> = app.hooks().events().map(HookEvent::name).collect();
+    if !hooks.is_empty() {
+        extras.push(format!("hooks ({})", hooks.join(", ")));
+    }
+    extras
+}
+
 fn colorize(value: &str, tone: ValueTone) -> String {
     match tone {
         ValueTone::Primary => value.bold().bright_white().to_string(),
@@ -119,16 +370,58 @@ fn colorize(value: &str, tone: ValueTone) -> String {
     }
 }
 
-pub fn launch_browser(url: String) {
+/// URL opened on startup: the `--open` path, or the site root.
+pub fn browser_url(config: &DevServerConfig, app: &Application) -> String {
+    match config.open.as_deref() {
+        Some(path) => format!(
+            "http://{}{}/{}",
+            app.address(),
+            app.base_path().unwrap_or_default(),
+            path.trim_start_matches('/')
+        ),
+        None => app.primary_url(),
+    }
+}
+
+/// Opens `url` in each of `browsers`, or in the system default browser when none are given.
+/// A browser containing whitespace is run as a command with the URL appended.
+pub fn launch_browser(url: String, browsers: Vec<String>) {
     task::spawn(async move {
-        match task::spawn_blocking(move || open::that(url)).await {
+        match task::spawn_blocking(move || open_url(&url, &browsers)).await {
             Ok(Ok(())) => {}
             Ok(Err(error)) => {
-                eprintln!("[web-dev-server] failed to open browser: {error}");
+                tracing::warn!(%error, "failed to open browser");
             }
             Err(error) => {
-                eprintln!("[web-dev-server] browser task join error: {error}");
+                tracing::error!(%error, "browser task join error");
             }
         }
     });
 }
+
+fn open_url(url: &str, browsers: &[String]) -> std::io::Result<()> {
+    if browsers.is_empty() {
+        return open::that(url);
+    }
+
+    let mut result = Ok(());
+    for browser in browsers {
+        let mut words = browser.split_whitespace();
+        let opened = match (words.next(), words.next()) {
+            (Some(program), Some(first)) => std::process::Command::new(program)
+                .arg(first)
+                .args(words)
+                .arg(url)
+                .spawn()
+                .map(drop),
+            _ => open::with(url, browser.trim()),
+        };
+        if let Err(error) = opened {
+            result = Err(std::io::Error::new(
+                error.kind(),
+                format!("{browser}: {error}"),
+            ));
+        }
+    }
+    result
+}
@@ -0,0 +1,153 @@
+//! Registry of connected live-reload clients, listed by `GET /_live/clients`.
+
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use crate::logging;
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    pub id: u64,
+    /// `websocket` or `sse`.
+    pub transport: &'static str,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// Path of the page the client script runs on.
+    pub page: Option<String>,
+    /// RFC 3339 UTC timestamp.
+    pub connected_at: String,
+}
+
+impl ClientInfo {
+    pub fn new(id: u64, transport: &'static str) -> Self {
+        Self {
+            id,
+            transport,
+            ip: None,
+            user_agent: None,
+            page: None,
+            connected_at: logging::rfc3339(SystemTime::now()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    clients: Mutex<BTreeMap<u64, ClientInfo>>,
+    /// Connected clients when the server began to stop, before their sessions closed.
+    at_shutdown: OnceLock<usize>,
+}
+
+impl ClientRegistry {
+    /// Records a connected client until the returned guard is dropped.
+    pub fn register(self: &Arc<Self>, info: ClientInfo) -> ClientGuard {
+        tracing::info!(
+            client_id = info.id,
+            transport = info.transport,
+            ip = ?info.ip,
+            page = info.page.as_deref().unwrap_or("?"),
+            "live client connected"
+        );
+        let id = info.id;
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.insert(id, info);
+        }
+        ClientGuard {
+            registry: Arc::clone(self),
+            id,
+        }
+    }
+
+    /// Connected clients, oldest first.
+    pub fn list(&self) -> Vec<ClientInfo> {
+        self.clients
+            .lock()
+            .map(|clients| clients.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Transport of a connected client, `None` once it is gone or if it never existed.
+    pub fn transport(&self, id: u64) -> Option<&'static str> {
+        self.clients
+            .lock()
+            .ok()
+            .and_then(|clients| clients.get(&id).map(|info| info.transport))
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients
+            .lock()
+            .map(|clients| clients.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remembers how many clients are connected as the server starts to stop. Only the
+    /// first call counts.
+    pub fn record_shutdown(&self) {
+        let _ = self.at_shutdown.set(self.len());
+    }
+
+    /// Clients connected when [`record_shutdown`](Self::record_shutdown) was called, or
+    /// right now if it was not.
+    pub fn len_at_shutdown(&self) -> usize {
+        self.at_shutdown
+            .get()
+            .copied()
+            .unwrap_or_else(|| self.len())
+    }
+}
+
+/// Removes its client from the registry when the session ends.
+pub struct ClientGuard {
+    registry: Arc<ClientRegistry>,
+    id: u64,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        let removed = self
+            .registry
+            .clients
+            .lock()
+            .ok()
+            .and_then(|mut clients| clients.remove(&self.id));
+        if let Some(info) = removed {
+            tracing::info!(
+                client_id = info.id,
+                transport = info.transport,
+                page = info.page.as_deref().unwrap_or("?"),
+                "live client disconnected"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_unregister_their_client() {
+        let registry = Arc::new(ClientRegistry::default());
+        let first = registry.register(ClientInfo::new(1, "websocket"));
+        let second = registry.register(ClientInfo::new(2, "sse"));
+        assert_eq!(registry.len(), 2);
+
+        drop(first);
+        let remaining = registry.list();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, 2);
+
+        drop(second);
+        assert!(registry.is_empty());
+    }
+}
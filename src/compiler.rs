@@ -0,0 +1,144 @@
+//! Sources compiled on request by an external executable, shared by `--sass` and
+//! `--transpile`.
+//!
+//! A request for a source (`style.scss`, `app.tsx`) is compiled directly, and a request for
+//! a missing output file (`style.css`, `app.js`) falls back to a source with the same stem.
+//! Output is cached by the source's modification time until the watcher reports a change.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use tokio::{fs, process::Command};
+
+/// What an external compiler reads and produces.
+pub struct Language {
+    /// Source extensions, in the order they are tried for a missing output file.
+    pub sources: &'static [&'static str],
+    /// Extension of the files the sources stand in for.
+    pub output: &'static str,
+    pub content_type: &'static str,
+    /// Adds the arguments that compile `source` to standard output.
+    pub args: fn(&mut Command, &Path),
+}
+
+pub struct ExternalCompiler {
+    command: String,
+    language: Language,
+    pub(crate) cache: Mutex<HashMap<PathBuf, Cached>>,
+}
+
+pub(crate) struct Cached {
+    pub(crate) modified: SystemTime,
+    pub(crate) output: String,
+}
+
+impl ExternalCompiler {
+    pub fn new(command: impl Into<String>, language: Language) -> Self {
+        Self {
+            command: command.into(),
+            language,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        self.language.content_type
+    }
+
+    pub fn is_source(&self, path: &Path) -> bool {
+        has_extension(path, self.language.sources)
+    }
+
+    /// Maps a requested file to the source that should produce it: a source is served
+    /// directly, and a missing output file falls back to the first source found next to it.
+    pub async fn resolve_source(&self, requested: &Path) -> Option<PathBuf> {
+        if self.is_source(requested) {
+            return fs::metadata(requested)
+                .await
+                .is_ok()
+                .then(|| requested.to_path_buf());
+        }
+
+        if !has_extension(requested, &[self.language.output])
+            || fs::metadata(requested).await.is_ok()
+        {
+            return None;
+        }
+
+        for ext in self.language.sources {
+            let candidate = requested.with_extension(ext);
+            if fs::metadata(&candidate).await.is_ok() {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    pub async fn compile(&self, source: &Path) -> anyhow::Result<String> {
+        let modified = fs::metadata(source).await?.modified()?;
+
+        if let Some(cached) = self.cache.lock().unwrap().get(source)
+            && cached.modified == modified
+        {
+            return Ok(cached.output.clone());
+        }
+
+        let mut command = Command::new(&self.command);
+        (self.language.args)(&mut command, source);
+        let output = command
+            .output()
+            .await
+            .with_context(|| format!("failed to run `{}`", self.command))?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} failed for {}: {}",
+                self.command,
+                source.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let compiled = String::from_utf8(output.stdout)?;
+        self.cache.lock().unwrap().insert(
+            source.to_path_buf(),
+            Cached {
+                modified,
+                output: compiled.clone(),
+            },
+        );
+
+        Ok(compiled)
+    }
+
+    /// Drops the cached output of one source.
+    pub fn evict(&self, source: &Path) {
+        self.cache.lock().unwrap().remove(source);
+    }
+
+    /// Drops all cached output, returning the sources it belonged to.
+    pub fn clear(&self) -> Vec<PathBuf> {
+        self.cache
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(path, _)| path)
+            .collect()
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(ext))
+        })
+}
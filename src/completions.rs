@@ -0,0 +1,399 @@
+//! The `completions` subcommand: shell completion scripts generated from the clap definition,
+//! so they list every flag without being maintained by hand.
+
+use std::fmt::Write;
+
+use clap::{ArgAction, ValueHint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Completion script for `command`, installed under its name.
+pub fn generate(shell: Shell, mut command: clap::Command) -> String {
+    command.build();
+    let spec = Spec::new(&command);
+    match shell {
+        Shell::Bash => bash(&spec),
+        Shell::Zsh => zsh(&spec),
+        Shell::Fish => fish(&spec),
+    }
+}
+
+/// The parts of a command line completions care about.
+struct Spec {
+    bin: String,
+    root: Vec<Opt>,
+    positional_values: Vec<String>,
+    subcommands: Vec<Subcommand>,
+}
+
+struct Subcommand {
+    name: String,
+    about: String,
+    opts: Vec<Opt>,
+    positional_values: Vec<String>,
+    /// Takes the same arguments as the bare command, like `serve`.
+    same_as_root: bool,
+}
+
+#[derive(PartialEq)]
+struct Opt {
+    long: String,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    repeatable: bool,
+    path: bool,
+    values: Vec<String>,
+}
+
+impl Spec {
+    fn new(command: &clap::Command) -> Self {
+        let root = opts(command);
+        let subcommands = command
+            .get_subcommands()
+            .map(|sub| {
+                let opts = opts(sub);
+                Subcommand {
+                    name: sub.get_name().to_owned(),
+                    about: sub.get_about().map(first_line).unwrap_or_default(),
+                    same_as_root: opts == root,
+                    opts,
+                    positional_values: positional_values(sub),
+                }
+            })
+            .collect();
+        Self {
+            bin: command.get_name().to_owned(),
+            positional_values: positional_values(command),
+            root,
+            subcommands,
+        }
+    }
+
+    /// Shell function name derived from the binary name.
+    fn function(&self) -> String {
+        format!("_{}", self.bin.replace('-', "_"))
+    }
+
+    /// Subcommands with arguments of their own, i.e. not aliases of the bare command.
+    fn distinct_subcommands(&self) -> impl Iterator<Item = &Subcommand> {
+        self.subcommands.iter().filter(|sub| !sub.same_as_root)
+    }
+}
+
+fn opts(command: &clap::Command) -> Vec<Opt> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .filter_map(|arg| {
+            let takes_value = arg.get_action().takes_values();
+            Some(Opt {
+                long: arg.get_long()?.to_owned(),
+                short: arg.get_short(),
+                help: arg.get_help().map(first_line).unwrap_or_default(),
+                takes_value,
+                repeatable: matches!(arg.get_action(), ArgAction::Append | ArgAction::Count),
+                path: matches!(
+                    arg.get_value_hint(),
+                    ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath
+                ),
+                values: if takes_value {
+                    possible_values(arg)
+                } else {
+                    Vec::new()
+                },
+            })
+        })
+        .collect()
+}
+
+fn positional_values(command: &clap::Command) -> Vec<String> {
+    command
+        .get_positionals()
+        .next()
+        .map(possible_values)
+        .unwrap_or_default()
+}
+
+fn possible_values(arg: &clap::Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_owned())
+        .collect()
+}
+
+fn first_line(text: &clap::builder::StyledStr) -> String {
+    text.to_string()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn bash(spec: &Spec) -> String {
+    let function = spec.function();
+    let names: Vec<&str> = spec
+        .subcommands
+        .iter()
+        .map(|sub| sub.name.as_str())
+        .collect();
+    let mut script = format!(
+        "{function}() {{\n    \
+         local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    \
+         local command=\"\" word\n    \
+         for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do\n        \
+         case \"$word\" in\n            \
+         {}) command=\"$word\"; break ;;\n        \
+         esac\n    \
+         done\n\n    \
+         case \"$prev\" in\n",
+        names.join("|")
+    );
+
+    // Values without fixed choices fall through to filename completion (`-o default`).
+    let all_opts = spec
+        .root
+        .iter()
+        .chain(spec.distinct_subcommands().flat_map(|sub| sub.opts.iter()));
+    let mut free_values = Vec::new();
+    for opt in all_opts.filter(|opt| opt.takes_value) {
+        if opt.values.is_empty() {
+            free_values.push(format!("--{}", opt.long));
+        } else {
+            let _ = writeln!(
+                script,
+                "        --{}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+                opt.long,
+                opt.values.join(" ")
+            );
+        }
+    }
+    if !free_values.is_empty() {
+        let _ = writeln!(script, "        {}) return ;;", free_values.join("|"));
+    }
+    script.push_str("    esac\n\n    local options choices\n    case \"$command\" in\n");
+
+    for sub in &spec.subcommands {
+        let _ = writeln!(
+            script,
+            "        {}) options=\"{}\"; choices=\"{}\" ;;",
+            sub.name,
+            bash_words(&sub.opts),
+            sub.positional_values.join(" ")
+        );
+    }
+    // Only the bare command offers the subcommands themselves.
+    let mut root_choices = spec.positional_values.clone();
+    root_choices.extend(names.iter().map(|name| name.to_string()));
+    let _ = writeln!(
+        script,
+        "        *) options=\"{}\"; choices=\"{}\" ;;",
+        bash_words(&spec.root),
+        root_choices.join(" ")
+    );
+    let _ = write!(
+        script,
+        "    esac\n\n    \
+         if [[ \"$cur\" == -* ]]; then\n        \
+         COMPREPLY=($(compgen -W \"$options\" -- \"$cur\"))\n    \
+         elif [[ -n \"$choices\" ]]; then\n        \
+         COMPREPLY=($(compgen -W \"$choices\" -- \"$cur\"))\n    \
+         fi\n}}\n\ncomplete -o default -F {function} {}\n",
+        spec.bin
+    );
+    script
+}
+
+fn bash_words(opts: &[Opt]) -> String {
+    opts.iter()
+        .flat_map(|opt| {
+            std::iter::once(format!("--{}", opt.long))
+                .chain(opt.short.map(|short| format!("-{short}")))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn zsh(spec: &Spec) -> String {
+    let function = spec.function();
+    let mut script = format!(
+        "#compdef {}\n\n{function}() {{\n    local state\n    local -a commands\n    commands=(\n",
+        spec.bin
+    );
+    for sub in &spec.subcommands {
+        let _ = writeln!(script, "        '{}:{}'", sub.name, zsh_quote(&sub.about));
+    }
+    script.push_str("    )\n\n    case $words[2] in\n");
+
+    let mut root_names = Vec::new();
+    for sub in &spec.subcommands {
+        if sub.same_as_root {
+            root_names.push(sub.name.as_str());
+            continue;
+        }
+        let _ = writeln!(
+            script,
+            "        {})\n            shift words; (( CURRENT-- ))\n            _arguments -s \\\n{}                '1: :{}'\n            ;;",
+            sub.name,
+            zsh_specs(&sub.opts),
+            zsh_positional(&sub.positional_values)
+        );
+    }
+    if !root_names.is_empty() {
+        let _ = writeln!(
+            script,
+            "        {})\n            shift words; (( CURRENT-- ))\n            _arguments -s \\\n{}                '1: :_files -/'\n            ;;",
+            root_names.join("|"),
+            zsh_specs(&spec.root)
+        );
+    }
+    let _ = write!(
+        script,
+        "        *)\n            _arguments -s \\\n{}                '1: :->first'\n            \
+         if [[ $state == first ]]; then\n                \
+         _describe -t commands command commands\n                \
+         _files -/\n            \
+         fi\n            ;;\n    esac\n}}\n\n{function} \"$@\"\n",
+        zsh_specs(&spec.root)
+    );
+    script
+}
+
+fn zsh_specs(opts: &[Opt]) -> String {
+    let mut specs = String::new();
+    for opt in opts {
+        let names = std::iter::once(format!("--{}", opt.long))
+            .chain(opt.short.map(|short| format!("-{short}")));
+        for name in names {
+            let repeat = if opt.repeatable { "*" } else { "" };
+            let help = zsh_quote(&opt.help).replace('[', "\\[").replace(']', "\\]");
+            let value = if !opt.takes_value {
+                String::new()
+            } else if !opt.values.is_empty() {
+                format!(":value:({})", opt.values.join(" "))
+            } else if opt.path {
+                String::from(":path:_files")
+            } else {
+                String::from(":value: ")
+            };
+            let separator = if opt.takes_value { "=" } else { "" };
+            let _ = writeln!(
+                specs,
+                "                '{repeat}{name}{separator}[{help}]{value}' \\"
+            );
+        }
+    }
+    specs
+}
+
+fn zsh_positional(values: &[String]) -> String {
+    if values.is_empty() {
+        String::from("_files -/")
+    } else {
+        format!("({})", values.join(" "))
+    }
+}
+
+fn zsh_quote(text: &str) -> String {
+    text.replace('\'', "'\\''")
+}
+
+fn fish(spec: &Spec) -> String {
+    let bin = &spec.bin;
+    let mut script = String::new();
+    for sub in &spec.subcommands {
+        let _ = writeln!(
+            script,
+            "complete -c {bin} -n __fish_use_subcommand -a {} -d '{}'",
+            sub.name,
+            fish_quote(&sub.about)
+        );
+    }
+
+    let others: Vec<&str> = spec
+        .distinct_subcommands()
+        .map(|sub| sub.name.as_str())
+        .collect();
+    let root_condition = if others.is_empty() {
+        String::from("true")
+    } else {
+        format!("not __fish_seen_subcommand_from {}", others.join(" "))
+    };
+    fish_opts(&mut script, bin, &root_condition, &spec.root);
+    if !spec.positional_values.is_empty() {
+        let _ = writeln!(
+            script,
+            "complete -c {bin} -n __fish_use_subcommand -f -a '{}'",
+            spec.positional_values.join(" ")
+        );
+    }
+
+    for sub in spec.distinct_subcommands() {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.name);
+        fish_opts(&mut script, bin, &condition, &sub.opts);
+        if !sub.positional_values.is_empty() {
+            let _ = writeln!(
+                script,
+                "complete -c {bin} -n '{condition}; and not __fish_seen_subcommand_from {}' -f -a '{}'",
+                sub.positional_values.join(" "),
+                sub.positional_values.join(" ")
+            );
+        }
+    }
+    script
+}
+
+fn fish_opts(script: &mut String, bin: &str, condition: &str, opts: &[Opt]) {
+    for opt in opts {
+        let _ = write!(script, "complete -c {bin} -n '{condition}' -l {}", opt.long);
+        if let Some(short) = opt.short {
+            let _ = write!(script, " -s {short}");
+        }
+        let _ = write!(script, " -d '{}'", fish_quote(&opt.help));
+        if !opt.values.is_empty() {
+            let _ = write!(script, " -x -a '{}'", opt.values.join(" "));
+        } else if opt.path {
+            script.push_str(" -r -F");
+        } else if opt.takes_value {
+            script.push_str(" -x");
+        }
+        script.push('\n');
+    }
+}
+
+fn fish_quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::config::Cli;
+
+    #[test]
+    fn scripts_cover_subcommands_flags_and_choices() {
+        let bash = generate(Shell::Bash, Cli::command());
+        assert!(bash.contains("serve|init|completions) command=\"$word\""));
+        assert!(bash.contains("--diff-mode"));
+        assert!(bash.contains("--log-format) COMPREPLY=($(compgen -W \"text json\""));
+        assert!(bash.contains("complete -o default -F _web_dev_server web-dev-server"));
+
+        let zsh = generate(Shell::Zsh, Cli::command());
+        assert!(zsh.starts_with("#compdef web-dev-server\n"));
+        assert!(zsh.contains("'*--mount=["));
+        assert!(zsh.contains("'1: :(bash zsh fish)'"));
+
+        let fish = generate(Shell::Fish, Cli::command());
+        assert!(fish.contains("-n __fish_use_subcommand -a init"));
+        assert!(fish.contains("-l dotfiles -d 'How to answer requests for hidden paths"));
+        assert!(fish.contains("-n 'not __fish_seen_subcommand_from init completions' -l port"));
+    }
+}
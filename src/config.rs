@@ -1,4 +1,70 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use anyhow::Context;
+
+use crate::{
+    access::IpRange, access_log::AccessLogFormat, completions::Shell, dotfiles::DotfilePolicy,
+    inject::InjectPosition, logging::LogFormat, otel::OtlpEndpoint, throttle::Throttle,
+    tunnel::TunnelProvider,
+};
+
 pub const DEFAULT_PORT: u16 = 3000;
+pub const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+pub const DEFAULT_CONFIG_FILE: &str = "web-dev-server.json";
+
+/// Command line of the `web-dev-server` binary. Without a subcommand it serves, taking the
+/// same arguments as `serve`.
+#[derive(Debug, clap::Parser)]
+#[command(
+    name = "web-dev-server",
+    version,
+    about = "Live reload development server for static sites",
+    long_about = None,
+    propagate_version = true,
+    args_conflicts_with_subcommands = true,
+    disable_help_subcommand = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub serve: DevServerConfig,
+}
+
+impl Cli {
+    /// The chosen subcommand, with a bare invocation treated as `serve`.
+    pub fn into_command(self) -> Command {
+        self.command
+            .unwrap_or_else(|| Command::Serve(Box::new(self.serve)))
+    }
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Run the development server (the default)
+    Serve(Box<DevServerConfig>),
+    /// Write a starter web-dev-server.json and index.html
+    Init(InitArgs),
+    /// Print a shell completion script
+    Completions { shell: Shell },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct InitArgs {
+    #[clap(default_value = "./", help = "Directory to create the files in")]
+    pub dir: PathBuf,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Overwrite files that already exist"
+    )]
+    pub force: bool,
+}
 
 #[derive(Debug, Clone, clap::Parser)]
 pub struct DevServerConfig {
@@ -8,11 +74,44 @@ pub struct DevServerConfig {
         help = "Port to run the development server on"
     )]
     pub port: u16,
+    #[clap(
+        long,
+        default_value_t = false,
+        conflicts_with = "port_range",
+        help = "Exit instead of trying another port when --port is taken"
+    )]
+    pub strict_port: bool,
+    #[clap(
+        long,
+        value_name = "FIRST-LAST",
+        help = "Ports to fall back to when --port is taken, e.g. 3000-3100"
+    )]
+    pub port_range: Option<PortRange>,
+    #[clap(
+        long,
+        default_value_t = DEFAULT_HOST,
+        help = "Address to bind; use 0.0.0.0 to make the server reachable from the network"
+    )]
+    pub host: IpAddr,
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["port", "host"],
+        help = "Listen on a Unix domain socket at PATH instead of TCP"
+    )]
+    pub uds: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "PROVIDER",
+        conflicts_with = "uds",
+        help = "Share the server on a public URL via cloudflared, ngrok, localtunnel, localhost.run, or a custom command"
+    )]
+    pub tunnel: Option<TunnelProvider>,
     #[clap(
         default_value = "./",
         help = "Base directory for the development server"
     )]
-    pub base_dir: String,
+    pub base_dir: PathBuf,
     #[clap(
         long,
         default_value_t = false,
@@ -26,4 +125,596 @@ pub struct DevServerConfig {
         help = "Disable automatically opening the default browser"
     )]
     pub no_open_browser: bool,
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "Open PATH (e.g. /docs/getting-started) instead of the site root on startup"
+    )]
+    pub open: Option<String>,
+    #[clap(
+        long = "browser",
+        value_name = "NAME|COMMAND",
+        help = "Browser to open pages in, e.g. firefox or \"chromium --incognito\" (repeatable)"
+    )]
+    pub browsers: Vec<String>,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Mirror scrolls, clicks, and form input across all connected browsers"
+    )]
+    pub sync: bool,
+    #[clap(
+        long = "no-preserve-state",
+        default_value_t = false,
+        action = clap::ArgAction::SetTrue,
+        help = "Do not restore scroll position and form values after full reloads"
+    )]
+    pub no_preserve_state: bool,
+    #[clap(
+        long,
+        value_name = "N",
+        default_value_t = 64,
+        help = "Live messages queued per browser before a slow one is caught up with a full reload"
+    )]
+    pub broadcast_capacity: usize,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Render Markdown files to HTML instead of serving raw text"
+    )]
+    pub render_markdown: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Compile .scss/.sass files to CSS on request"
+    )]
+    pub sass: bool,
+    #[clap(long, default_value = "sass", help = "Sass executable used by --sass")]
+    pub sass_command: String,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Transpile .ts/.tsx/.jsx files to JavaScript on request"
+    )]
+    pub transpile: bool,
+    #[clap(
+        long,
+        default_value = "esbuild",
+        help = "esbuild executable used by --transpile"
+    )]
+    pub esbuild_command: String,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Serve /about from about.html and report HTML changes under extensionless URLs"
+    )]
+    pub clean_urls: bool,
+    #[clap(
+        long = "index",
+        value_name = "FILES",
+        value_delimiter = ',',
+        default_value = "index.html",
+        help = "Comma-separated index files to try for directory URLs, in order"
+    )]
+    pub index_files: Vec<String>,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = DotfilePolicy::Deny,
+        help = "How to answer requests for hidden paths like .env or .git/"
+    )]
+    pub dotfiles: DotfilePolicy,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Serve and watch symlink targets outside the served directories"
+    )]
+    pub follow_symlinks: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Always read files from disk instead of keeping small assets and pages in memory"
+    )]
+    pub no_memory_cache: bool,
+    #[clap(
+        long,
+        value_name = "SIZE",
+        default_value = "10M",
+        value_parser = parse_size,
+        help = "Serve HTML and Markdown files larger than SIZE (e.g. 512K, 10M) untouched, without the live client; 0 disables the limit"
+    )]
+    pub inject_max_size: u64,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = InjectPosition::Head,
+        help = "Where to insert the live client in served pages"
+    )]
+    pub inject_position: InjectPosition,
+    #[clap(
+        long = "no-inject",
+        value_name = "GLOB",
+        help = "Serve pages whose request path matches GLOB (e.g. /embed/*) without the live client"
+    )]
+    pub no_inject: Vec<String>,
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Insert the contents of FILE into served pages after the live client"
+    )]
+    pub inject_snippet: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Expand <!--#include file=\"...\" --> directives in served HTML"
+    )]
+    pub includes: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Compress text responses with gzip/brotli/zstd when the client accepts it"
+    )]
+    pub compress: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Send Cache-Control: no-store for every file, overriding cache_control rules"
+    )]
+    pub no_cache_all: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Also accept cleartext HTTP/2 (h2c with prior knowledge) on the same port"
+    )]
+    pub http2: bool,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Let clients write files with PUT and remove them with DELETE"
+    )]
+    pub writable: bool,
+    #[clap(
+        long,
+        value_name = "PROFILE",
+        help = "Simulate a slow network: 3g, slow, or <kbps>,<latency-ms>"
+    )]
+    pub throttle: Option<Throttle>,
+    #[clap(
+        long,
+        value_name = "DIR",
+        help = "Serve mock API responses from fixture files such as DIR/GET/api/users.json"
+    )]
+    pub mocks: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "TOKEN",
+        help = "Enable the /_live control API (reload, diff, clients) for this bearer token"
+    )]
+    pub control_token: Option<String>,
+    #[clap(
+        long,
+        value_name = "USER:PASS",
+        value_parser = crate::auth::parse_credentials,
+        help = "Require HTTP basic authentication for every route except /_live/health"
+    )]
+    pub auth: Option<String>,
+    #[clap(
+        long = "allow-host",
+        value_name = "HOST",
+        help = "Accept requests whose Host header names HOST (.example.test matches subdomains, * disables the check)"
+    )]
+    pub allow_hosts: Vec<String>,
+    #[clap(
+        long = "allow-ip",
+        value_name = "IP[/PREFIX]",
+        help = "Only accept clients from loopback and these addresses or CIDR ranges (repeatable)"
+    )]
+    pub allow_ips: Vec<IpRange>,
+    #[clap(
+        long,
+        value_name = "ORIGIN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "*",
+        help = "Send CORS headers allowing ORIGIN (any origin when given as plain --cors)"
+    )]
+    pub cors: Option<String>,
+    #[clap(
+        long = "mount",
+        value_name = "PREFIX=DIR",
+        help = "Also serve DIR under the URL prefix PREFIX, e.g. /assets=../shared-assets (repeatable)"
+    )]
+    pub mounts: Vec<Mount>,
+    #[clap(
+        long,
+        value_name = "PREFIX",
+        help = "Serve the site under PREFIX (e.g. /my-repo/) like a GitHub Pages project site"
+    )]
+    pub base_path: Option<String>,
+    #[clap(
+        long = "watch",
+        value_name = "PATH",
+        help = "Also watch PATH (e.g. a src/ that builds into the served dir) and reload on changes (repeatable)"
+    )]
+    pub watch: Vec<PathBuf>,
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Explain every watcher event in the activity feed, including ignored ones"
+    )]
+    pub verbose_watch: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = LogFormat::Text,
+        help = "Log output format"
+    )]
+    pub log_format: LogFormat,
+    #[clap(
+        long,
+        value_name = "LEVEL",
+        default_value = "info",
+        help = "Most verbose log level to show: error, warn, info, debug, or trace"
+    )]
+    pub log_level: tracing::Level,
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "Append logs to PATH instead of stderr"
+    )]
+    pub log_file: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "Append every request to PATH in Apache log format, for goaccess and other log analyzers"
+    )]
+    pub access_log: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = AccessLogFormat::Combined,
+        help = "Line format for --access-log"
+    )]
+    pub access_log_format: AccessLogFormat,
+    #[clap(
+        long,
+        value_name = "URL",
+        help = "Export request, file serving, and reload timings as OpenTelemetry traces to this OTLP/HTTP collector (e.g. http://localhost:4318)"
+    )]
+    pub otel_endpoint: Option<OtlpEndpoint>,
+    #[clap(
+        long,
+        help = "JSON config file (defaults to ./web-dev-server.json when present)"
+    )]
+    pub config: Option<PathBuf>,
+    #[clap(skip)]
+    pub file: FileConfig,
+}
+
+/// A directory served under a URL prefix next to the base directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    /// URL prefix with a leading and no trailing slash, e.g. `/assets`.
+    pub prefix: String,
+    pub dir: PathBuf,
+}
+
+impl Mount {
+    pub fn new(prefix: &str, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: format!("/{}", prefix.trim_matches('/')),
+            dir: dir.into(),
+        }
+    }
+}
+
+impl FromStr for Mount {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (prefix, dir) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected PREFIX=DIR, got `{value}`"))?;
+        if dir.is_empty() {
+            return Err(format!("mount `{value}` has no directory"));
+        }
+        let mount = Self::new(prefix, dir);
+        if mount.prefix == "/" {
+            return Err(String::from(
+                "mount prefix cannot be `/`; pass the directory as the base directory instead",
+            ));
+        }
+        Ok(mount)
+    }
+}
+
+/// Inclusive range of fallback ports, e.g. `3000-3100`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortRange {
+    pub first: u16,
+    pub last: u16,
+}
+
+impl PortRange {
+    pub fn ports(self) -> std::ops::RangeInclusive<u16> {
+        self.first..=self.last
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected FIRST-LAST such as 3000-3100, got `{value}`");
+        let (first, last) = value.split_once('-').ok_or_else(invalid)?;
+        let first: u16 = first.trim().parse().map_err(|_| invalid())?;
+        let last: u16 = last.trim().parse().map_err(|_| invalid())?;
+        if first == 0 || first > last {
+            return Err(format!(
+                "port range `{value}` must start at 1 or above and not end before it starts"
+            ));
+        }
+        Ok(Self { first, last })
+    }
+}
+
+/// Settings read from the JSON config file.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Values for `%%NAME%%` placeholders in served HTML.
+    pub vars: BTreeMap<String, String>,
+    /// Redirect and rewrite rules, evaluated in order before files are served.
+    pub rules: Vec<RuleConfig>,
+    /// Injected failures for matching requests.
+    pub faults: Vec<FaultConfig>,
+    /// Sites to serve side by side; see [`crate::workspace`].
+    pub sites: Vec<SiteConfig>,
+    /// Shell commands run on server events; see [`crate::hooks`].
+    pub hooks: HooksConfig,
+    /// `Cache-Control` values for matching files; see [`crate::cache_policy`].
+    pub cache_control: Vec<CacheRuleConfig>,
+}
+
+/// One entry of the `sites` section. Every other setting comes from the command line.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteConfig {
+    /// Label in the startup summary; the directory name when absent.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Directory or archive to serve, relative to the config file.
+    pub base_dir: PathBuf,
+    /// Port for this site; one above the previous site's port when absent.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// URL prefix, as with `--base-path`.
+    #[serde(default)]
+    pub base_path: Option<String>,
+}
+
+/// One entry of the `cache_control` section; see [`crate::cache_policy`].
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheRuleConfig {
+    /// Path glob such as `/assets/*`.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// File extensions such as `["js", "css"]`, as an alternative to `path`.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Header value, e.g. `public, max-age=31536000, immutable`.
+    pub value: String,
+}
+
+/// The `hooks` section: one shell command per event, each optional.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    pub on_start: Option<String>,
+    pub on_change: Option<String>,
+    pub on_client_connect: Option<String>,
+    pub on_shutdown: Option<String>,
+}
+
+/// Parses a byte count with an optional binary `K`, `M`, or `G` suffix, e.g. `512K`.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let invalid = || format!("expected a size such as 4096, 512K, or 10M, got `{value}`");
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (digits, multiplier) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 1 << 10),
+        Some('M') => (&digits[..digits.len() - 1], 1 << 20),
+        Some('G') => (&digits[..digits.len() - 1], 1 << 30),
+        _ => (digits, 1),
+    };
+    let count: u64 = digits.trim().parse().map_err(|_| invalid())?;
+    count.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// One entry of the `rules` section; see [`crate::rewrites`].
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub from: String,
+    pub to: String,
+    /// `200` rewrites, any 3xx redirects.
+    #[serde(default = "default_rule_status")]
+    pub status: u16,
+    /// Apply even when the requested path exists as a file.
+    #[serde(default)]
+    pub force: bool,
+}
+
+fn default_rule_status() -> u16 {
+    301
+}
+
+/// One entry of the `faults` section; see [`crate::faults`].
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultConfig {
+    /// Path glob such as `/api/*`.
+    pub path: String,
+    /// Only match this method; every method when absent.
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default = "default_fault_probability")]
+    pub probability: f64,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub timeout: bool,
+    #[serde(default)]
+    pub drop: bool,
+}
+
+fn default_fault_probability() -> f64 {
+    1.0
+}
+
+impl DevServerConfig {
+    /// Loads the file named by `--config`, or `web-dev-server.json` in the current directory
+    /// if it exists, into [`DevServerConfig::file`].
+    pub fn with_file_config(mut self) -> anyhow::Result<Self> {
+        let path = match &self.config {
+            Some(path) => path.clone(),
+            None => {
+                let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+                if !default.is_file() {
+                    return Ok(self);
+                }
+                default
+            }
+        };
+
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        self.file = serde_json::from_str(&raw)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+        self.config = Some(path);
+
+        Ok(self)
+    }
+}
+
+impl Default for DevServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            strict_port: false,
+            port_range: None,
+            host: DEFAULT_HOST,
+            uds: None,
+            tunnel: None,
+            base_dir: PathBuf::from("./"),
+            diff_mode: false,
+            no_open_browser: false,
+            open: None,
+            browsers: Vec::new(),
+            sync: false,
+            no_preserve_state: false,
+            broadcast_capacity: 64,
+            render_markdown: false,
+            sass: false,
+            sass_command: String::from("sass"),
+            transpile: false,
+            esbuild_command: String::from("esbuild"),
+            clean_urls: false,
+            index_files: vec![String::from("index.html")],
+            dotfiles: DotfilePolicy::Deny,
+            follow_symlinks: false,
+            no_memory_cache: false,
+            inject_max_size: 10 * 1024 * 1024,
+            inject_position: InjectPosition::Head,
+            no_inject: Vec::new(),
+            inject_snippet: None,
+            includes: false,
+            compress: false,
+            no_cache_all: false,
+            http2: false,
+            writable: false,
+            throttle: None,
+            mocks: None,
+            control_token: None,
+            auth: None,
+            allow_hosts: Vec::new(),
+            allow_ips: Vec::new(),
+            cors: None,
+            mounts: Vec::new(),
+            base_path: None,
+            watch: Vec::new(),
+            verbose_watch: false,
+            log_format: LogFormat::Text,
+            log_level: tracing::Level::INFO,
+            log_file: None,
+            access_log: None,
+            access_log_format: AccessLogFormat::Combined,
+            otel_endpoint: None,
+            config: None,
+            file: FileConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_with_suffixes() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("10mb"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1GiB"), Ok(1 << 30));
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn bare_invocations_serve() {
+        use std::path::Path;
+
+        use clap::Parser;
+
+        let serve = |args: &[&str]| match Cli::try_parse_from(args).unwrap().into_command() {
+            Command::Serve(config) => (config.base_dir, config.port),
+            other => panic!("expected serve, got {other:?}"),
+        };
+        assert_eq!(
+            serve(&["web-dev-server", "site", "--port", "4100"]),
+            (PathBuf::from("site"), 4100)
+        );
+        assert_eq!(
+            serve(&["web-dev-server", "serve", "site", "--port", "4100"]),
+            (PathBuf::from("site"), 4100)
+        );
+        assert_eq!(
+            serve(&["web-dev-server"]),
+            (PathBuf::from("./"), DEFAULT_PORT)
+        );
+
+        let init = Cli::try_parse_from(["web-dev-server", "init", "demo", "--force"]).unwrap();
+        assert!(matches!(
+            init.into_command(),
+            Command::Init(InitArgs { dir, force: true }) if dir == Path::new("demo")
+        ));
+    }
+
+    #[test]
+    fn parses_port_ranges() {
+        assert_eq!(
+            "3000-3100".parse(),
+            Ok(PortRange {
+                first: 3000,
+                last: 3100
+            })
+        );
+        assert!("3100-3000".parse::<PortRange>().is_err());
+        assert!("0-10".parse::<PortRange>().is_err());
+        assert!("3000".parse::<PortRange>().is_err());
+    }
 }
@@ -0,0 +1,86 @@
+//! Cross-origin headers behind `--cors[=origin]`.
+//!
+//! Every response gets `Access-Control-Allow-Origin`, and preflight `OPTIONS` requests are
+//! answered directly. With a specific origin, credentials (cookies, basic auth) are allowed
+//! as well; the wildcard form cannot carry credentials per the Fetch spec.
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        Method,
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+    },
+    middleware::Next,
+    web,
+};
+
+use crate::startup::AppState;
+
+const ALLOWED_METHODS: &str = "GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS";
+const PREFLIGHT_MAX_AGE: &str = "600";
+const REQUEST_PRIVATE_NETWORK: &str = "access-control-request-private-network";
+const ALLOW_PRIVATE_NETWORK: &str = "access-control-allow-private-network";
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let origin = req
+        .app_data::<web::Data<AppState>>()
+        .and_then(|state| state.cors_origin.clone());
+    let Some(origin) = origin else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let is_preflight = req.method() == Method::OPTIONS
+        && req
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    if is_preflight {
+        let mut response = HttpResponse::NoContent().finish();
+        let headers = response.headers_mut();
+        apply_origin(headers, &origin);
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(ALLOWED_METHODS),
+        );
+        let requested_headers = req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .cloned()
+            .unwrap_or_else(|| HeaderValue::from_static("*"));
+        headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, requested_headers);
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from_static(PREFLIGHT_MAX_AGE),
+        );
+        // Chrome asks before a public page may reach a server on localhost or the LAN.
+        if req.headers().contains_key(REQUEST_PRIVATE_NETWORK) {
+            headers.insert(
+                HeaderName::from_static(ALLOW_PRIVATE_NETWORK),
+                HeaderValue::from_static("true"),
+            );
+        }
+        return Ok(req.into_response(response));
+    }
+
+    let mut response = next.call(req).await?.map_into_boxed_body();
+    apply_origin(response.headers_mut(), &origin);
+    Ok(response)
+}
+
+fn apply_origin(headers: &mut HeaderMap, origin: &str) {
+    let Ok(value) = HeaderValue::from_str(origin) else {
+        return;
+    };
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+    if origin != "*" {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+        headers.append(header::VARY, HeaderValue::from_static("origin"));
+    }
+}
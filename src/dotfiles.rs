@@ -0,0 +1,53 @@
+//! Access policy for hidden paths such as `.env` or `.git/config` (`--dotfiles`).
+
+use actix_web::HttpResponse;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DotfilePolicy {
+    /// Answer `403 Forbidden`.
+    #[default]
+    Deny,
+    /// Answer `404 Not Found`, as if the file did not exist.
+    Ignore,
+    /// Serve hidden files like any other.
+    Allow,
+}
+
+impl DotfilePolicy {
+    /// Response for a request path under this policy, or `None` when it may be served.
+    pub fn check(self, request_path: &str) -> Option<HttpResponse> {
+        if self == Self::Allow || !is_hidden(request_path) {
+            return None;
+        }
+        match self {
+            Self::Deny => Some(
+                HttpResponse::Forbidden()
+                    .body("dotfiles are not served; pass --dotfiles allow to serve them"),
+            ),
+            _ => Some(HttpResponse::NotFound().body("Not Found")),
+        }
+    }
+}
+
+/// Whether any segment of `request_path` starts with a dot. `.well-known` is exempt since it
+/// holds public metadata that sites are expected to serve.
+pub fn is_hidden(request_path: &str) -> bool {
+    request_path
+        .split('/')
+        .any(|segment| segment.starts_with('.') && !matches!(segment, "." | ".." | ".well-known"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_hidden_segments() {
+        assert!(is_hidden(".env"));
+        assert!(is_hidden("/.git/config"));
+        assert!(is_hidden("admin/.htpasswd"));
+        assert!(!is_hidden("/.well-known/security.txt"));
+        assert!(!is_hidden("/assets/app.v1.js"));
+        assert!(!is_hidden("/"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Fault injection from the `faults` section of the config file.
+//!
+//! Each rule matches a path glob (`*` spans any characters, including `/`) and, with the
+//! given probability, replaces the response with a failure:
+//!
+//! ```json
+//! { "faults": [
+//!     { "path": "/api/*", "probability": 0.1, "status": 503 },
+//!     { "path": "/api/search", "method": "POST", "timeout": true },
+//!     { "path": "/api/upload", "probability": 0.5, "drop": true }
+//! ] }
+//! ```
+//!
+//! `timeout` holds the request until the client gives up (answering `504` after
+//! [`HANG_LIMIT`]) and `drop` cuts the connection mid-response, which browsers report as a
+//! network error. The first matching rule decides; `/_live` is never affected.
+
+use std::time::Duration;
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorServiceUnavailable,
+    http::StatusCode,
+    middleware::Next,
+    web::{self, Bytes},
+};
+use futures_util::stream;
+
+use crate::{config::FaultConfig, startup::AppState};
+
+/// How long a `timeout` fault holds the request open.
+pub const HANG_LIMIT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone)]
+pub struct Fault {
+    path: String,
+    method: Option<String>,
+    probability: f64,
+    action: Action,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Status(StatusCode),
+    Timeout,
+    Drop,
+}
+
+impl Fault {
+    pub fn compile(config: &FaultConfig) -> anyhow::Result<Self> {
+        let action = match (config.status, config.timeout, config.drop) {
+            (Some(status), false, false) => Action::Status(StatusCode::from_u16(status)?),
+            (None, true, false) => Action::Timeout,
+            (None, false, true) => Action::Drop,
+            _ => anyhow::bail!(
+                "fault for {} needs exactly one of status, timeout, or drop",
+                config.path
+            ),
+        };
+        if !(0.0..=1.0).contains(&config.probability) {
+            anyhow::bail!(
+                "fault for {}: probability must be between 0 and 1",
+                config.path
+            );
+        }
+
+        Ok(Self {
+            path: config.path.clone(),
+            method: config
+                .method
+                .as_ref()
+                .map(|method| method.to_ascii_uppercase()),
+            probability: config.probability,
+            action,
+        })
+    }
+
+    fn matches(&self, method: &str, path: &str) -> bool {
+        self.method
+            .as_deref()
+            .is_none_or(|expected| expected == method)
+            && glob_match(&self.path, path)
+    }
+}
+
+/// Matches `path` against `pattern`, where `*` matches any run of characters.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let fault = req
+        .app_data::<web::Data<AppState>>()
+        .filter(|_| !req.path().starts_with("/_live/"))
+        .and_then(|state| {
+            state
+                .faults
+                .iter()
+                .find(|fault| fault.matches(req.method().as_str(), req.path()))
+                .cloned()
+        });
+    let Some(fault) = fault else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if rand::random::<f64>() >= fault.probability {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    tracing::info!(
+        action = ?fault.action,
+        method = %req.method(),
+        path = req.path(),
+        "injecting fault"
+    );
+    let response = match fault.action {
+        Action::Status(status) => HttpResponse::build(status)
+            .append_header(("Cache-Control", "no-store"))
+            .body(format!("injected fault ({status})")),
+        Action::Timeout => {
+            tokio::time::sleep(HANG_LIMIT).await;
+            HttpResponse::GatewayTimeout().body("injected timeout")
+        }
+        Action::Drop => {
+            // A body that fails before its first chunk makes the server abort the connection.
+            let body = stream::once(async {
+                Err::<Bytes, _>(ErrorServiceUnavailable("injected connection drop"))
+            });
+            HttpResponse::Ok().streaming(body)
+        }
+    };
+    Ok(req.into_response(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_any_run_of_characters() {
+        assert!(glob_match("/api/*", "/api/users/1"));
+        assert!(glob_match("/api/*.json", "/api/users.json"));
+        assert!(glob_match("*.png", "/img/logo.png"));
+        assert!(glob_match("/exact", "/exact"));
+        assert!(!glob_match("/exact", "/exact/more"));
+        assert!(!glob_match("/api/*", "/apix"));
+        assert!(!glob_match("/api/*.json", "/api/users.xml"));
+    }
+
+    #[test]
+    fn faults_need_exactly_one_action() {
+        let config = |status, timeout, drop| FaultConfig {
+            path: String::from("/api/*"),
+            method: Some(String::from("post")),
+            probability: 1.0,
+            status,
+            timeout,
+            drop,
+        };
+        assert!(Fault::compile(&config(None, false, false)).is_err());
+        assert!(Fault::compile(&config(Some(500), true, false)).is_err());
+
+        let fault = Fault::compile(&config(Some(503), false, false)).unwrap();
+        assert_eq!(
+            fault.action,
+            Action::Status(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert!(fault.matches("POST", "/api/users"));
+        assert!(!fault.matches("GET", "/api/users"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Shell commands run on server events, from the `hooks` section of the config file:
+//!
+//! ```json
+//! { "hooks": {
+//!     "on_start": "./scripts/warm-cache.sh \"$WEB_DEV_SERVER_URL\"",
+//!     "on_change": "notify-send \"changed: $WEB_DEV_SERVER_PATH\""
+//! } }
+//! ```
+//!
+//! Commands run through `sh -c` (`cmd /C` on Windows) in the server's working directory and
+//! share its terminal. Every hook gets `WEB_DEV_SERVER_EVENT`, `WEB_DEV_SERVER_URL`,
+//! `WEB_DEV_SERVER_BASE_DIR`, and `WEB_DEV_SERVER_CLIENTS` (connected live clients), plus
+//! `WEB_DEV_SERVER_PUBLIC_URL` with `--tunnel`. `on_change` adds `WEB_DEV_SERVER_PATH` (the
+//! first changed file) and `WEB_DEV_SERVER_PATHS` (all of them, one per line);
+//! `on_client_connect` adds `WEB_DEV_SERVER_CLIENT_ID`, `_IP`, `_PAGE`, and `_TRANSPORT`.
+//!
+//! Hooks never hold up the server, except `on_shutdown`, which is waited for (up to
+//! [`SHUTDOWN_TIMEOUT`]) so it can finish before the process exits.
+
+use std::{process::Stdio, time::Duration};
+
+use tokio::process::{Child, Command};
+
+use crate::config::HooksConfig;
+
+/// Longest wait for `on_shutdown` before the server exits anyway.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookEvent {
+    Start,
+    Change,
+    ClientConnect,
+    Shutdown,
+}
+
+impl HookEvent {
+    pub const ALL: [Self; 4] = [
+        Self::Start,
+        Self::Change,
+        Self::ClientConnect,
+        Self::Shutdown,
+    ];
+
+    /// Value of `WEB_DEV_SERVER_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Change => "change",
+            Self::ClientConnect => "client-connect",
+            Self::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// Configured hooks plus the variables every one of them receives.
+#[derive(Debug, Default)]
+pub struct Hooks {
+    config: HooksConfig,
+    env: Vec<(&'static str, String)>,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig, env: Vec<(&'static str, String)>) -> Self {
+        Self { config, env }
+    }
+
+    /// Command configured for `event`.
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::Start => self.config.on_start.as_deref(),
+            HookEvent::Change => self.config.on_change.as_deref(),
+            HookEvent::ClientConnect => self.config.on_client_connect.as_deref(),
+            HookEvent::Shutdown => self.config.on_shutdown.as_deref(),
+        }
+    }
+
+    /// Events that have a command.
+    pub fn events(&self) -> impl Iterator<Item = HookEvent> + '_ {
+        HookEvent::ALL
+            .into_iter()
+            .filter(|event| self.command(*event).is_some())
+    }
+
+    /// Starts the hook for `event`, if any, and logs its outcome in the background.
+    pub fn fire(&self, event: HookEvent, vars: Vec<(&'static str, String)>) {
+        if let Some(mut child) = self.spawn(event, vars) {
+            tokio::spawn(async move { log_exit(event, child.wait().await) });
+        }
+    }
+
+    /// Runs the hook for `event`, if any, and waits up to `timeout` for it to exit.
+    pub async fn run(
+        &self,
+        event: HookEvent,
+        vars: Vec<(&'static str, String)>,
+        timeout: Duration,
+    ) {
+        let Some(mut child) = self.spawn(event, vars) else {
+            return;
+        };
+        match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(status) => log_exit(event, status),
+            Err(_) => tracing::warn!(
+                hook = event.name(),
+                "hook still running after {}s, not waiting for it",
+                timeout.as_secs()
+            ),
+        }
+    }
+
+    fn spawn(&self, event: HookEvent, vars: Vec<(&'static str, String)>) -> Option<Child> {
+        let command = self.command(event)?;
+        tracing::debug!(hook = event.name(), command, "running hook");
+        let result = shell(command)
+            .env("WEB_DEV_SERVER_EVENT", event.name())
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .envs(vars)
+            .stdin(Stdio::null())
+            .spawn();
+        match result {
+            Ok(child) => Some(child),
+            Err(error) => {
+                tracing::warn!(hook = event.name(), %error, "failed to run hook");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+fn log_exit(event: HookEvent, status: std::io::Result<std::process::ExitStatus>) {
+    match status {
+        Ok(status) if status.success() => tracing::debug!(hook = event.name(), "hook finished"),
+        Ok(status) => tracing::warn!(hook = event.name(), %status, "hook failed"),
+        Err(error) => tracing::warn!(hook = event.name(), %error, "hook failed"),
+    }
+}
@@ -0,0 +1,308 @@
+//! Server-side include expansion behind `--includes`.
+//!
+//! `<!--#include file="partials/nav.html" -->` resolves relative to the including page, and
+//! `<!--#include virtual="/partials/nav.html" -->` resolves relative to the served directory
+//! holding the page: the base directory, or the mount it was served from.
+//! Includes follow the same `--dotfiles` and symlink rules as requests for the files would.
+//! The pages that pulled in each partial are remembered so the watcher can refresh them when
+//! the partial changes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Context;
+
+use crate::dotfiles::DotfilePolicy;
+
+const DIRECTIVE_START: &str = "<!--#include";
+const DIRECTIVE_END: &str = "-->";
+const MAX_DEPTH: usize = 16;
+
+/// Maps each partial to the pages that included it the last time they were served.
+#[derive(Default)]
+pub struct IncludeGraph {
+    dependents: Mutex<HashMap<PathBuf, HashSet<PathBuf>>>,
+}
+
+impl IncludeGraph {
+    pub fn record(&self, page: &Path, partials: &[PathBuf]) {
+        let mut dependents = self.dependents.lock().unwrap();
+        for pages in dependents.values_mut() {
+            pages.remove(page);
+        }
+        dependents.retain(|_, pages| !pages.is_empty());
+        for partial in partials {
+            dependents
+                .entry(partial.clone())
+                .or_default()
+                .insert(page.to_path_buf());
+        }
+    }
+
+    pub fn dependents_of(&self, partial: &Path) -> Vec<PathBuf> {
+        self.dependents
+            .lock()
+            .unwrap()
+            .get(partial)
+            .map(|pages| pages.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Which files a page may include.
+pub struct IncludeScope<'a> {
+    /// Directory `virtual` paths start from and every include must stay within.
+    pub root: &'a Path,
+    pub dotfiles: DotfilePolicy,
+    /// Directories an include may resolve into through symlinks, or `None` with
+    /// `--follow-symlinks`.
+    pub served_dirs: Option<Vec<&'a Path>>,
+}
+
+impl IncludeScope<'_> {
+    /// Rejects hidden targets and ones that resolve outside the served directories.
+    fn check(&self, target: &Path, relative: &str) -> anyhow::Result<()> {
+        let request_path = target
+            .strip_prefix(self.root)
+            .unwrap_or(target)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if self.dotfiles.check(&request_path).is_some() {
+            anyhow::bail!("include path {relative} is hidden");
+        }
+        if let Some(served_dirs) = &self.served_dirs {
+            let resolved = target
+                .canonicalize()
+                .with_context(|| format!("cannot read {}", target.display()))?;
+            if !served_dirs.iter().any(|dir| resolved.starts_with(dir)) {
+                anyhow::bail!("include path {relative} resolves outside the served directories");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands include directives in `html`, returning the expanded document and every file that
+/// was included (directly or transitively).
+pub fn expand(html: &str, page: &Path, scope: &IncludeScope<'_>) -> (String, Vec<PathBuf>) {
+    let mut included = Vec::new();
+    let mut stack = vec![page.to_path_buf()];
+    let output = expand_inner(html, page, scope, &mut included, &mut stack);
+    (output, included)
+}
+
+fn expand_inner(
+    html: &str,
+    page: &Path,
+    scope: &IncludeScope<'_>,
+    included: &mut Vec<PathBuf>,
+    stack: &mut Vec<PathBuf>,
+) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(DIRECTIVE_START) {
+        let Some(end) = rest[start..].find(DIRECTIVE_END) else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let directive = &rest[start + DIRECTIVE_START.len()..start + end];
+        rest = &rest[start + end + DIRECTIVE_END.len()..];
+
+        match include_one(directive, page, scope, included, stack) {
+            Ok(contents) => output.push_str(&contents),
+            Err(error) => {
+                tracing::warn!(page = %page.display(), "include failed: {error:#}");
+                output.push_str(&format!("<!-- include error: {error} -->"));
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn include_one(
+    directive: &str,
+    page: &Path,
+    scope: &IncludeScope<'_>,
+    included: &mut Vec<PathBuf>,
+    stack: &mut Vec<PathBuf>,
+) -> anyhow::Result<String> {
+    let root = scope.root;
+    let (target, relative) = if let Some(file) = attribute(directive, "file") {
+        let parent = page.parent().unwrap_or(root);
+        (resolve_within(root, parent, file)?, file)
+    } else if let Some(virtual_path) = attribute(directive, "virtual") {
+        (resolve_within(root, root, virtual_path)?, virtual_path)
+    } else {
+        anyhow::bail!("expected a file or virtual attribute");
+    };
+    scope.check(&target, relative)?;
+
+    if stack.contains(&target) {
+        anyhow::bail!("recursive include of {}", target.display());
+    }
+    if stack.len() > MAX_DEPTH {
+        anyhow::bail!("includes nested deeper than {MAX_DEPTH} levels");
+    }
+
+    let contents = std::fs::read_to_string(&target)
+        .map_err(|error| anyhow::anyhow!("cannot read {}: {error}", target.display()))?;
+    if !included.contains(&target) {
+        included.push(target.clone());
+    }
+
+    stack.push(target.clone());
+    let expanded = expand_inner(&contents, &target, scope, included, stack);
+    stack.pop();
+
+    Ok(expanded)
+}
+
+fn attribute<'a>(directive: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{name}=\"");
+    let start = directive.find(&needle)? + needle.len();
+    let len = directive[start..].find('"')?;
+    Some(&directive[start..start + len])
+}
+
+/// Joins `relative` onto `dir`, rejecting anything that would leave `base_dir`.
+fn resolve_within(base_dir: &Path, dir: &Path, relative: &str) -> anyhow::Result<PathBuf> {
+    let (mut target, relative) = match relative.strip_prefix('/') {
+        Some(rooted) => (base_dir.to_path_buf(), rooted),
+        None => (dir.to_path_buf(), relative),
+    };
+
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => target.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                target.pop();
+            }
+            _ => anyhow::bail!("invalid include path {relative}"),
+        }
+    }
+
+    if !target.starts_with(base_dir) {
+        anyhow::bail!("include path {relative} escapes the base directory");
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(root: &Path) -> IncludeScope<'_> {
+        IncludeScope {
+            root,
+            dotfiles: DotfilePolicy::Deny,
+            served_dirs: Some(vec![root]),
+        }
+    }
+
+    fn site(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "web_dev_server_includes_{name}_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("partials")).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn expands_nested_includes_and_tracks_them() {
+        let base = site("nested");
+        std::fs::write(
+            base.join("partials/nav.html"),
+            "<nav><!--#include file=\"item.html\" --></nav>",
+        )
+        .unwrap();
+        std::fs::write(base.join("partials/item.html"), "<a>home</a>").unwrap();
+
+        let page = base.join("index.html");
+        let (html, included) = expand(
+            "<body><!--#include virtual=\"/partials/nav.html\" --></body>",
+            &page,
+            &scope(&base),
+        );
+
+        assert_eq!(html, "<body><nav><a>home</a></nav></body>");
+        assert_eq!(
+            included,
+            vec![
+                base.join("partials/nav.html"),
+                base.join("partials/item.html")
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_paths_outside_base_dir() {
+        let base = site("escape");
+        let (html, included) = expand(
+            "<!--#include file=\"../../etc/passwd\" -->",
+            &base.join("index.html"),
+            &scope(&base),
+        );
+        assert!(html.starts_with("<!-- include error:"), "html was {html}");
+        assert!(included.is_empty());
+    }
+
+    #[test]
+    fn hidden_files_follow_the_dotfile_policy() {
+        let base = site("dotfiles");
+        std::fs::write(base.join(".env"), "SECRET=1").unwrap();
+        let page = base.join("index.html");
+        let directive = "<!--#include virtual=\"/.env\" -->";
+
+        let (html, included) = expand(directive, &page, &scope(&base));
+        assert!(html.starts_with("<!-- include error:"), "html was {html}");
+        assert!(included.is_empty());
+
+        let allow = IncludeScope {
+            dotfiles: DotfilePolicy::Allow,
+            ..scope(&base)
+        };
+        assert_eq!(expand(directive, &page, &allow).0, "SECRET=1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_leaving_the_served_dirs_are_refused() {
+        let base = site("symlink");
+        let outside = site("symlink_outside").join("secret.html");
+        std::fs::write(&outside, "<p>secret</p>").unwrap();
+        let link = base.join("partials/leak.html");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let page = base.join("index.html");
+        let directive = "<!--#include file=\"partials/leak.html\" -->";
+
+        let (html, _) = expand(directive, &page, &scope(&base));
+        assert!(html.starts_with("<!-- include error:"), "html was {html}");
+
+        let follow = IncludeScope {
+            served_dirs: None,
+            ..scope(&base)
+        };
+        assert_eq!(expand(directive, &page, &follow).0, "<p>secret</p>");
+    }
+
+    #[test]
+    fn graph_replaces_previous_dependencies() {
+        let graph = IncludeGraph::default();
+        let page = PathBuf::from("/site/index.html");
+        graph.record(&page, &[PathBuf::from("/site/a.html")]);
+        graph.record(&page, &[PathBuf::from("/site/b.html")]);
+
+        assert!(graph.dependents_of(Path::new("/site/a.html")).is_empty());
+        assert_eq!(graph.dependents_of(Path::new("/site/b.html")), vec![page]);
+    }
+}
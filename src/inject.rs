@@ -0,0 +1,166 @@
+//! Where and whether the live client is written into served pages (`--inject-position`,
+//! `--no-inject`, `--inject-snippet`).
+
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::faults::glob_match;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum InjectPosition {
+    /// Before `</head>`, or before `<body>` when the head is not closed.
+    #[default]
+    Head,
+    /// Before `</body>`, after the page's own content.
+    BodyEnd,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Injection {
+    pub position: InjectPosition,
+    /// Request path globs (`*` matches any run of characters) served without the client.
+    pub skip: Vec<String>,
+    /// Extra markup added after the client tags.
+    pub snippet: Option<String>,
+}
+
+impl Injection {
+    /// Reads the user snippet, if any, so a missing file fails at startup.
+    pub fn load(
+        position: InjectPosition,
+        skip: Vec<String>,
+        snippet: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let snippet = snippet
+            .map(|path| {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read inject snippet {}", path.display()))
+            })
+            .transpose()?;
+        Ok(Self {
+            position,
+            skip,
+            snippet,
+        })
+    }
+
+    /// Whether pages at `request_path` are served without the client.
+    pub fn skips(&self, request_path: &str) -> bool {
+        self.skip
+            .iter()
+            .any(|pattern| glob_match(pattern, request_path))
+    }
+
+    /// Inserts `tags` and the user snippet into `html` at the configured position.
+    pub fn apply(&self, html: &str, tags: &str) -> String {
+        let mut snippet = String::from(tags);
+        if let Some(extra) = &self.snippet {
+            snippet.push('\n');
+            snippet.push_str(extra.trim_end());
+        }
+
+        let mut result = String::with_capacity(html.len() + snippet.len() + 2);
+        match insertion_point(html, self.position) {
+            Some(idx) => {
+                result.push_str(&html[..idx]);
+                result.push('\n');
+                result.push_str(&snippet);
+                result.push('\n');
+                result.push_str(&html[idx..]);
+            }
+            // Fragments and unclosed bodies get the client at the end.
+            None => {
+                result.push_str(html);
+                if !result.ends_with('\n') {
+                    result.push('\n');
+                }
+                result.push_str(&snippet);
+            }
+        }
+        result
+    }
+}
+
+/// Byte offset to insert at, trying each landmark the position allows in turn, or `None` to
+/// append. Tags are matched case-insensitively since `</HEAD>` is as valid as `</head>`.
+fn insertion_point(html: &str, position: InjectPosition) -> Option<usize> {
+    let lower = html.to_ascii_lowercase();
+    let head = || lower.find("</head>").or_else(|| find_tag(&lower, "<body"));
+    let body_end = || lower.rfind("</body>").or_else(|| lower.rfind("</html>"));
+    match position {
+        InjectPosition::Head => head().or_else(body_end),
+        // A body that is never closed runs to the end of the document.
+        InjectPosition::BodyEnd => body_end(),
+    }
+}
+
+/// Start of the first `<name` opening tag, skipping longer names such as `<bodyx`.
+fn find_tag(lower: &str, name: &str) -> Option<usize> {
+    lower.match_indices(name).map(|(idx, _)| idx).find(|&idx| {
+        lower[idx + name.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c == '>' || c == '/' || c.is_ascii_whitespace())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injection(position: InjectPosition) -> Injection {
+        Injection {
+            position,
+            ..Injection::default()
+        }
+    }
+
+    #[test]
+    fn positions_fall_back_to_the_nearest_landmark() {
+        let head = injection(InjectPosition::Head);
+        let body_end = injection(InjectPosition::BodyEnd);
+
+        let page = "<html><HEAD><title>x</title></HEAD><body><p>hi</p></body></html>";
+        assert_eq!(
+            head.apply(page, "<s>"),
+            "<html><HEAD><title>x</title>\n<s>\n</HEAD><body><p>hi</p></body></html>"
+        );
+        assert_eq!(
+            body_end.apply(page, "<s>"),
+            "<html><HEAD><title>x</title></HEAD><body><p>hi</p>\n<s>\n</body></html>"
+        );
+
+        let unclosed_head = "<!doctype html><meta charset=utf-8><body class=a>hi";
+        assert_eq!(
+            head.apply(unclosed_head, "<s>"),
+            "<!doctype html><meta charset=utf-8>\n<s>\n<body class=a>hi"
+        );
+        assert_eq!(
+            body_end.apply(unclosed_head, "<s>"),
+            "<!doctype html><meta charset=utf-8><body class=a>hi\n<s>"
+        );
+
+        assert_eq!(head.apply("<p>fragment</p>", "<s>"), "<p>fragment</p>\n<s>");
+        assert_eq!(
+            head.apply("<bodyguard>hi</bodyguard>", "<s>"),
+            "<bodyguard>hi</bodyguard>\n<s>"
+        );
+    }
+
+    #[test]
+    fn snippets_follow_the_client_and_globs_skip_pages() {
+        let injection = Injection {
+            skip: vec!["/embed/*".into(), "*.amp.html".into()],
+            snippet: Some("<script src=\"/debug.js\"></script>\n".into()),
+            ..Injection::default()
+        };
+        assert_eq!(
+            injection.apply("<head></head>", "<s>"),
+            "<head>\n<s>\n<script src=\"/debug.js\"></script>\n</head>"
+        );
+        assert!(injection.skips("/embed/widget.html"));
+        assert!(injection.skips("/news/story.amp.html"));
+        assert!(!injection.skips("/index.html"));
+    }
+}
@@ -1,14 +1,62 @@
-use actix_web::{HttpRequest, HttpResponse, web};
-use actix_ws::Message;
-use futures_util::StreamExt;
+use std::{
+    convert::Infallible,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
 
-use crate::startup::AppState;
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{self, ContentEncoding},
+    web::{self, Bytes},
+};
+use actix_ws::{CloseCode, CloseReason, Message};
+use futures_util::{StreamExt, stream};
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
+
+use crate::{
+    access,
+    clients::ClientInfo,
+    hooks::HookEvent,
+    startup::{AppState, DiffResource, LiveMessage, SyncEvent, fire_hook},
+};
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How often the server pings each websocket client.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sessions that send nothing (not even a pong) for this long are closed.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Interval between SSE comments that keep idle proxies from closing the stream.
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Close-frame reason sent to every websocket when the server stops.
+const SHUTDOWN_REASON: &str = "server shutting down";
+
+/// Endpoints guarded by `--control-token`.
+pub(crate) const CONTROL_PATHS: &[&str] = &["/_live/reload", "/_live/diff", "/_live/clients"];
+
+/// Messages sent by the injected client over `/_live/ws` (or `POST /_live/events`).
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ClientMessage {
+    Sync { event: SyncEvent },
+}
 
 pub fn build_internal_scope() -> actix_web::Scope {
     web::scope("/_live")
         .route("/health", web::get().to(|| async { "OK" }))
         .route("/script.js", web::get().to(script))
         .route("/ws", web::get().to(ws_handler))
+        .route("/events", web::get().to(sse_handler))
+        .route("/events", web::post().to(sse_client_message))
+        .route("/reload", web::post().to(control_reload))
+        .route("/diff", web::post().to(control_diff))
+        .route("/clients", web::get().to(control_clients))
 }
 
 async fn script() -> HttpResponse {
@@ -23,46 +71,416 @@ async fn ws_handler(
     stream: web::Payload,
     state: web::Data<AppState>,
 ) -> actix_web::Result<HttpResponse> {
-    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
-    let mut rx = state.broadcaster.subscribe();
-
-    let mut session_for_messages = session.clone();
+    if !access::is_own_origin(&req, &state.allowed_hosts) {
+        return Ok(foreign_origin());
+    }
+    let (response, session, msg_stream) = actix_ws::handle(&req, stream)?;
+    let rx = state.broadcaster.subscribe();
+    let client_id = next_client_id();
+    let info = client_info(&req, client_id, "websocket");
+    let guard = state.clients.register(info.clone());
+    client_connected(&state, &info);
 
     actix_web::rt::spawn(async move {
-        while let Some(Ok(message)) = msg_stream.next().await {
-            match message {
-                Message::Ping(bytes) => {
-                    if session_for_messages.pong(&bytes).await.is_err() {
-                        break;
+        run_ws_session(state, client_id, session, msg_stream, rx).await;
+        drop(guard);
+    });
+
+    Ok(response)
+}
+
+/// Drives one websocket: relays broadcasts, handles client messages, and pings the client
+/// so sessions whose peer vanished (e.g. a laptop going to sleep) are closed and dropped.
+async fn run_ws_session(
+    state: web::Data<AppState>,
+    client_id: u64,
+    mut session: actix_ws::Session,
+    mut msg_stream: actix_ws::MessageStream,
+    mut rx: broadcast::Receiver<LiveMessage>,
+) {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let close_reason = loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break Some(CloseReason {
+                        code: CloseCode::Away,
+                        description: Some(String::from("heartbeat timeout")),
+                    });
+                }
+                if session.ping(b"").await.is_err() {
+                    break None;
+                }
+            }
+            message = msg_stream.next() => {
+                let Some(Ok(message)) = message else {
+                    break None;
+                };
+                last_seen = Instant::now();
+
+                match message {
+                    Message::Ping(bytes) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
                     }
+                    Message::Close(reason) => break reason,
+                    Message::Text(text) => handle_client_message(&state, client_id, &text),
+                    Message::Binary(_) | Message::Continuation(_) | Message::Pong(_) => {}
+                    Message::Nop => {}
+                }
+            }
+            batch = recv_batch(&mut rx) => {
+                let Some(batch) = batch else {
+                    break None;
+                };
+                if batch.contains(&LiveMessage::Shutdown) {
+                    break Some(CloseReason {
+                        code: CloseCode::Away,
+                        description: Some(String::from(SHUTDOWN_REASON)),
+                    });
                 }
-                Message::Close(reason) => {
-                    let _ = session_for_messages.close(reason).await;
-                    break;
+
+                let mut sent = true;
+                for event in batch.iter().filter(|event| should_deliver(event, client_id)) {
+                    match serde_json::to_string(event) {
+                        Ok(payload) => {
+                            if session.text(payload).await.is_err() {
+                                sent = false;
+                                break;
+                            }
+                        }
+                        Err(error) => {
+                            tracing::error!(%error, "failed to serialize live message")
+                        }
+                    }
+                }
+                if !sent {
+                    break None;
                 }
-                Message::Text(_)
-                | Message::Binary(_)
-                | Message::Continuation(_)
-                | Message::Pong(_) => {}
-                Message::Nop => {}
             }
         }
-    });
+    };
 
-    actix_web::rt::spawn(async move {
-        while let Ok(event) = rx.recv().await {
-            match serde_json::to_string(&event) {
-                Ok(payload) => {
-                    if session.text(payload).await.is_err() {
-                        break;
+    let _ = session.close(close_reason).await;
+}
+
+/// Server-Sent Events transport for clients that cannot open a WebSocket. The first event
+/// (`hello`) carries the client id to use when posting messages back to `/_live/events`.
+async fn sse_handler(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    if !access::is_own_origin(&req, &state.allowed_hosts) {
+        return foreign_origin();
+    }
+    let client_id = next_client_id();
+    let rx = state.broadcaster.subscribe();
+    let info = client_info(&req, client_id, "sse");
+    let guard = state.clients.register(info.clone());
+    client_connected(&state, &info);
+
+    let hello = format!(
+        "event: hello\ndata: {}\n\n",
+        serde_json::json!({ "clientId": client_id })
+    );
+    // The receiver is dropped after the shutdown notice so the stream ends and the server can
+    // finish its graceful stop.
+    let events = stream::unfold(Some(rx), move |rx| async move {
+        let mut rx = rx?;
+        loop {
+            let batch = match tokio::time::timeout(SSE_KEEPALIVE, recv_batch(&mut rx)).await {
+                Err(_) => return Some((Bytes::from_static(b": keepalive\n\n"), Some(rx))),
+                Ok(Some(batch)) => batch,
+                Ok(None) => return None,
+            };
+            let mut chunk = String::new();
+            for event in batch
+                .iter()
+                .filter(|event| should_deliver(event, client_id))
+            {
+                match serde_json::to_string(event) {
+                    Ok(payload) => chunk.push_str(&format!("data: {payload}\n\n")),
+                    Err(error) => {
+                        tracing::error!(%error, "failed to serialize live message")
                     }
                 }
-                Err(error) => {
-                    eprintln!("[web-dev-server] failed to serialize live message: {error}")
-                }
+            }
+            if !chunk.is_empty() {
+                let rx = (!batch.contains(&LiveMessage::Shutdown)).then_some(rx);
+                return Some((Bytes::from(chunk), rx));
             }
         }
     });
+    let body = stream::once(async move { Bytes::from(hello) })
+        .chain(events)
+        .map(move |chunk| {
+            // Unregisters the client once the response stream is dropped.
+            let _ = &guard;
+            Ok::<_, Infallible>(chunk)
+        });
 
-    Ok(response)
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .append_header(("Cache-Control", "no-store"))
+        // Keeps the compression middleware from buffering the stream.
+        .insert_header(ContentEncoding::Identity)
+        .streaming(body)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SseClient {
+    client: u64,
+}
+
+/// Messages from SSE clients. Requiring JSON makes cross-site pages go through a CORS
+/// preflight, so another site open in the browser cannot post sync events, and the id must
+/// belong to a connected event stream.
+async fn sse_client_message(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<SseClient>,
+    body: String,
+) -> HttpResponse {
+    if !access::is_own_origin(&req, &state.allowed_hosts) {
+        return foreign_origin();
+    }
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return HttpResponse::UnsupportedMediaType().body("expected application/json");
+    }
+    if state.clients.transport(query.client) != Some("sse") {
+        return HttpResponse::Forbidden().body("unknown event stream client");
+    }
+
+    handle_client_message(&state, query.client, &body);
+    HttpResponse::NoContent().finish()
+}
+
+/// Refusal for live-reload connections from pages of another site, which could otherwise
+/// read the mirrored form input and drive every connected tab.
+fn foreign_origin() -> HttpResponse {
+    tracing::warn!("rejected live-reload connection from a foreign Origin");
+    HttpResponse::Forbidden().body("Origin not allowed; pass --allow-host to accept it")
+}
+
+fn next_client_id() -> u64 {
+    NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct ClientQuery {
+    page: Option<String>,
+}
+
+/// Details of the connecting client; the script reports its page as `?page=`.
+fn client_info(req: &HttpRequest, id: u64, transport: &'static str) -> ClientInfo {
+    let query = web::Query::<ClientQuery>::from_query(req.query_string())
+        .map(web::Query::into_inner)
+        .unwrap_or_default();
+    ClientInfo {
+        ip: req.peer_addr().map(|addr| addr.ip()),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned),
+        page: query.page,
+        ..ClientInfo::new(id, transport)
+    }
+}
+
+fn client_connected(state: &AppState, info: &ClientInfo) {
+    let vars = vec![
+        ("WEB_DEV_SERVER_CLIENT_ID", info.id.to_string()),
+        (
+            "WEB_DEV_SERVER_CLIENT_IP",
+            info.ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        ),
+        (
+            "WEB_DEV_SERVER_CLIENT_PAGE",
+            info.page.clone().unwrap_or_default(),
+        ),
+        (
+            "WEB_DEV_SERVER_CLIENT_TRANSPORT",
+            info.transport.to_string(),
+        ),
+    ];
+    fire_hook(state, HookEvent::ClientConnect, vars);
+}
+
+/// Waits for the next broadcast and drains whatever else is already queued, dropping repeats
+/// of the same reload or diff so a burst of saves is applied once. A receiver that fell
+/// behind the channel gets a `Reload` in place of the messages it missed; `None` means the
+/// channel is closed.
+async fn recv_batch(rx: &mut broadcast::Receiver<LiveMessage>) -> Option<Vec<LiveMessage>> {
+    let first = match rx.recv().await {
+        Ok(event) => event,
+        Err(RecvError::Lagged(skipped)) => catch_up(skipped),
+        Err(RecvError::Closed) => return None,
+    };
+
+    let mut batch = vec![first];
+    loop {
+        let event = match rx.try_recv() {
+            Ok(event) => event,
+            Err(TryRecvError::Lagged(skipped)) => catch_up(skipped),
+            Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+        };
+        let repeat = matches!(event, LiveMessage::Reload | LiveMessage::Diff { .. })
+            && batch.contains(&event);
+        if !repeat {
+            batch.push(event);
+        }
+    }
+    Some(batch)
+}
+
+fn catch_up(skipped: u64) -> LiveMessage {
+    tracing::debug!(skipped, "live client fell behind, sending a full reload");
+    LiveMessage::Reload
+}
+
+/// Sync events are not echoed back to the client that produced them.
+fn should_deliver(event: &LiveMessage, client_id: u64) -> bool {
+    !matches!(event, LiveMessage::Sync { origin, .. } if *origin == client_id)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct DiffRequest {
+    path: String,
+    resource: DiffResource,
+}
+
+async fn control_reload(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+
+    broadcast(&state, LiveMessage::Reload)
+}
+
+async fn control_diff(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    body: web::Bytes,
+) -> HttpResponse {
+    // Parsed only after authorizing, so a bad body does not reveal the endpoint.
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+
+    match serde_json::from_slice::<DiffRequest>(&body) {
+        Ok(DiffRequest { path, resource }) => {
+            broadcast(&state, LiveMessage::Diff { path, resource })
+        }
+        Err(error) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": format!("invalid diff request: {error}") })),
+    }
+}
+
+async fn control_clients(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+
+    let clients = state.clients.list();
+    HttpResponse::Ok().json(serde_json::json!({
+        "count": clients.len(),
+        "clients": clients,
+    }))
+}
+
+fn broadcast(state: &AppState, message: LiveMessage) -> HttpResponse {
+    let delivered = state.broadcaster.send(message).unwrap_or(0);
+    HttpResponse::Ok().json(serde_json::json!({ "delivered": delivered }))
+}
+
+/// Control endpoints require `--control-token` and a matching `Authorization: Bearer` header.
+/// Without a configured token they behave as if they did not exist.
+fn authorize(req: &HttpRequest, state: &AppState) -> Result<(), HttpResponse> {
+    let Some(expected) = state.control_token.as_deref() else {
+        return Err(HttpResponse::NotFound().finish());
+    };
+
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        Err(HttpResponse::Unauthorized()
+            .append_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .finish())
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn handle_client_message(state: &AppState, client_id: u64, text: &str) {
+    match serde_json::from_str::<ClientMessage>(text) {
+        Ok(ClientMessage::Sync { event }) => {
+            if state.sync {
+                let _ = state.broadcaster.send(LiveMessage::Sync {
+                    origin: client_id,
+                    event,
+                });
+            }
+        }
+        Err(error) => {
+            tracing::warn!(client_id, %error, "ignoring malformed client message");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(path: &str) -> LiveMessage {
+        LiveMessage::Diff {
+            path: path.into(),
+            resource: DiffResource::Css,
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_repeats_are_dropped() {
+        let (tx, mut rx) = broadcast::channel(8);
+        for event in [
+            diff("/a.css"),
+            diff("/b.css"),
+            diff("/a.css"),
+            LiveMessage::Reload,
+        ] {
+            tx.send(event).unwrap();
+        }
+        tx.send(LiveMessage::Reload).unwrap();
+
+        let batch = recv_batch(&mut rx).await.unwrap();
+        assert_eq!(batch, [diff("/a.css"), diff("/b.css"), LiveMessage::Reload]);
+    }
+
+    #[tokio::test]
+    async fn lagging_receivers_catch_up_with_a_reload() {
+        let (tx, mut rx) = broadcast::channel(2);
+        for path in ["/a.css", "/b.css", "/c.css", "/d.css"] {
+            tx.send(diff(path)).unwrap();
+        }
+
+        let batch = recv_batch(&mut rx).await.unwrap();
+        assert_eq!(batch, [LiveMessage::Reload, diff("/c.css"), diff("/d.css")]);
+        drop(tx);
+        assert!(recv_batch(&mut rx).await.is_none());
+    }
 }
@@ -2,21 +2,53 @@
   const configKey = "__WEB_DEV_SERVER_CONFIG__";
   const config = window[configKey] || {};
   const wsPath = typeof config.wsPath === "string" ? config.wsPath : "/_live/ws";
+  const eventsPath =
+    typeof config.eventsPath === "string" ? config.eventsPath : "/_live/events";
   const diffMode = Boolean(config.diffMode);
-
-  let retryDelay = 500;
+  const syncEnabled = Boolean(config.sync);
+  const preserveState = config.preserveState !== false;
+  const cleanUrls = Boolean(config.cleanUrls);
+  const indexFiles = Array.isArray(config.indexFiles) ? config.indexFiles : ["index.html"];
+  const stateKey = "__web_dev_server_state__";
+  // Lets the server list which page each live client is on.
+  const pageQuery = `?page=${encodeURIComponent(window.location.pathname)}`;
+  const badgeId = "__web_dev_server_badge";
+  const shutdownReason = "server shutting down";
+
+  // Consecutive WebSocket attempts that never opened before falling back to SSE.
+  const maxSocketFailures = 3;
+
+  const minRetryDelay = 500;
+  const maxRetryDelay = 10000;
+
+  let retryDelay = minRetryDelay;
+  let retryTimer = null;
+  let hasConnected = false;
+  let activeSocket = null;
+  let socketFailures = 0;
+  let sseClientId = null;
+  let applyingRemote = false;
 
   const log = (...parts) => console.log("[web-dev-server]", ...parts);
   const error = (...parts) => console.error("[web-dev-server]", ...parts);
 
   function connect() {
+    if (typeof WebSocket === "undefined") {
+      connectEventSource();
+      return;
+    }
+
     const protocol = window.location.protocol === "https:" ? "wss" : "ws";
-    const target = `${protocol}://${window.location.host}${wsPath}`;
+    const target = `${protocol}://${window.location.host}${wsPath}${pageQuery}`;
     const socket = new WebSocket(target);
+    let opened = false;
 
     socket.addEventListener("open", () => {
-      log("connected");
-      retryDelay = 500;
+      opened = true;
+      socketFailures = 0;
+      retryDelay = minRetryDelay;
+      activeSocket = socket;
+      onConnected("connected");
     });
 
     socket.addEventListener("message", (event) => {
@@ -28,9 +60,22 @@
       }
     });
 
-    socket.addEventListener("close", () => {
-      retryDelay = Math.min(retryDelay * 2, 8000);
-      setTimeout(connect, retryDelay);
+    socket.addEventListener("close", (event) => {
+      if (activeSocket === socket) {
+        activeSocket = null;
+      }
+      if (event.reason === shutdownReason) {
+        showDisconnectedBadge();
+      }
+      if (!opened) {
+        socketFailures += 1;
+      }
+      if (socketFailures >= maxSocketFailures && typeof EventSource !== "undefined") {
+        log("WebSocket unavailable, falling back to Server-Sent Events");
+        connectEventSource();
+        return;
+      }
+      scheduleReconnect();
     });
 
     socket.addEventListener("error", () => {
@@ -38,6 +83,68 @@
     });
   }
 
+  function scheduleReconnect() {
+    if (retryTimer !== null) {
+      return;
+    }
+    // Exponential backoff with jitter so many tabs do not reconnect in lockstep.
+    const delay = retryDelay + Math.random() * retryDelay * 0.3;
+    retryDelay = Math.min(retryDelay * 2, maxRetryDelay);
+    retryTimer = setTimeout(() => {
+      retryTimer = null;
+      connect();
+    }, delay);
+  }
+
+  function reconnectNow() {
+    if (activeSocket || sseClientId !== null || retryTimer === null) {
+      return;
+    }
+    clearTimeout(retryTimer);
+    retryTimer = null;
+    retryDelay = minRetryDelay;
+    connect();
+  }
+
+  function onConnected(message) {
+    log(message);
+    if (hasConnected) {
+      // Changes made while disconnected were missed; reload once to resync.
+      log("reconnected, reloading to pick up missed changes");
+      hardReload(window.location.pathname);
+      return;
+    }
+    hasConnected = true;
+  }
+
+  function connectEventSource() {
+    if (typeof EventSource === "undefined") {
+      error("no live reload transport available");
+      return;
+    }
+
+    // EventSource reconnects on its own; every new stream starts with a fresh hello.
+    const source = new EventSource(`${eventsPath}${pageQuery}`);
+
+    source.addEventListener("hello", (event) => {
+      try {
+        sseClientId = JSON.parse(event.data).clientId;
+        onConnected("connected (event stream)");
+      } catch (err) {
+        error("received malformed hello", err);
+      }
+    });
+
+    source.addEventListener("message", (event) => {
+      try {
+        const message = JSON.parse(event.data);
+        handleMessage(message);
+      } catch (err) {
+        error("received malformed message", err);
+      }
+    });
+  }
+
   function handleMessage(message) {
     if (!message || typeof message.type !== "string") {
       return;
@@ -68,11 +175,217 @@
           hardReload(window.location.pathname);
         }
         break;
+      case "sync":
+        if (syncEnabled) {
+          applySyncEvent(message.event);
+        }
+        break;
+      case "shutdown":
+        showDisconnectedBadge();
+        break;
       default:
         break;
     }
   }
 
+  // Shown when the server stops; the next successful connection reloads the page, which
+  // removes it again.
+  function showDisconnectedBadge() {
+    log("server stopped, waiting for it to come back");
+    if (!document.body || document.getElementById(badgeId)) {
+      return;
+    }
+    const badge = document.createElement("div");
+    badge.id = badgeId;
+    badge.textContent = "web-dev-server disconnected";
+    badge.style.cssText = [
+      "position:fixed",
+      "right:12px",
+      "bottom:12px",
+      "z-index:2147483647",
+      "padding:6px 10px",
+      "border-radius:4px",
+      "background:#b91c1c",
+      "color:#fff",
+      "font:12px/1.4 system-ui,sans-serif",
+      "pointer-events:none",
+    ].join(";");
+    document.body.appendChild(badge);
+  }
+
+  function send(message) {
+    if (activeSocket && activeSocket.readyState === WebSocket.OPEN) {
+      activeSocket.send(JSON.stringify(message));
+    } else if (sseClientId !== null) {
+      fetch(`${eventsPath}?client=${sseClientId}`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(message),
+        keepalive: true,
+      }).catch((err) => error("failed to send message", err));
+    }
+  }
+
+  function sendSync(event) {
+    if (!applyingRemote) {
+      send({ type: "sync", event });
+    }
+  }
+
+  function installSyncListeners() {
+    let scrollQueued = false;
+    let lastClicked = null;
+
+    window.addEventListener(
+      "scroll",
+      () => {
+        if (scrollQueued) {
+          return;
+        }
+        scrollQueued = true;
+        window.requestAnimationFrame(() => {
+          scrollQueued = false;
+          const root = document.documentElement;
+          const maxX = Math.max(root.scrollWidth - window.innerWidth, 1);
+          const maxY = Math.max(root.scrollHeight - window.innerHeight, 1);
+          sendSync({
+            kind: "scroll",
+            x: window.scrollX / maxX,
+            y: window.scrollY / maxY,
+          });
+        });
+      },
+      { passive: true }
+    );
+
+    document.addEventListener(
+      "click",
+      (event) => {
+        lastClicked = event.target;
+        const selector = selectorFor(event.target);
+        if (selector) {
+          sendSync({ kind: "click", selector });
+        }
+      },
+      true
+    );
+
+    const onInput = (event) => {
+      const target = event.target;
+      const selector = selectorFor(target);
+      if (!selector || !("value" in target)) {
+        return;
+      }
+      if (target.type === "checkbox" || target.type === "radio") {
+        sendSync({ kind: "input", selector, checked: Boolean(target.checked) });
+      } else if (target.type !== "password") {
+        sendSync({ kind: "input", selector, value: String(target.value) });
+      }
+    };
+    document.addEventListener("input", onInput, true);
+    document.addEventListener("change", onInput, true);
+
+    document.addEventListener(
+      "submit",
+      (event) => {
+        // Submissions caused by a mirrored click are replayed by that click.
+        if (event.submitter && event.submitter === lastClicked) {
+          return;
+        }
+        const selector = selectorFor(event.target);
+        if (selector) {
+          sendSync({ kind: "submit", selector });
+        }
+      },
+      true
+    );
+  }
+
+  function applySyncEvent(event) {
+    if (!event || typeof event.kind !== "string") {
+      return;
+    }
+
+    applyingRemote = true;
+    try {
+      switch (event.kind) {
+        case "scroll": {
+          const root = document.documentElement;
+          const maxX = Math.max(root.scrollWidth - window.innerWidth, 0);
+          const maxY = Math.max(root.scrollHeight - window.innerHeight, 0);
+          window.scrollTo(event.x * maxX, event.y * maxY);
+          break;
+        }
+        case "click": {
+          const target = document.querySelector(event.selector);
+          if (target && typeof target.click === "function") {
+            target.click();
+          }
+          break;
+        }
+        case "input": {
+          const target = document.querySelector(event.selector);
+          if (!target) {
+            break;
+          }
+          if (typeof event.checked === "boolean") {
+            target.checked = event.checked;
+          } else if (typeof event.value === "string") {
+            target.value = event.value;
+          }
+          target.dispatchEvent(new Event("input", { bubbles: true }));
+          target.dispatchEvent(new Event("change", { bubbles: true }));
+          break;
+        }
+        case "submit": {
+          const target = document.querySelector(event.selector);
+          if (target && typeof target.requestSubmit === "function") {
+            target.requestSubmit();
+          }
+          break;
+        }
+        default:
+          break;
+      }
+    } catch (err) {
+      error("failed to apply sync event", err);
+    } finally {
+      // Scroll listeners fire asynchronously, so keep echo suppression for a frame.
+      window.requestAnimationFrame(() => {
+        applyingRemote = false;
+      });
+    }
+  }
+
+  function selectorFor(node) {
+    if (!(node instanceof Element)) {
+      return null;
+    }
+
+    const parts = [];
+    let current = node;
+    while (current && current !== document.documentElement) {
+      if (current.id) {
+        parts.unshift(`#${CSS.escape(current.id)}`);
+        break;
+      }
+      const tag = current.tagName.toLowerCase();
+      const parent = current.parentElement;
+      if (!parent) {
+        parts.unshift(tag);
+        break;
+      }
+      const siblings = Array.from(parent.children).filter(
+        (child) => child.tagName === current.tagName
+      );
+      const index = siblings.indexOf(current) + 1;
+      parts.unshift(siblings.length > 1 ? `${tag}:nth-of-type(${index})` : tag);
+      current = parent;
+    }
+
+    return parts.length > 0 ? parts.join(" > ") : null;
+  }
+
   async function applyHtmlDiff(path) {
     if (!path) {
       hardReload(window.location.pathname);
@@ -186,10 +499,78 @@
   }
 
   function hardReload(path) {
+    if (preserveState) {
+      savePageState();
+    }
     const url = cacheBustUrl(path);
     window.location.replace(url);
   }
 
+  function savePageState() {
+    try {
+      const fields = [];
+      document.querySelectorAll("input, textarea, select").forEach((field) => {
+        if (field.type === "password" || field.type === "file") {
+          return;
+        }
+        const selector = selectorFor(field);
+        if (!selector) {
+          return;
+        }
+        if (field.type === "checkbox" || field.type === "radio") {
+          fields.push({ selector, checked: Boolean(field.checked) });
+        } else {
+          fields.push({ selector, value: String(field.value) });
+        }
+      });
+
+      const snapshot = {
+        path: window.location.pathname,
+        scrollX: window.scrollX,
+        scrollY: window.scrollY,
+        fields,
+      };
+      window.sessionStorage.setItem(stateKey, JSON.stringify(snapshot));
+    } catch (err) {
+      error("failed to save page state", err);
+    }
+  }
+
+  function restorePageState() {
+    let snapshot = null;
+    try {
+      const raw = window.sessionStorage.getItem(stateKey);
+      window.sessionStorage.removeItem(stateKey);
+      snapshot = raw ? JSON.parse(raw) : null;
+    } catch (err) {
+      error("failed to read saved page state", err);
+      return;
+    }
+
+    if (!snapshot || snapshot.path !== window.location.pathname) {
+      return;
+    }
+
+    (snapshot.fields || []).forEach((entry) => {
+      const field = document.querySelector(entry.selector);
+      if (!field) {
+        return;
+      }
+      if (typeof entry.checked === "boolean") {
+        field.checked = entry.checked;
+      } else if (typeof entry.value === "string") {
+        field.value = entry.value;
+      }
+    });
+
+    const scroll = () => window.scrollTo(snapshot.scrollX, snapshot.scrollY);
+    if (document.readyState === "complete") {
+      scroll();
+    } else {
+      window.addEventListener("load", scroll, { once: true });
+    }
+  }
+
   function pathsMatch(messagePath, currentPath) {
     const normalizedMessage = normalizeHtmlPath(messagePath);
     const normalizedCurrent = normalizeHtmlPath(currentPath);
@@ -199,8 +580,12 @@
   function normalizeHtmlPath(path) {
     const url = new URL(path, window.location.origin);
     let pathname = url.pathname;
-    if (pathname.endsWith("index.html") || pathname.endsWith("index.htm")) {
-      pathname = pathname.replace(/index\.html?$/i, "");
+    const lastSlash = pathname.lastIndexOf("/");
+    if (indexFiles.includes(pathname.slice(lastSlash + 1))) {
+      pathname = pathname.slice(0, lastSlash + 1);
+    }
+    if (cleanUrls) {
+      pathname = pathname.replace(/([^/])\.html?$/i, "$1");
     }
     if (pathname.length > 1 && pathname.endsWith("/")) {
       pathname = pathname.replace(/\/+$/, "");
@@ -215,7 +600,7 @@
   }
 
   function getPreservedIds() {
-    return new Set(["__web_dev_server_config", "__web_dev_server_client"]);
+    return new Set(["__web_dev_server_config", "__web_dev_server_client", badgeId]);
   }
 
   function reactivateScripts(root) {
@@ -236,5 +621,21 @@
     });
   }
 
+  if (preserveState) {
+    restorePageState();
+  }
+
+  if (syncEnabled) {
+    installSyncListeners();
+  }
+
+  // Waking from sleep or regaining network should not wait out the backoff.
+  window.addEventListener("online", reconnectNow);
+  document.addEventListener("visibilitychange", () => {
+    if (document.visibilityState === "visible") {
+      reconnectNow();
+    }
+  });
+
   connect();
 })();
@@ -0,0 +1,136 @@
+//! Single-key shortcuts read from the terminal while the server runs.
+
+use std::io::{IsTerminal, Read, Write};
+
+use tokio::sync::mpsc;
+
+use crate::{cli, startup::ShutdownHandle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Command {
+    Reload,
+    Open,
+    Clear,
+    Quit,
+}
+
+impl Command {
+    fn from_key(key: u8) -> Option<Self> {
+        match key.to_ascii_lowercase() {
+            b'r' => Some(Self::Reload),
+            b'o' => Some(Self::Open),
+            b'c' => Some(Self::Clear),
+            b'q' => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Restores the terminal mode changed by [`listen`] when dropped.
+pub struct TerminalGuard {
+    #[cfg(unix)]
+    original: Option<libc::termios>,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(original) = self.original {
+            // SAFETY: `original` was filled in by `tcgetattr` on the same descriptor.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &original);
+            }
+        }
+    }
+}
+
+/// Starts reading keypresses when stdin is a terminal: `r` reloads every connected page, `o`
+/// opens `url` in `browsers` (see [`cli::launch_browser`]), `c` clears the screen, and `q`
+/// stops the server gracefully. Returns `None` without touching the terminal otherwise. Keep
+/// the guard alive until the server has stopped.
+pub fn listen(handle: ShutdownHandle, url: String, browsers: Vec<String>) -> Option<TerminalGuard> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return None;
+    }
+    let guard = enable_key_mode();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    // A plain thread rather than `spawn_blocking`: the read never returns on its own, and the
+    // runtime waits for its blocking pool on shutdown.
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 1];
+        while let Ok(1) = stdin.lock().read(&mut buf) {
+            if let Some(command) = Command::from_key(buf[0])
+                && tx.send(command).is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some(command) = rx.recv().await {
+            match command {
+                Command::Reload => {
+                    let delivered = handle.reload();
+                    tracing::info!(delivered, "manual reload sent");
+                }
+                Command::Open => cli::launch_browser(url.clone(), browsers.clone()),
+                Command::Clear => {
+                    print!("\x1b[2J\x1b[H");
+                    let _ = std::io::stdout().flush();
+                }
+                Command::Quit => {
+                    tracing::info!("shutting down");
+                    handle.stop(true).await;
+                    break;
+                }
+            }
+        }
+    });
+
+    Some(guard)
+}
+
+/// Turns off line buffering and echo so single keys arrive immediately. Signal keys keep
+/// working, so Ctrl+C still stops the server.
+#[cfg(unix)]
+fn enable_key_mode() -> TerminalGuard {
+    // SAFETY: `termios` is plain data and `tcgetattr` fully initializes it on success.
+    let original = unsafe {
+        let mut original = std::mem::zeroed::<libc::termios>();
+        (libc::tcgetattr(libc::STDIN_FILENO, &mut original) == 0).then_some(original)
+    };
+    if let Some(original) = original {
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: `raw` is a valid termios derived from the current settings.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw);
+        }
+    }
+    TerminalGuard { original }
+}
+
+/// Without termios, keys are delivered once Enter is pressed.
+#[cfg(not(unix))]
+fn enable_key_mode() -> TerminalGuard {
+    TerminalGuard {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_shortcut_keys() {
+        assert_eq!(Command::from_key(b'r'), Some(Command::Reload));
+        assert_eq!(Command::from_key(b'O'), Some(Command::Open));
+        assert_eq!(Command::from_key(b'c'), Some(Command::Clear));
+        assert_eq!(Command::from_key(b'q'), Some(Command::Quit));
+        assert_eq!(Command::from_key(b'\n'), None);
+    }
+}
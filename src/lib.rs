@@ -1,4 +1,37 @@
+pub mod access;
+pub mod access_log;
+pub mod archive;
+pub mod auth;
+pub mod base_path;
+pub mod cache;
+pub mod cache_policy;
+pub mod changes;
 pub mod cli;
+pub mod clients;
+pub mod compiler;
+pub mod completions;
 pub mod config;
+pub mod cors;
+pub mod dotfiles;
+pub mod faults;
+pub mod hooks;
+pub mod includes;
+pub mod inject;
 pub mod internal_scope;
+pub mod keys;
+pub mod logging;
+pub mod markdown;
+pub mod mocks;
+pub mod otel;
+pub mod plugin;
+pub mod qr;
+pub mod rewrites;
+pub mod sass;
+pub mod scaffold;
 pub mod startup;
+pub mod template;
+pub mod throttle;
+pub mod transpile;
+pub mod tunnel;
+pub mod workspace;
+pub mod writable;
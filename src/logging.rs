@@ -0,0 +1,325 @@
+//! `tracing` subscriber behind `--log-format`, `--log-level`, and `--log-file`.
+//!
+//! Text output keeps the familiar `[web-dev-server] message` shape; JSON output writes one
+//! object per line with `timestamp`, `level`, `target`, `message`, and the event's fields.
+//! Spans are only tracked when they are exported with `--otel-endpoint`; see [`crate::otel`].
+
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use anyhow::Context;
+use tracing::{
+    Event, Instrument, Level, Metadata, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+
+use crate::otel::{OtlpEndpoint, Tracer};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Installs the global subscriber. Events from other crates are only shown at `warn` and
+/// above so `--log-level debug` stays focused on the server itself. With `otel`, this
+/// crate's spans are exported there whatever the log level.
+pub fn init(
+    format: LogFormat,
+    level: Level,
+    file: Option<&Path>,
+    otel: Option<&OtlpEndpoint>,
+) -> anyhow::Result<()> {
+    let writer: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open log file {}", path.display()))?,
+        ),
+        None => Box::new(std::io::stderr()),
+    };
+
+    let subscriber = LogSubscriber {
+        format,
+        level,
+        writer: Mutex::new(writer),
+        next_span: AtomicU64::new(1),
+        tracer: otel.cloned().map(Tracer::start).transpose()?,
+    };
+    tracing::subscriber::set_global_default(subscriber).context("logging is already set up")
+}
+
+/// Logs every request at `debug` with its status and duration; `/_live/` traffic at `trace`.
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = req.method().clone();
+    let path = req.path().to_string();
+    let started = Instant::now();
+    let span = tracing::info_span!(
+        "request",
+        otel.kind = "server",
+        http.request.method = %method,
+        url.path = path,
+        http.response.status_code = tracing::field::Empty,
+        otel.status_code = tracing::field::Empty,
+    );
+    let res = next.call(req).instrument(span.clone()).await?;
+
+    let status = res.status().as_u16();
+    span.record("http.response.status_code", status);
+    if status >= 500 {
+        span.record("otel.status_code", "error");
+    }
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if path.starts_with("/_live/") {
+        tracing::trace!(%method, path, status, elapsed_ms, "request");
+    } else {
+        tracing::debug!(%method, path, status, elapsed_ms, "request");
+    }
+    Ok(res)
+}
+
+struct LogSubscriber {
+    format: LogFormat,
+    level: Level,
+    writer: Mutex<Box<dyn Write + Send>>,
+    next_span: AtomicU64,
+    tracer: Option<Tracer>,
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let ours = metadata.target().starts_with(env!("CARGO_CRATE_NAME"));
+        if metadata.is_span() {
+            return ours && self.tracer.is_some();
+        }
+        let level = if ours { self.level } else { Level::WARN };
+        metadata.level() <= &level
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        let level = self.level.max(Level::WARN);
+        // Spans are exported in full, so they must not be filtered out by the log level.
+        let level = if self.tracer.is_some() {
+            Level::TRACE
+        } else {
+            level
+        };
+        Some(level.into())
+    }
+
+    // Spans are not rendered; without a tracer they only need distinct ids.
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let id = span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed));
+        if let Some(tracer) = &self.tracer {
+            tracer.new_span(&id, attrs);
+        }
+        id
+    }
+
+    fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        if let Some(tracer) = &self.tracer {
+            tracer.record(id, values);
+        }
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let line = match self.format {
+            LogFormat::Text => {
+                let mut visitor = TextVisitor::default();
+                event.record(&mut visitor);
+                let mut line = String::from("[web-dev-server] ");
+                if *metadata.level() != Level::INFO {
+                    let _ = write!(line, "{}: ", metadata.level().as_str().to_lowercase());
+                }
+                line.push_str(&visitor.message);
+                line.push_str(&visitor.fields);
+                line
+            }
+            LogFormat::Json => {
+                let mut visitor = JsonVisitor::default();
+                event.record(&mut visitor);
+                let mut fields = visitor.0;
+                let message = fields.remove("message").unwrap_or_default();
+                // Written by hand so the fixed keys come first, in this order.
+                let mut line = format!(
+                    "{{\"timestamp\":\"{}\",\"level\":\"{}\",\"target\":{},\"message\":{message}",
+                    rfc3339(SystemTime::now()),
+                    metadata.level(),
+                    serde_json::Value::from(metadata.target()),
+                );
+                for (name, value) in fields {
+                    let _ = write!(line, ",{}:{value}", serde_json::Value::from(name));
+                }
+                line.push('}');
+                line
+            }
+        };
+
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writeln!(writer, "{line}");
+            let _ = writer.flush();
+        }
+    }
+
+    fn enter(&self, id: &span::Id) {
+        if let Some(tracer) = &self.tracer {
+            tracer.enter(id);
+        }
+    }
+
+    fn exit(&self, id: &span::Id) {
+        if let Some(tracer) = &self.tracer {
+            tracer.exit(id);
+        }
+    }
+
+    fn clone_span(&self, id: &span::Id) -> span::Id {
+        if let Some(tracer) = &self.tracer {
+            tracer.clone_span(id);
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: span::Id) -> bool {
+        self.tracer
+            .as_ref()
+            .is_some_and(|tracer| tracer.try_close(&id))
+    }
+}
+
+#[derive(Default)]
+struct TextVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for TextVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor(serde_json::Map<String, serde_json::Value>);
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{value:?}").into());
+    }
+}
+
+/// UTC timestamp with millisecond precision, e.g. `2024-05-01T12:30:00.123Z`.
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let utc = UtcDateTime::from(time);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        utc.year, utc.month, utc.day, utc.hour, utc.minute, utc.second, utc.millis
+    )
+}
+
+/// Calendar fields of a moment in UTC.
+pub(crate) struct UtcDateTime {
+    pub year: i64,
+    /// `1` for January.
+    pub month: i64,
+    pub day: i64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+    pub millis: u32,
+}
+
+impl From<SystemTime> for UtcDateTime {
+    fn from(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+        let secs = since_epoch.as_secs();
+        let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+        // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html.
+        let z = days as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3_600,
+            minute: secs_of_day / 60 % 60,
+            second: secs_of_day % 60,
+            millis: since_epoch.subsec_millis(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let leap_day = UNIX_EPOCH + Duration::from_millis(951_782_400_250 + 3_723_000);
+        assert_eq!(rfc3339(leap_day), "2000-02-29T01:02:03.250Z");
+    }
+}
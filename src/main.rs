@@ -1,15 +1,68 @@
-use clap::Parser;
-use web_dev_server::{cli, startup::Application};
+use clap::{CommandFactory, Parser};
+use web_dev_server::{
+    cli, completions,
+    config::{Cli, Command, DevServerConfig},
+    keys, logging, otel, scaffold,
+    startup::Application,
+    workspace::Workspace,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config = web_dev_server::config::DevServerConfig::parse();
+    match Cli::parse().into_command() {
+        Command::Serve(config) => serve(config.with_file_config()?).await,
+        Command::Init(args) => {
+            let files = scaffold::init(&args.dir, args.force)?;
+            cli::print_init_summary(&args.dir, &files);
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            print!("{}", completions::generate(shell, Cli::command()));
+            Ok(())
+        }
+    }
+}
+
+async fn serve(config: DevServerConfig) -> anyhow::Result<()> {
+    logging::init(
+        config.log_format,
+        config.log_level,
+        config.log_file.as_deref(),
+        config.otel_endpoint.as_ref(),
+    )?;
+    if !config.file.sites.is_empty() {
+        return run_workspace(&config).await;
+    }
+
     let app = Application::build(&config).await?;
     cli::print_startup_summary(&config, &app);
-    if !config.no_open_browser {
-        cli::launch_browser(app.primary_url());
+    let url = cli::browser_url(&config, &app);
+    if !config.no_open_browser && app.uds_path().is_none() {
+        cli::launch_browser(url.clone(), config.browsers.clone());
     }
+    let _terminal = keys::listen(app.handle(), url, config.browsers.clone());
     app.run_until_stopped().await?;
+    otel::flush();
+
+    Ok(())
+}
+
+async fn run_workspace(config: &DevServerConfig) -> anyhow::Result<()> {
+    let workspace = Workspace::build(config).await?;
+    cli::print_workspace_summary(config, &workspace);
+    let urls: Vec<String> = workspace
+        .sites()
+        .iter()
+        .map(|site| cli::browser_url(config, &site.app))
+        .collect();
+    if !config.no_open_browser {
+        for url in &urls {
+            cli::launch_browser(url.clone(), config.browsers.clone());
+        }
+    }
+    let _terminal = keys::listen(workspace.handle(), urls[0].clone(), config.browsers.clone());
+    workspace.run_until_stopped().await?;
+    otel::flush();
 
     Ok(())
 }
@@ -0,0 +1,468 @@
+//! Small Markdown-to-HTML renderer used by `--render-markdown`.
+//!
+//! Covers the subset documentation folders typically rely on: ATX headings, paragraphs,
+//! emphasis, inline code, links, images, fenced code blocks, block quotes, flat lists, pipe
+//! tables, horizontal rules, and raw HTML blocks. It is meant for previews, not spec
+//! compliance.
+
+use std::path::Path;
+
+/// Renders `source` into a standalone HTML document with a minimal default layout.
+pub fn render_document(source: &str, path: &Path) -> String {
+    let body = render(source);
+    let title = first_heading(source)
+        .or_else(|| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| String::from("Document"));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<main class=\"markdown-body\">\n{}</main>\n</body>\n</html>\n",
+        escape_html(&title),
+        DEFAULT_STYLE,
+        body
+    )
+}
+
+const DEFAULT_STYLE: &str = "body{margin:0;font:16px/1.6 system-ui,sans-serif;color:#1f2328;background:#fff}\
+.markdown-body{max-width:820px;margin:0 auto;padding:32px 24px}\
+h1,h2{border-bottom:1px solid #d0d7de;padding-bottom:.3em}\
+code{font-family:ui-monospace,monospace;background:#f6f8fa;padding:.1em .3em;border-radius:4px}\
+pre{background:#f6f8fa;padding:16px;overflow:auto;border-radius:6px}pre code{padding:0;background:none}\
+blockquote{margin:0;padding:0 1em;color:#59636e;border-left:.25em solid #d0d7de}\
+table{border-collapse:collapse}th,td{border:1px solid #d0d7de;padding:6px 13px}\
+img{max-width:100%}a{color:#0969da}";
+
+/// Renders Markdown into an HTML fragment.
+pub fn render(source: &str) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = fence_marker(trimmed) {
+            let lang = trimmed[fence.len()..].trim();
+            let mut code = String::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                code.push_str(lines[i]);
+                code.push('\n');
+                i += 1;
+            }
+            i += 1;
+            if lang.is_empty() {
+                out.push_str("<pre><code>");
+            } else {
+                out.push_str(&format!(
+                    "<pre><code class=\"language-{}\">",
+                    escape_html(lang)
+                ));
+            }
+            out.push_str(&escape_html(&code));
+            out.push_str("</code></pre>\n");
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            out.push_str(&format!(
+                "<h{level} id=\"{}\">{}</h{level}>\n",
+                slugify(text),
+                render_inline(text)
+            ));
+            i += 1;
+            continue;
+        }
+
+        if is_rule(trimmed) {
+            out.push_str("<hr>\n");
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            let mut quoted = String::new();
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                let rest = &lines[i].trim_start()[1..];
+                let inner = rest.strip_prefix(' ').unwrap_or(rest);
+                quoted.push_str(inner);
+                quoted.push('\n');
+                i += 1;
+            }
+            out.push_str("<blockquote>\n");
+            out.push_str(&render(&quoted));
+            out.push_str("</blockquote>\n");
+            continue;
+        }
+
+        if let Some(ordered) = list_item(trimmed).map(|(ordered, _)| ordered) {
+            let tag = if ordered { "ol" } else { "ul" };
+            out.push_str(&format!("<{tag}>\n"));
+            while i < lines.len() {
+                match list_item(lines[i].trim()) {
+                    Some((item_ordered, text)) if item_ordered == ordered => {
+                        out.push_str(&format!("<li>{}</li>\n", render_list_text(text)));
+                        i += 1;
+                    }
+                    _ => break,
+                }
+            }
+            out.push_str(&format!("</{tag}>\n"));
+            continue;
+        }
+
+        if trimmed.starts_with('|') && i + 1 < lines.len() && is_table_separator(lines[i + 1]) {
+            let header = table_cells(trimmed);
+            out.push_str("<table>\n<thead>\n<tr>");
+            for cell in &header {
+                out.push_str(&format!("<th>{}</th>", render_inline(cell)));
+            }
+            out.push_str("</tr>\n</thead>\n<tbody>\n");
+            i += 2;
+            while i < lines.len() && lines[i].trim().starts_with('|') {
+                out.push_str("<tr>");
+                for cell in table_cells(lines[i].trim()) {
+                    out.push_str(&format!("<td>{}</td>", render_inline(cell)));
+                }
+                out.push_str("</tr>\n");
+                i += 1;
+            }
+            out.push_str("</tbody>\n</table>\n");
+            continue;
+        }
+
+        if trimmed.starts_with('<') {
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                out.push_str(lines[i]);
+                out.push('\n');
+                i += 1;
+            }
+            continue;
+        }
+
+        let mut paragraph = Vec::new();
+        while i < lines.len() {
+            let current = lines[i].trim();
+            if current.is_empty() || starts_block(current) {
+                break;
+            }
+            paragraph.push(current);
+            i += 1;
+        }
+        out.push_str("<p>");
+        out.push_str(&render_inline(&paragraph.join("\n")));
+        out.push_str("</p>\n");
+    }
+
+    out
+}
+
+fn starts_block(line: &str) -> bool {
+    fence_marker(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || line.starts_with('>')
+        || list_item(line).is_some()
+}
+
+fn fence_marker(line: &str) -> Option<&'static str> {
+    if line.starts_with("```") {
+        Some("```")
+    } else if line.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && matches!(compact[0], '-' | '*' | '_')
+        && compact.iter().all(|c| *c == compact[0])
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return Some((false, rest));
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(text) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((true, text));
+        }
+    }
+
+    None
+}
+
+fn render_list_text(text: &str) -> String {
+    if let Some(rest) = text.strip_prefix("[ ] ") {
+        format!("<input type=\"checkbox\" disabled> {}", render_inline(rest))
+    } else if let Some(rest) = text
+        .strip_prefix("[x] ")
+        .or_else(|| text.strip_prefix("[X] "))
+    {
+        format!(
+            "<input type=\"checkbox\" checked disabled> {}",
+            render_inline(rest)
+        )
+    } else {
+        render_inline(text)
+    }
+}
+
+fn is_table_separator(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('|')
+        && trimmed.contains('-')
+        && trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> Vec<&str> {
+    line.trim_matches('|').split('|').map(str::trim).collect()
+}
+
+fn first_heading(source: &str) -> Option<String> {
+    source
+        .lines()
+        .find_map(|line| heading(line.trim()).map(|(_, text)| strip_inline(text)))
+}
+
+fn strip_inline(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '*' | '_' | '`'))
+        .collect()
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in strip_inline(text).chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c == ' ' || c == '-') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_owned()
+}
+
+/// Renders inline markup: code spans, images, links, autolinks, and emphasis.
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() => {
+                out.push_str(&escape_html(&chars[i + 1].to_string()));
+                i += 2;
+            }
+            '`' => {
+                if let Some(end) = find(&chars, i + 1, "`") {
+                    let code: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&format!("<code>{}</code>", escape_html(&code)));
+                    i = end + 1;
+                } else {
+                    out.push('`');
+                    i += 1;
+                }
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                if let Some((alt, url, next)) = link_parts(&chars, i + 1) {
+                    out.push_str(&format!(
+                        "<img src=\"{}\" alt=\"{}\">",
+                        escape_html(&url),
+                        escape_html(&alt)
+                    ));
+                    i = next;
+                } else {
+                    out.push('!');
+                    i += 1;
+                }
+            }
+            '[' => {
+                if let Some((label, url, next)) = link_parts(&chars, i) {
+                    out.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(&url),
+                        render_inline(&label)
+                    ));
+                    i = next;
+                } else {
+                    out.push('[');
+                    i += 1;
+                }
+            }
+            '<' => {
+                let end = find(&chars, i + 1, ">");
+                let inner: Option<String> = end.map(|end| chars[i + 1..end].iter().collect());
+                match (end, inner) {
+                    (Some(end), Some(url))
+                        if url.starts_with("http://") || url.starts_with("https://") =>
+                    {
+                        out.push_str(&format!("<a href=\"{0}\">{0}</a>", escape_html(&url)));
+                        i = end + 1;
+                    }
+                    (Some(end), Some(tag))
+                        if tag
+                            .chars()
+                            .next()
+                            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/') =>
+                    {
+                        out.push('<');
+                        out.push_str(&tag);
+                        out.push('>');
+                        i = end + 1;
+                    }
+                    _ => {
+                        out.push_str("&lt;");
+                        i += 1;
+                    }
+                }
+            }
+            '_' if i > 0 && chars[i - 1].is_alphanumeric() => {
+                out.push('_');
+                i += 1;
+            }
+            '*' | '_' => {
+                let double = chars.get(i + 1) == Some(&c);
+                let marker: String = if double {
+                    format!("{c}{c}")
+                } else {
+                    c.to_string()
+                };
+                let start = i + marker.len();
+                match find(&chars, start, &marker) {
+                    Some(end) if end > start && !chars[start].is_whitespace() => {
+                        let inner: String = chars[start..end].iter().collect();
+                        let tag = if double { "strong" } else { "em" };
+                        out.push_str(&format!("<{tag}>{}</{tag}>", render_inline(&inner)));
+                        i = end + marker.len();
+                    }
+                    _ => {
+                        out.push_str(&marker);
+                        i += marker.len();
+                    }
+                }
+            }
+            '\n' => {
+                out.push('\n');
+                i += 1;
+            }
+            _ => {
+                out.push_str(&escape_html(&c.to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+fn find(chars: &[char], from: usize, needle: &str) -> Option<usize> {
+    let needle: Vec<char> = needle.chars().collect();
+    (from..chars.len()).find(|&idx| chars[idx..].starts_with(&needle))
+}
+
+/// Parses `[label](url)` starting at the opening bracket and returns the label, the url, and
+/// the index just past the closing parenthesis.
+fn link_parts(chars: &[char], open: usize) -> Option<(String, String, usize)> {
+    let close = find(chars, open + 1, "]")?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = find(chars, close + 2, ")")?;
+    let label: String = chars[open + 1..close].iter().collect();
+    let target: String = chars[close + 2..end].iter().collect();
+    let url = target
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+    Some((label, url, end + 1))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_headings_with_slugs() {
+        assert_eq!(
+            render("## Getting Started"),
+            "<h2 id=\"getting-started\">Getting Started</h2>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_markup() {
+        assert_eq!(
+            render("Some **bold**, *em*, `a<b>` and [docs](guide.md)."),
+            "<p>Some <strong>bold</strong>, <em>em</em>, <code>a&lt;b&gt;</code> and <a href=\"guide.md\">docs</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_fenced_code_escaped() {
+        assert_eq!(
+            render("```rust\nlet a = 1 < 2;\n```"),
+            "<pre><code class=\"language-rust\">let a = 1 &lt; 2;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_lists_and_tables() {
+        let html = render("- one\n- two\n\n| a | b |\n|---|---|\n| 1 | 2 |\n");
+        assert!(html.contains("<ul>\n<li>one</li>\n<li>two</li>\n</ul>"));
+        assert!(html.contains("<th>a</th><th>b</th>"));
+        assert!(html.contains("<td>1</td><td>2</td>"));
+    }
+
+    #[test]
+    fn document_title_uses_first_heading() {
+        let html = render_document("# My *Docs*\n\ntext", Path::new("README.md"));
+        assert!(html.contains("<title>My Docs</title>"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Fixture-backed mock API behind `--mocks <dir>`.
+//!
+//! A request for `GET /api/users` is answered from `<dir>/GET/api/users.json` (or the exact
+//! file `<dir>/GET/api/users`, or `<dir>/GET/api/users/index.json`). Fixtures under `ANY/`
+//! match every method. An optional sibling `users.meta.json` sets the status, extra headers,
+//! and an artificial delay:
+//!
+//! ```json
+//! { "status": 201, "delay_ms": 300, "headers": { "X-Total-Count": "2" } }
+//! ```
+//!
+//! Fixtures are read on every request, so edits apply immediately; the watcher additionally
+//! reloads connected pages when anything under the mocks directory changes.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::StatusCode,
+    middleware::Next,
+    web,
+};
+use tokio::fs;
+
+use crate::startup::{AppState, sanitize_path};
+
+const META_SUFFIX: &str = ".meta.json";
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct MockMeta {
+    status: Option<u16>,
+    delay_ms: u64,
+    headers: BTreeMap<String, String>,
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let mocks_dir = req
+        .app_data::<web::Data<AppState>>()
+        .and_then(|state| state.mocks_dir.clone());
+
+    let Some(mocks_dir) = mocks_dir else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if req.path().starts_with("/_live/") {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    match find_fixture(&mocks_dir, req.method().as_str(), req.path()).await {
+        Some(fixture) => {
+            let response = respond(&fixture).await;
+            Ok(req.into_response(response))
+        }
+        None => Ok(next.call(req).await?.map_into_boxed_body()),
+    }
+}
+
+async fn find_fixture(mocks_dir: &Path, method: &str, path: &str) -> Option<PathBuf> {
+    for method_dir in [method.to_ascii_uppercase(), String::from("ANY")] {
+        let Ok(base) = sanitize_path(&mocks_dir.join(&method_dir), path) else {
+            return None;
+        };
+
+        for candidate in fixture_candidates(&base) {
+            let is_meta = candidate
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(META_SUFFIX));
+            if is_meta {
+                continue;
+            }
+            if fs::metadata(&candidate)
+                .await
+                .is_ok_and(|metadata| metadata.is_file())
+            {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+fn fixture_candidates(base: &Path) -> [PathBuf; 3] {
+    let mut with_json = base.as_os_str().to_owned();
+    with_json.push(".json");
+    [
+        base.to_path_buf(),
+        PathBuf::from(with_json),
+        base.join("index.json"),
+    ]
+}
+
+fn meta_path(fixture: &Path) -> PathBuf {
+    let stem = fixture
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    fixture.with_file_name(format!("{stem}{META_SUFFIX}"))
+}
+
+async fn respond(fixture: &Path) -> HttpResponse {
+    let meta = match fs::read_to_string(meta_path(fixture)).await {
+        Ok(raw) => match serde_json::from_str::<MockMeta>(&raw) {
+            Ok(meta) => meta,
+            Err(error) => {
+                return HttpResponse::InternalServerError().body(format!(
+                    "invalid mock metadata for {}: {error}",
+                    fixture.display()
+                ));
+            }
+        },
+        Err(_) => MockMeta::default(),
+    };
+
+    if meta.delay_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(meta.delay_ms)).await;
+    }
+
+    let body = match fs::read(fixture).await {
+        Ok(body) => body,
+        Err(error) => {
+            return HttpResponse::InternalServerError().body(format!(
+                "failed to read mock {}: {error}",
+                fixture.display()
+            ));
+        }
+    };
+
+    let status = meta
+        .status
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .unwrap_or(StatusCode::OK);
+    let mime = mime_guess::from_path(fixture).first_or(mime_guess::mime::APPLICATION_JSON);
+
+    let mut builder = HttpResponse::build(status);
+    builder
+        .content_type(mime.as_ref())
+        .append_header(("Cache-Control", "no-store"));
+    for (name, value) in &meta.headers {
+        builder.append_header((name.as_str(), value.as_str()));
+    }
+    builder.body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_file_sits_next_to_fixture() {
+        assert_eq!(
+            meta_path(Path::new("/mocks/GET/api/users.json")),
+            PathBuf::from("/mocks/GET/api/users.meta.json")
+        );
+    }
+
+    #[tokio::test]
+    async fn fixtures_resolve_by_method_with_any_fallback() {
+        let dir = std::env::temp_dir().join(format!("web_dev_server_mocks_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("GET/api")).unwrap();
+        std::fs::create_dir_all(dir.join("ANY/api")).unwrap();
+        std::fs::write(dir.join("GET/api/users.json"), "[]").unwrap();
+        std::fs::write(dir.join("GET/api/users.meta.json"), "{}").unwrap();
+        std::fs::write(dir.join("ANY/api/health.json"), "{}").unwrap();
+
+        assert_eq!(
+            find_fixture(&dir, "GET", "/api/users").await,
+            Some(dir.join("GET/api/users.json"))
+        );
+        assert_eq!(
+            find_fixture(&dir, "post", "/api/health").await,
+            Some(dir.join("ANY/api/health.json"))
+        );
+        assert_eq!(find_fixture(&dir, "GET", "/api/users.meta").await, None);
+        assert_eq!(find_fixture(&dir, "POST", "/api/users").await, None);
+    }
+}
@@ -0,0 +1,514 @@
+//! OpenTelemetry trace export behind `--otel-endpoint`.
+//!
+//! The log subscriber hands every span of this crate to a [`Tracer`], which tracks parents
+//! and timings and, once a span closes, queues it for a background thread. That thread posts
+//! batches to the collector as OTLP/HTTP JSON (`POST <endpoint>/v1/traces`), so any
+//! collector, Jaeger, or Grafana Tempo with the OTLP HTTP receiver enabled can show them.
+//!
+//! Spans follow the `tracing-opentelemetry` conventions: an `otel.kind` field sets the span
+//! kind and `otel.status_code = "error"` marks a failed span. Only plain `http://` endpoints
+//! are supported; point it at a local collector to forward elsewhere.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{Mutex, OnceLock, mpsc},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span,
+};
+
+/// How long finished spans wait before being sent, unless a batch fills up first.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Spans per request to the collector.
+const MAX_BATCH: usize = 512;
+
+/// Connect, write, and read timeout for the collector.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where spans are posted, parsed from `--otel-endpoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpEndpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl FromStr for OtlpEndpoint {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some(rest) = value.trim().strip_prefix("http://") else {
+            return Err(format!(
+                "`{value}` must be an http:// URL such as http://localhost:4318; https collectors need a local forwarder"
+            ));
+        };
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let invalid_port = || format!("invalid port in `{value}`");
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| format!("unclosed `[` in `{value}`"))?;
+                match after.strip_prefix(':') {
+                    Some(port) => (host, port.parse().map_err(|_| invalid_port())?),
+                    None => (host, 4318),
+                }
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse().map_err(|_| invalid_port())?),
+                None => (authority, 4318),
+            },
+        };
+        if host.is_empty() {
+            return Err(format!("`{value}` has no host"));
+        }
+
+        let path = path.trim_end_matches('/');
+        let path = if path.ends_with("/v1/traces") {
+            path.to_string()
+        } else {
+            format!("{path}/v1/traces")
+        };
+        Ok(Self {
+            host: host.into(),
+            port,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for OtlpEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "http://[{}]:{}{}", self.host, self.port, self.path)
+        } else {
+            write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+        }
+    }
+}
+
+enum Message {
+    Span(FinishedSpan),
+    Flush(mpsc::SyncSender<()>),
+}
+
+/// Sender of the running exporter, for [`flush`].
+static EXPORTER: OnceLock<Mutex<mpsc::Sender<Message>>> = OnceLock::new();
+
+/// Sends queued spans now and waits briefly for the collector, so spans from the last moments
+/// before exit are not lost. Does nothing without `--otel-endpoint`.
+pub fn flush() {
+    let Some(sender) = EXPORTER.get() else {
+        return;
+    };
+    let (done_tx, done_rx) = mpsc::sync_channel(1);
+    let sent = sender
+        .lock()
+        .is_ok_and(|sender| sender.send(Message::Flush(done_tx)).is_ok());
+    if sent {
+        let _ = done_rx.recv_timeout(EXPORT_TIMEOUT);
+    }
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last.
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Span bookkeeping for the subscriber in [`crate::logging`].
+pub(crate) struct Tracer {
+    spans: Mutex<HashMap<u64, OpenSpan>>,
+    exporter: mpsc::Sender<Message>,
+}
+
+struct OpenSpan {
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    name: &'static str,
+    target: &'static str,
+    start: SystemTime,
+    started: Instant,
+    attributes: Attributes,
+    /// Handles to the span that are still alive; it ends when the last one closes.
+    refs: usize,
+}
+
+struct FinishedSpan {
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    name: &'static str,
+    target: &'static str,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Attributes,
+}
+
+impl Tracer {
+    /// Starts the export thread for `endpoint`.
+    pub(crate) fn start(endpoint: OtlpEndpoint) -> anyhow::Result<Self> {
+        let (exporter, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("otel-export"))
+            .spawn(move || export_loop(&endpoint, &rx))?;
+        // Only one subscriber is ever installed, so only the first tracer is flushed.
+        let _ = EXPORTER.set(Mutex::new(exporter.clone()));
+        Ok(Self {
+            spans: Mutex::default(),
+            exporter,
+        })
+    }
+
+    pub(crate) fn new_span(&self, id: &span::Id, attrs: &span::Attributes<'_>) {
+        let parent = if attrs.is_root() {
+            None
+        } else if let Some(parent) = attrs.parent() {
+            Some(parent.into_u64())
+        } else if attrs.is_contextual() {
+            ENTERED.with(|entered| entered.borrow().last().copied())
+        } else {
+            None
+        };
+
+        let mut attributes = Attributes::default();
+        attrs.record(&mut attributes);
+        let Ok(mut spans) = self.spans.lock() else {
+            return;
+        };
+        let parent = parent.and_then(|parent| spans.get(&parent));
+        let span = OpenSpan {
+            trace_id: parent.map_or_else(random_nonzero::<u128>, |parent| parent.trace_id),
+            span_id: random_nonzero::<u64>(),
+            parent_span_id: parent.map(|parent| parent.span_id),
+            name: attrs.metadata().name(),
+            target: attrs.metadata().target(),
+            start: SystemTime::now(),
+            started: Instant::now(),
+            attributes,
+            refs: 1,
+        };
+        spans.insert(id.into_u64(), span);
+    }
+
+    pub(crate) fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        if let Ok(mut spans) = self.spans.lock()
+            && let Some(span) = spans.get_mut(&id.into_u64())
+        {
+            values.record(&mut span.attributes);
+        }
+    }
+
+    pub(crate) fn enter(&self, id: &span::Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(id.into_u64()));
+    }
+
+    pub(crate) fn exit(&self, id: &span::Id) {
+        let id = id.into_u64();
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(index) = entered.iter().rposition(|entered| *entered == id) {
+                entered.remove(index);
+            }
+        });
+    }
+
+    pub(crate) fn clone_span(&self, id: &span::Id) {
+        if let Ok(mut spans) = self.spans.lock()
+            && let Some(span) = spans.get_mut(&id.into_u64())
+        {
+            span.refs += 1;
+        }
+    }
+
+    /// Drops one handle to the span, exporting it when it was the last. Returns whether the
+    /// span is closed.
+    pub(crate) fn try_close(&self, id: &span::Id) -> bool {
+        let Ok(mut spans) = self.spans.lock() else {
+            return false;
+        };
+        let id = id.into_u64();
+        let Some(span) = spans.get_mut(&id) else {
+            return false;
+        };
+        span.refs -= 1;
+        if span.refs > 0 {
+            return false;
+        }
+        let Some(span) = spans.remove(&id) else {
+            return false;
+        };
+        drop(spans);
+
+        let _ = self.exporter.send(Message::Span(FinishedSpan {
+            trace_id: span.trace_id,
+            span_id: span.span_id,
+            parent_span_id: span.parent_span_id,
+            name: span.name,
+            target: span.target,
+            end: span.start + span.started.elapsed(),
+            start: span.start,
+            attributes: span.attributes,
+        }));
+        true
+    }
+}
+
+fn random_nonzero<T: From<u8> + PartialEq>() -> T
+where
+    rand::distr::StandardUniform: rand::distr::Distribution<T>,
+{
+    loop {
+        let value: T = rand::random();
+        if value != T::from(0) {
+            return value;
+        }
+    }
+}
+
+fn export_loop(endpoint: &OtlpEndpoint, rx: &mpsc::Receiver<Message>) {
+    let mut batch = Vec::new();
+    let mut deadline = Instant::now() + EXPORT_INTERVAL;
+    let mut failing = false;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let flush = match rx.recv_timeout(timeout) {
+            Ok(Message::Span(span)) => {
+                batch.push(span);
+                if batch.len() < MAX_BATCH {
+                    continue;
+                }
+                None
+            }
+            Ok(Message::Flush(done)) => Some(done),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+
+        if !batch.is_empty() {
+            // Failures are reported once rather than every interval while the collector is
+            // down; the spans are dropped either way.
+            match post(endpoint, &encode(&batch)) {
+                Ok(()) if failing => {
+                    failing = false;
+                    tracing::info!(%endpoint, "trace export recovered");
+                }
+                Ok(()) => {}
+                Err(error) if !failing => {
+                    failing = true;
+                    tracing::warn!(%endpoint, "trace export failed: {error:#}");
+                }
+                Err(_) => {}
+            }
+            batch.clear();
+        }
+        if let Some(done) = flush {
+            let _ = done.send(());
+        }
+        deadline = Instant::now() + EXPORT_INTERVAL;
+    }
+}
+
+fn post(endpoint: &OtlpEndpoint, body: &str) -> anyhow::Result<()> {
+    let address = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("{} did not resolve", endpoint.host))?;
+    let mut stream = TcpStream::connect_timeout(&address, EXPORT_TIMEOUT)?;
+    stream.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+    stream.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        endpoint.path,
+        endpoint.host,
+        endpoint.port,
+        body.len()
+    )?;
+
+    let mut status_line = [0; 12];
+    stream.read_exact(&mut status_line)?;
+    let status = String::from_utf8_lossy(&status_line[9..12]).into_owned();
+    if !status.starts_with('2') {
+        anyhow::bail!("collector answered {status}");
+    }
+    Ok(())
+}
+
+/// OTLP/HTTP JSON body for a batch of spans.
+fn encode(spans: &[FinishedSpan]) -> String {
+    let spans: Vec<serde_json::Value> = spans.iter().map(encode_span).collect();
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", &Value::Str(env!("CARGO_PKG_NAME").into())),
+                    attribute("service.version", &Value::Str(env!("CARGO_PKG_VERSION").into())),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_CRATE_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+    .to_string()
+}
+
+fn encode_span(span: &FinishedSpan) -> serde_json::Value {
+    let mut kind = 1; // SPAN_KIND_INTERNAL
+    let mut status = serde_json::json!({});
+    let mut attributes = vec![attribute("code.namespace", &Value::Str(span.target.into()))];
+    for (name, value) in &span.attributes.0 {
+        match (*name, value) {
+            ("otel.kind", Value::Str(value)) => {
+                kind = match value.as_str() {
+                    "server" => 2,
+                    "client" => 3,
+                    "producer" => 4,
+                    "consumer" => 5,
+                    _ => 1,
+                };
+            }
+            ("otel.status_code", Value::Str(value)) if value.eq_ignore_ascii_case("error") => {
+                status = serde_json::json!({ "code": 2 });
+            }
+            _ => attributes.push(attribute(name, value)),
+        }
+    }
+
+    let mut encoded = serde_json::json!({
+        "traceId": format!("{:032x}", span.trace_id),
+        "spanId": format!("{:016x}", span.span_id),
+        "name": span.name,
+        "kind": kind,
+        "startTimeUnixNano": unix_nanos(span.start).to_string(),
+        "endTimeUnixNano": unix_nanos(span.end).to_string(),
+        "attributes": attributes,
+        "status": status,
+    });
+    if let Some(parent) = span.parent_span_id {
+        encoded["parentSpanId"] = format!("{parent:016x}").into();
+    }
+    encoded
+}
+
+fn attribute(name: &str, value: &Value) -> serde_json::Value {
+    // 64-bit integers are strings in the JSON encoding of OTLP.
+    let value = match value {
+        Value::Str(value) => serde_json::json!({ "stringValue": value }),
+        Value::Int(value) => serde_json::json!({ "intValue": value.to_string() }),
+        Value::Float(value) => serde_json::json!({ "doubleValue": value }),
+        Value::Bool(value) => serde_json::json!({ "boolValue": value }),
+    };
+    serde_json::json!({ "key": name, "value": value })
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+/// Span fields in the order they were first recorded.
+#[derive(Debug, Default)]
+struct Attributes(Vec<(&'static str, Value)>);
+
+impl Attributes {
+    fn set(&mut self, field: &Field, value: Value) {
+        match self.0.iter_mut().find(|(name, _)| *name == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name(), value)),
+        }
+    }
+}
+
+impl Visit for Attributes {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Value::Int(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Value::Int(value.try_into().unwrap_or(i64::MAX)));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, Value::Float(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::Str(value.into()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, Value::Str(format!("{value:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_default_the_port_and_traces_path() {
+        let endpoint: OtlpEndpoint = "http://localhost".parse().unwrap();
+        assert_eq!(endpoint.to_string(), "http://localhost:4318/v1/traces");
+        let endpoint: OtlpEndpoint = "http://10.0.0.5:4320/otlp/".parse().unwrap();
+        assert_eq!(endpoint.to_string(), "http://10.0.0.5:4320/otlp/v1/traces");
+        let endpoint: OtlpEndpoint = "http://[::1]:4318/v1/traces".parse().unwrap();
+        assert_eq!(endpoint.to_string(), "http://[::1]:4318/v1/traces");
+        assert!("https://api.honeycomb.io".parse::<OtlpEndpoint>().is_err());
+        assert!("http://:4318".parse::<OtlpEndpoint>().is_err());
+    }
+
+    #[test]
+    fn spans_encode_as_otlp_json() {
+        let start = UNIX_EPOCH + Duration::from_millis(1_500);
+        let span = FinishedSpan {
+            trace_id: 0xabc,
+            span_id: 0x12,
+            parent_span_id: Some(0x34),
+            name: "request",
+            target: "web_dev_server::logging",
+            start,
+            end: start + Duration::from_millis(3),
+            attributes: Attributes(vec![
+                ("otel.kind", Value::Str("server".into())),
+                ("otel.status_code", Value::Str("error".into())),
+                ("http.response.status_code", Value::Int(500)),
+            ]),
+        };
+
+        let body: serde_json::Value = serde_json::from_str(&encode(&[span])).unwrap();
+        let encoded = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(encoded["traceId"], "00000000000000000000000000000abc");
+        assert_eq!(encoded["parentSpanId"], "0000000000000034");
+        assert_eq!(encoded["kind"], 2);
+        assert_eq!(encoded["status"]["code"], 2);
+        assert_eq!(encoded["startTimeUnixNano"], "1500000000");
+        assert_eq!(encoded["endTimeUnixNano"], "1503000000");
+        assert_eq!(encoded["attributes"][1]["key"], "http.response.status_code");
+        assert_eq!(encoded["attributes"][1]["value"]["intValue"], "500");
+    }
+}
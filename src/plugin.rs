@@ -0,0 +1,121 @@
+use std::{path::Path, sync::Arc};
+
+use actix_web::{
+    Error, HttpRequest, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web,
+};
+
+use crate::startup::AppState;
+
+/// Extension point for customizing how the dev server handles requests and file changes.
+///
+/// Every hook has a no-op default, so implementors only override what they need. Plugins run
+/// in registration order.
+pub trait Plugin: Send + Sync + 'static {
+    /// Name used in log and error messages.
+    fn name(&self) -> &str;
+
+    /// Called before a request is routed. Returning a response short-circuits the request and
+    /// skips the remaining plugins.
+    fn on_request(&self, _req: &HttpRequest) -> Option<HttpResponse> {
+        None
+    }
+
+    /// Called for every response before it is sent, e.g. to adjust headers.
+    fn on_response(&self, _req: &HttpRequest, _res: &mut HttpResponse) {}
+
+    /// Rewrites the contents of a file served from the base directory. `path` is the resolved
+    /// file on disk. HTML is transformed before the live client is injected.
+    fn transform_response(&self, _path: &Path, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        Ok(body)
+    }
+
+    /// Called for every filesystem event seen by the watcher, before reload classification.
+    fn on_fs_event(&self, _event: &notify::Event) {}
+}
+
+pub type Plugins = Arc<Vec<Arc<dyn Plugin>>>;
+
+/// Runs `on_request`/`on_response` hooks around every request.
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let plugins = match req.app_data::<web::Data<AppState>>() {
+        Some(state) if !state.plugins.is_empty() => state.plugins.clone(),
+        _ => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+
+    for plugin in plugins.iter() {
+        if let Some(response) = plugin.on_request(req.request()) {
+            return Ok(req.into_response(response));
+        }
+    }
+
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    let request = res.request().clone();
+    for plugin in plugins.iter() {
+        plugin.on_response(&request, res.response_mut());
+    }
+
+    Ok(res)
+}
+
+/// Applies every plugin's `transform_response` hook in order.
+pub fn transform(
+    plugins: &[Arc<dyn Plugin>],
+    path: &Path,
+    body: Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    plugins.iter().try_fold(body, |body, plugin| {
+        plugin
+            .transform_response(path, body)
+            .map_err(|error| error.context(format!("plugin {} failed", plugin.name())))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl Plugin for Upper {
+        fn name(&self) -> &str {
+            "upper"
+        }
+
+        fn transform_response(&self, _path: &Path, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+            Ok(body.to_ascii_uppercase())
+        }
+    }
+
+    struct Failing;
+
+    impl Plugin for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn transform_response(&self, _path: &Path, _body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("boom")
+        }
+    }
+
+    #[test]
+    fn transforms_run_in_order() {
+        let plugins: Vec<Arc<dyn Plugin>> = vec![Arc::new(Upper)];
+        let body = transform(&plugins, Path::new("a.txt"), b"hello".to_vec()).unwrap();
+        assert_eq!(body, b"HELLO");
+    }
+
+    #[test]
+    fn transform_errors_name_the_plugin() {
+        let plugins: Vec<Arc<dyn Plugin>> = vec![Arc::new(Upper), Arc::new(Failing)];
+        let error = transform(&plugins, Path::new("a.txt"), Vec::new()).unwrap_err();
+        assert!(format!("{error:#}").contains("plugin failing failed"));
+    }
+}
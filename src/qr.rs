@@ -0,0 +1,488 @@
+//! Minimal QR code encoder for the startup summary.
+//!
+//! Supports byte mode at error-correction level L for versions 1–10 (up to 271 bytes),
+//! which is plenty for a URL. The layout follows ISO/IEC 18004; see Project Nayuki's
+//! "QR Code generator library" for a readable walkthrough of the same steps.
+
+/// Per version (index 0 = version 1): EC codewords per block and the data codewords of each
+/// block, at error-correction level L.
+const LEVEL_L_BLOCKS: [(usize, &[usize]); 10] = [
+    (7, &[19]),
+    (10, &[34]),
+    (15, &[55]),
+    (20, &[80]),
+    (26, &[108]),
+    (18, &[68, 68]),
+    (20, &[78, 78]),
+    (24, &[97, 97]),
+    (30, &[116, 116]),
+    (18, &[68, 68, 69, 69]),
+];
+
+const ALIGNMENT_CENTERS: [&[usize]; 10] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+
+/// Format-information bits for level L.
+const LEVEL_L_FORMAT: u32 = 0b01;
+
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes `data`, or returns `None` if it does not fit in version 10.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let (version, codewords) = (1..=LEVEL_L_BLOCKS.len())
+            .find_map(|version| data_codewords(data, version).map(|cw| (version, cw)))?;
+        let codewords = add_error_correction(&codewords, version);
+
+        let mut qr = Builder::new(version);
+        qr.draw_function_patterns();
+        qr.draw_codewords(&codewords);
+
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                qr.apply_mask(mask);
+                qr.draw_format_bits(mask);
+                let penalty = qr.penalty();
+                qr.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or(0);
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+
+        Some(Self {
+            size: qr.size,
+            modules: qr.modules,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// Renders two module rows per text line with half-block characters and a two-module
+    /// quiet zone. Dark modules are drawn as the foreground, so print the result in dark
+    /// ink on a light background.
+    pub fn render_half_blocks(&self) -> Vec<String> {
+        const QUIET: usize = 2;
+        let span = self.size + QUIET * 2;
+        let dark =
+            |x: usize, y: usize| x >= QUIET && y >= QUIET && self.is_dark(x - QUIET, y - QUIET);
+
+        (0..span)
+            .step_by(2)
+            .map(|y| {
+                (0..span)
+                    .map(|x| match (dark(x, y), dark(x, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Builds the padded data codewords for `data` in byte mode, if it fits in `version`.
+fn data_codewords(data: &[u8], version: usize) -> Option<Vec<u8>> {
+    let capacity: usize = LEVEL_L_BLOCKS[version - 1].1.iter().sum();
+    let count_bits = if version < 10 { 8 } else { 16 };
+    if data.len() >= 1 << count_bits || 4 + count_bits + data.len() * 8 > capacity * 8 {
+        return None;
+    }
+
+    let mut bits = BitBuffer::default();
+    bits.push(0b0100, 4);
+    bits.push(data.len() as u32, count_bits);
+    for &byte in data {
+        bits.push(u32::from(byte), 8);
+    }
+    let terminator = (capacity * 8 - bits.len).min(4);
+    bits.push(0, terminator);
+    bits.push(0, (8 - bits.len % 8) % 8);
+
+    let mut codewords = bits.bytes;
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= capacity {
+            break;
+        }
+        codewords.push(pad);
+    }
+    Some(codewords)
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Splits the data into blocks, appends Reed–Solomon codewords, and interleaves the result.
+fn add_error_correction(data: &[u8], version: usize) -> Vec<u8> {
+    let (ec_len, block_lens) = LEVEL_L_BLOCKS[version - 1];
+    let divisor = reed_solomon_divisor(ec_len);
+
+    let mut blocks = Vec::with_capacity(block_lens.len());
+    let mut offset = 0;
+    for &len in block_lens {
+        let block = &data[offset..offset + len];
+        offset += len;
+        blocks.push((block, reed_solomon_remainder(block, &divisor)));
+    }
+
+    let longest = block_lens.iter().copied().max().unwrap_or(0);
+    let mut result = Vec::with_capacity(data.len() + ec_len * blocks.len());
+    for i in 0..longest {
+        result.extend(blocks.iter().filter_map(|(block, _)| block.get(i)));
+    }
+    for i in 0..ec_len {
+        result.extend(blocks.iter().map(|(_, ec)| ec[i]));
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (value, &coefficient) in result.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(coefficient, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        self.draw_finder(3, 3);
+        self.draw_finder(size - 4, 3);
+        self.draw_finder(3, size - 4);
+
+        let centers = ALIGNMENT_CENTERS[self.version - 1];
+        let last = centers.len().saturating_sub(1);
+        for (i, &x) in centers.iter().enumerate() {
+            for (j, &y) in centers.iter().enumerate() {
+                let overlaps_finder = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+                if !overlaps_finder {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+
+        // Reserve the format areas; the real bits are written once the mask is chosen.
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder(&mut self, cx: usize, cy: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                if (0..self.size as i32).contains(&x) && (0..self.size as i32).contains(&y) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, cx: usize, cy: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let (x, y) = ((cx as i32 + dx) as usize, (cy as i32 + dy) as usize);
+                self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let bit = |i: u32| (bits >> i) & 1 == 1;
+        let size = self.size;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(i as u32));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i as u32));
+        }
+
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i as u32));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i as u32));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let version = self.version as u32;
+        let mut remainder = version;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = version << 12 | remainder;
+
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size as i32;
+        let total_bits = codewords.len() * 8;
+        let mut index = 0;
+        let mut right = size - 1;
+
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    } as usize;
+                    if !self.is_function[y * self.size + x] && index < total_bits {
+                        self.modules[y * self.size + x] =
+                            (codewords[index >> 3] >> (7 - (index & 7))) & 1 == 1;
+                        index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    /// XORs the mask pattern onto data modules; applying it twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Penalty score from ISO/IEC 18004 §7.8.3; lower is easier to scan.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| self.get(j, i)).collect::<Vec<_>>(),
+                (0..size).map(|j| self.get(i, j)).collect::<Vec<_>>(),
+            ]
+        });
+        for line in lines {
+            let mut run = 1;
+            for k in 1..=line.len() {
+                if k < line.len() && line[k] == line[k - 1] {
+                    run += 1;
+                } else {
+                    if run >= 5 {
+                        penalty += run - 2;
+                    }
+                    run = 1;
+                }
+            }
+
+            const FINDER: [bool; 7] = [true, false, true, true, true, false, true];
+            for start in 0..line.len().saturating_sub(6) {
+                if line[start..start + 7] != FINDER {
+                    continue;
+                }
+                let light = |from: usize, to: usize| {
+                    (from..to).all(|k| !line.get(k).copied().unwrap_or(false))
+                };
+                if (start >= 4 && light(start - 4, start)) || light(start + 7, start + 11) {
+                    penalty += 40;
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y)
+                    && color == self.get(x, y + 1)
+                    && color == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let total = size * size;
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty += deviation.div_ceil(total).saturating_sub(1) * 10;
+
+        penalty
+    }
+}
+
+/// 15-bit format information for level L and `mask`, BCH-protected and masked.
+fn format_bits(mask: u32) -> u32 {
+    let data = LEVEL_L_FORMAT << 3 | mask;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    (data << 10 | remainder) ^ 0x5412
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reed_solomon_matches_the_reference_example() {
+        // "HELLO WORLD" at 1-M from the ISO/IEC 18004 worked example.
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            reed_solomon_remainder(&data, &reed_solomon_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn format_bits_match_the_spec_table() {
+        assert_eq!(format_bits(0), 0b111011111000100);
+        assert_eq!(format_bits(7), 0b110100101110110);
+    }
+
+    #[test]
+    fn urls_pick_the_smallest_version() {
+        let qr = QrCode::encode(b"http://192.168.1.20:3000").unwrap();
+        assert_eq!(qr.size(), 25);
+        // Finder pattern corners and the always-dark module.
+        assert!(qr.is_dark(0, 0) && qr.is_dark(24, 0) && qr.is_dark(0, 24));
+        assert!(qr.is_dark(8, 25 - 8));
+        assert!(QrCode::encode(&[b'a'; 272]).is_none());
+    }
+}
@@ -0,0 +1,284 @@
+//! Redirect and rewrite rules from the `rules` section of the config file.
+//!
+//! Rules follow Netlify's `_redirects` semantics so production behaviour can be mirrored:
+//!
+//! ```json
+//! { "rules": [
+//!     { "from": "/blog/:year/:slug", "to": "/posts/:slug", "status": 301 },
+//!     { "from": "/docs/*", "to": "https://docs.example.com/:splat", "status": 302 },
+//!     { "from": "/*", "to": "/index.html", "status": 200 }
+//! ] }
+//! ```
+//!
+//! `:name` matches one path segment and a trailing `*` matches the rest (`:splat`). Status
+//! `200` rewrites the request internally; 301/302/303/307/308 redirect. Like Netlify, a rule
+//! is skipped when the requested path exists as a file unless it sets `"force": true`. The
+//! first matching rule wins.
+
+use std::collections::HashMap;
+
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{StatusCode, Uri, header},
+    middleware::Next,
+    web,
+};
+
+use crate::{
+    config::RuleConfig,
+    startup::{AppState, locate_file},
+};
+
+/// A validated rule ready for matching.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    from: Vec<Segment>,
+    to: String,
+    status: StatusCode,
+    force: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Splat,
+}
+
+impl Rule {
+    pub fn compile(config: &RuleConfig) -> anyhow::Result<Self> {
+        let status = StatusCode::from_u16(config.status)?;
+        if !(status == StatusCode::OK || status.is_redirection()) {
+            anyhow::bail!(
+                "rule {} -> {}: status must be 200 (rewrite) or a 3xx redirect",
+                config.from,
+                config.to
+            );
+        }
+        let external = config.to.starts_with("http://") || config.to.starts_with("https://");
+        if status == StatusCode::OK && external {
+            anyhow::bail!(
+                "rule {} -> {}: rewrites cannot target another host; use a redirect status",
+                config.from,
+                config.to
+            );
+        }
+        if !external && !config.to.starts_with('/') {
+            anyhow::bail!(
+                "rule {} -> {}: destination must start with /",
+                config.from,
+                config.to
+            );
+        }
+
+        let parts: Vec<&str> = segments(&config.from).collect();
+        let mut from = Vec::with_capacity(parts.len());
+        for (index, part) in parts.iter().enumerate() {
+            let segment = match *part {
+                "*" if index + 1 == parts.len() => Segment::Splat,
+                "*" => anyhow::bail!("rule {}: `*` is only allowed at the end", config.from),
+                part => match part.strip_prefix(':') {
+                    Some(name) => Segment::Param(name.to_owned()),
+                    None => Segment::Literal(part.to_owned()),
+                },
+            };
+            from.push(segment);
+        }
+
+        Ok(Self {
+            from,
+            to: config.to.clone(),
+            status,
+            force: config.force,
+        })
+    }
+
+    /// Returns the destination for `path`, or `None` if the rule does not match.
+    fn apply(&self, path: &str) -> Option<String> {
+        let parts: Vec<&str> = segments(path).collect();
+        let mut captures = HashMap::new();
+
+        for (index, segment) in self.from.iter().enumerate() {
+            match segment {
+                Segment::Splat => {
+                    captures.insert("splat", parts.get(index..).unwrap_or_default().join("/"));
+                    return Some(substitute(&self.to, &captures));
+                }
+                Segment::Literal(literal) => {
+                    if parts.get(index) != Some(&literal.as_str()) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    captures.insert(name.as_str(), (*parts.get(index)?).to_owned());
+                }
+            }
+        }
+
+        (parts.len() == self.from.len()).then(|| substitute(&self.to, &captures))
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// Replaces `:name` tokens in `to` with captured values; unknown tokens are kept.
+fn substitute(to: &str, captures: &HashMap<&str, String>) -> String {
+    let mut output = String::with_capacity(to.len());
+    let mut rest = to;
+
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        match captures.get(&after[..len]) {
+            Some(value) if len > 0 => output.push_str(value),
+            _ => {
+                output.push(':');
+                output.push_str(&after[..len]);
+            }
+        }
+        rest = &after[len..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+pub async fn middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if state.rules.is_empty() || req.path().starts_with("/_live/") {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let path = req.path().to_owned();
+    let exists = locate_file(&state, &percent_decode(&path)).await.is_ok();
+    let matched = state
+        .rules
+        .iter()
+        .filter(|rule| rule.force || !exists)
+        .find_map(|rule| rule.apply(&path).map(|to| (rule, to)));
+    let Some((rule, to)) = matched else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let destination = match (to.contains('?'), req.query_string()) {
+        (false, query) if !query.is_empty() => format!("{to}?{query}"),
+        _ => to,
+    };
+
+    if rule.status != StatusCode::OK {
+        // Redirects leave the server, so site-relative targets need the base path back.
+        let location = match &state.base_path {
+            Some(base_path) if destination.starts_with('/') => format!("{base_path}{destination}"),
+            _ => destination,
+        };
+        let response = HttpResponse::build(rule.status)
+            .append_header((header::LOCATION, location))
+            .finish();
+        return Ok(req.into_response(response));
+    }
+
+    match destination.parse::<Uri>() {
+        Ok(uri) => rewrite_uri(&mut req, uri),
+        Err(error) => {
+            tracing::warn!(destination, %error, "invalid rewrite destination");
+        }
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Points the request at `uri` for everything further down the middleware chain.
+pub(crate) fn rewrite_uri(req: &mut ServiceRequest, uri: Uri) {
+    req.match_info_mut().get_mut().update(&uri);
+    req.head_mut().uri = uri;
+}
+
+/// Decodes `%XX` escapes the way the files route decodes its path, so `my%20page.html`
+/// counts as the existing `my page.html`.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(from: &str, to: &str, status: u16) -> Rule {
+        Rule::compile(&RuleConfig {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            status,
+            force: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn placeholders_and_splats_are_substituted() {
+        let blog = rule("/blog/:year/:slug", "/posts/:slug?year=:year", 301);
+        assert_eq!(
+            blog.apply("/blog/2024/hello").as_deref(),
+            Some("/posts/hello?year=2024")
+        );
+        assert_eq!(blog.apply("/blog/2024"), None);
+        assert_eq!(blog.apply("/blog/2024/hello/extra"), None);
+
+        let docs = rule("/docs/*", "https://docs.example.com/:splat", 302);
+        assert_eq!(
+            docs.apply("/docs/guide/intro").as_deref(),
+            Some("https://docs.example.com/guide/intro")
+        );
+        assert_eq!(
+            docs.apply("/docs").as_deref(),
+            Some("https://docs.example.com/")
+        );
+        assert_eq!(docs.apply("/documents"), None);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let compile = |from: &str, to: &str, status| {
+            Rule::compile(&RuleConfig {
+                from: from.to_owned(),
+                to: to.to_owned(),
+                status,
+                force: false,
+            })
+        };
+        assert!(compile("/a", "/b", 404).is_err());
+        assert!(compile("/a", "https://example.com", 200).is_err());
+        assert!(compile("/*/a", "/b", 301).is_err());
+        assert!(compile("/a", "b", 301).is_err());
+    }
+}
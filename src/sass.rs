@@ -0,0 +1,103 @@
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use crate::compiler::{ExternalCompiler, Language};
+
+/// Compiles `.scss`/`.sass` stylesheets on request by invoking an external Sass executable
+/// (dart-sass by default) and caches the output until the watcher reports a change. A missing
+/// `style.css` falls back to `style.scss` or `style.sass`.
+pub struct SassCompiler(ExternalCompiler);
+
+impl SassCompiler {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self(ExternalCompiler::new(
+            command,
+            Language {
+                sources: &["scss", "sass"],
+                output: "css",
+                content_type: "text/css; charset=utf-8",
+                args: |command, source| {
+                    command.arg("--no-source-map").arg(source);
+                },
+            },
+        ))
+    }
+
+    /// Drops cached output after `changed` was modified and returns the sources whose
+    /// stylesheets need refreshing. Partials (`_name.scss`) may be imported anywhere, so they
+    /// invalidate every compiled stylesheet.
+    pub fn invalidate(&self, changed: &Path) -> Vec<PathBuf> {
+        if is_partial(changed) {
+            self.0.clear()
+        } else {
+            self.0.evict(changed);
+            vec![changed.to_path_buf()]
+        }
+    }
+}
+
+impl Deref for SassCompiler {
+    type Target = ExternalCompiler;
+
+    fn deref(&self) -> &ExternalCompiler {
+        &self.0
+    }
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::compiler::Cached;
+
+    #[test]
+    fn partials_invalidate_every_cached_stylesheet() {
+        let compiler = SassCompiler::new("sass");
+        for name in ["/site/a.scss", "/site/b.scss"] {
+            compiler.cache.lock().unwrap().insert(
+                PathBuf::from(name),
+                Cached {
+                    modified: SystemTime::UNIX_EPOCH,
+                    output: String::new(),
+                },
+            );
+        }
+
+        let mut affected = compiler.invalidate(Path::new("/site/_vars.scss"));
+        affected.sort();
+        assert_eq!(
+            affected,
+            vec![PathBuf::from("/site/a.scss"), PathBuf::from("/site/b.scss")]
+        );
+        assert!(compiler.cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn regular_sources_refresh_themselves() {
+        let compiler = SassCompiler::new("sass");
+        for name in ["/site/style.sass", "/site/other.scss"] {
+            compiler.cache.lock().unwrap().insert(
+                PathBuf::from(name),
+                Cached {
+                    modified: SystemTime::UNIX_EPOCH,
+                    output: String::new(),
+                },
+            );
+        }
+
+        let affected = compiler.invalidate(Path::new("/site/style.sass"));
+        assert_eq!(affected, vec![PathBuf::from("/site/style.sass")]);
+        let cache = compiler.cache.lock().unwrap();
+        assert!(cache.contains_key(Path::new("/site/other.scss")));
+        assert!(!cache.contains_key(Path::new("/site/style.sass")));
+    }
+}
@@ -0,0 +1,108 @@
+//! The `init` subcommand: a starter config file and page for a new site.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::config::DEFAULT_CONFIG_FILE;
+
+const STARTER_CONFIG: &str = r#"{
+  "vars": {
+    "TITLE": "My site"
+  },
+  "rules": [],
+  "faults": [],
+  "cache_control": [],
+  "hooks": {}
+}
+"#;
+
+const STARTER_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>%%TITLE%%</title>
+  </head>
+  <body>
+    <h1>%%TITLE%%</h1>
+    <p>Edit index.html and this page reloads on save.</p>
+  </body>
+</html>
+"#;
+
+/// What happened to one starter file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scaffolded {
+    Written(PathBuf),
+    /// Already present and left untouched because `force` was not set.
+    Kept(PathBuf),
+}
+
+/// Writes `web-dev-server.json` and `index.html` into `dir`, creating it if needed. Existing
+/// files are kept unless `force` is set.
+pub fn init(dir: &Path, force: bool) -> anyhow::Result<Vec<Scaffolded>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+    [
+        (DEFAULT_CONFIG_FILE, STARTER_CONFIG),
+        ("index.html", STARTER_PAGE),
+    ]
+    .into_iter()
+    .map(|(name, contents)| {
+        let path = dir.join(name);
+        if path.exists() && !force {
+            return Ok(Scaffolded::Kept(path));
+        }
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(Scaffolded::Written(path))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FileConfig;
+
+    #[test]
+    fn starter_config_parses() {
+        let config: FileConfig = serde_json::from_str(STARTER_CONFIG).unwrap();
+        assert!(config.vars.contains_key("TITLE"));
+    }
+
+    #[test]
+    fn existing_files_are_kept_unless_forced() {
+        let dir =
+            std::env::temp_dir().join(format!("web_dev_server_scaffold_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), "<p>mine</p>").unwrap();
+
+        let config = dir.join(DEFAULT_CONFIG_FILE);
+        let page = dir.join("index.html");
+        assert_eq!(
+            init(&dir, false).unwrap(),
+            vec![
+                Scaffolded::Written(config.clone()),
+                Scaffolded::Kept(page.clone())
+            ]
+        );
+        assert_eq!(std::fs::read_to_string(&page).unwrap(), "<p>mine</p>");
+
+        assert_eq!(
+            init(&dir, true).unwrap(),
+            vec![
+                Scaffolded::Written(config),
+                Scaffolded::Written(page.clone())
+            ]
+        );
+        assert!(
+            std::fs::read_to_string(&page)
+                .unwrap()
+                .contains("%%TITLE%%")
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
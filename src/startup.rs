@@ -1,29 +1,62 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::{DefaultHasher, Hash, Hasher},
     io::ErrorKind,
-    net::TcpListener,
+    net::{IpAddr, SocketAddr, TcpListener},
     path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use actix_files::NamedFile;
 use actix_web::{
-    HttpRequest, HttpResponse, Result as ActixResult,
-    dev::Server,
-    error::{ErrorInternalServerError, ErrorNotFound},
-    web,
+    HttpMessage, HttpRequest, HttpResponse, Result as ActixResult,
+    dev::{Server, ServerHandle},
+    error::{ErrorForbidden, ErrorInternalServerError, ErrorNotFound},
+    http::header::{
+        self, ContentEncoding, ETag, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch,
+        LastModified,
+    },
+    middleware::{Compress, Condition, from_fn},
+    web::{self, Bytes},
 };
 use anyhow::{Context, anyhow};
 use notify::{
     RecommendedWatcher, RecursiveMode, Watcher,
     event::{EventKind, ModifyKind, RenameMode},
-    recommended_watcher,
 };
 use tokio::fs;
 use tokio::sync::{broadcast, mpsc};
 use tokio::time::{Duration, sleep};
+use tracing::{Instrument, field::Empty};
 
 use crate::{
-    config::{self, DevServerConfig},
+    access::{self, IpRange},
+    access_log::{self, AccessLog, AccessLogFormat},
+    archive::{self, Extracted},
+    auth, base_path,
+    cache::{self, AssetCache},
+    cache_policy::{self, CacheRule},
+    changes::{self, ContentHashes},
+    clients::ClientRegistry,
+    compiler::ExternalCompiler,
+    config::{CacheRuleConfig, DevServerConfig, FaultConfig, Mount, PortRange, RuleConfig},
+    cors,
+    dotfiles::DotfilePolicy,
+    faults::{self, Fault},
+    hooks::{self, HookEvent, Hooks},
+    includes::{self, IncludeGraph},
+    inject::{InjectPosition, Injection},
     internal_scope::build_internal_scope,
+    logging, markdown, mocks,
+    plugin::{self, Plugin, Plugins},
+    rewrites::{self, Rule},
+    sass::SassCompiler,
+    template,
+    throttle::{self, Throttle},
+    transpile::Transpiler,
+    tunnel::{self, Tunnel, TunnelProvider},
+    writable,
 };
 
 #[derive(Clone)]
@@ -31,9 +64,72 @@ pub struct AppState {
     pub base_dir: PathBuf,
     pub broadcaster: broadcast::Sender<LiveMessage>,
     pub diff_mode: bool,
+    pub sync: bool,
+    pub preserve_state: bool,
+    pub render_markdown: bool,
+    /// Serve `/about` from `about.html` (`--clean-urls`).
+    pub clean_urls: bool,
+    /// File names served for directory URLs, tried in order (`--index`).
+    pub index_files: Arc<Vec<String>>,
+    pub dotfiles: DotfilePolicy,
+    /// Serve files whose symlinks resolve outside the base and mount directories.
+    pub follow_symlinks: bool,
+    /// Small assets and rendered pages kept in memory, unless `--no-memory-cache`.
+    pub cache: Option<Arc<AssetCache>>,
+    /// Pages larger than this many bytes are served untouched; `0` means no limit.
+    pub inject_max_size: u64,
+    /// Where the live client goes, which pages skip it, and any user snippet.
+    pub injection: Arc<Injection>,
+    pub sass: Option<Arc<SassCompiler>>,
+    pub transpiler: Option<Arc<Transpiler>>,
+    pub includes: Option<Arc<IncludeGraph>>,
+    pub vars: Arc<BTreeMap<String, String>>,
+    pub compress: bool,
+    /// Send `Cache-Control: no-store` for every file (`--no-cache-all`).
+    pub no_cache_all: bool,
+    /// Compiled `Cache-Control` rules from the config file.
+    pub cache_rules: Arc<Vec<CacheRule>>,
+    /// Accept HTTP/2 with prior knowledge alongside HTTP/1.1 (`--http2`).
+    pub http2: bool,
+    /// Accept `PUT` and `DELETE` for files (`--writable`).
+    pub writable: bool,
+    pub throttle: Option<Throttle>,
+    pub mocks_dir: Option<PathBuf>,
+    pub control_token: Option<String>,
+    /// Expected `Authorization: Basic` token when `--auth` is set.
+    pub basic_auth: Option<String>,
+    /// Hostnames accepted in the `Host` header besides localhost and IP literals.
+    pub allowed_hosts: Arc<Vec<String>>,
+    /// When non-empty, the only non-loopback clients that may connect.
+    pub allowed_ips: Arc<Vec<IpRange>>,
+    /// Value for `Access-Control-Allow-Origin` when `--cors` is set.
+    pub cors_origin: Option<String>,
+    /// Compiled redirect and rewrite rules from the config file.
+    pub rules: Arc<Vec<Rule>>,
+    /// Compiled fault injection rules from the config file.
+    pub faults: Arc<Vec<Fault>>,
+    /// URL prefix the whole site is served under, e.g. `/my-repo` (no trailing slash).
+    pub base_path: Option<String>,
+    /// Extra directories served under URL prefixes, longest prefix first.
+    pub mounts: Arc<Vec<Mount>>,
+    /// Extra paths passed with `--watch`; changes there always reload.
+    pub watch_paths: Arc<Vec<PathBuf>>,
+    /// Unpacked archive served as the base directory; it is unpacked again when it changes.
+    pub archive: Option<Arc<Extracted>>,
+    /// Log the event kind, reason, and delivery count for every watcher event.
+    pub verbose_watch: bool,
+    /// Connected live-reload sessions, for `GET /_live/clients`.
+    pub clients: Arc<ClientRegistry>,
+    /// Shell commands from the config file's `hooks` section.
+    pub hooks: Arc<Hooks>,
+    /// Apache-format request log behind `--access-log`.
+    pub access_log: Option<Arc<AccessLog>>,
+    /// Content of files as last served or seen, to drop saves that changed nothing.
+    pub hashes: Arc<ContentHashes>,
+    pub plugins: Plugins,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum LiveMessage {
     Reload,
@@ -41,9 +137,38 @@ pub enum LiveMessage {
         path: String,
         resource: DiffResource,
     },
+    Sync {
+        origin: u64,
+        event: SyncEvent,
+    },
+    /// The server is stopping; clients close their connection and wait for it to return.
+    Shutdown,
+}
+
+/// Browser interaction mirrored between clients when `--sync` is enabled.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SyncEvent {
+    Scroll {
+        x: f64,
+        y: f64,
+    },
+    Click {
+        selector: String,
+    },
+    Input {
+        selector: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checked: Option<bool>,
+    },
+    Submit {
+        selector: String,
+    },
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DiffResource {
     Html,
@@ -52,32 +177,556 @@ pub enum DiffResource {
 
 pub struct Application {
     server: Server,
-    port: u16,
-    _watcher: RecommendedWatcher,
+    address: SocketAddr,
+    uds: Option<PathBuf>,
+    tunnel: Option<Tunnel>,
+    watcher: RecommendedWatcher,
     state: AppState,
 }
 
+/// Stops a running [`Application`], telling connected browsers before the server goes away.
+/// Handles collected into one (see the [`FromIterator`] impl) control several servers.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    servers: Vec<(
+        ServerHandle,
+        broadcast::Sender<LiveMessage>,
+        Arc<ClientRegistry>,
+    )>,
+}
+
+impl ShutdownHandle {
+    /// Notifies live clients, then stops the server. A graceful stop waits for in-flight
+    /// requests to finish.
+    pub async fn stop(&self, graceful: bool) {
+        for (_, broadcaster, clients) in &self.servers {
+            // Counted first, since the `on_shutdown` hook runs after every session closed.
+            clients.record_shutdown();
+            let _ = broadcaster.send(LiveMessage::Shutdown);
+        }
+        futures_util::future::join_all(
+            self.servers
+                .iter()
+                .map(|(server, _, _)| server.stop(graceful)),
+        )
+        .await;
+    }
+
+    /// Asks every connected page to reload, returning how many sessions received it.
+    pub fn reload(&self) -> usize {
+        self.servers
+            .iter()
+            .map(|(_, broadcaster, _)| broadcaster.send(LiveMessage::Reload).unwrap_or(0))
+            .sum()
+    }
+}
+
+impl FromIterator<ShutdownHandle> for ShutdownHandle {
+    fn from_iter<I: IntoIterator<Item = ShutdownHandle>>(handles: I) -> Self {
+        Self {
+            servers: handles
+                .into_iter()
+                .flat_map(|handle| handle.servers)
+                .collect(),
+        }
+    }
+}
+
+/// Configures an [`Application`] without going through command-line parsing.
+#[derive(Clone, Default)]
+pub struct ApplicationBuilder {
+    config: DevServerConfig,
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl ApplicationBuilder {
+    /// Port to bind. `0` picks any free port; see [`Application::address`].
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    /// Fails to build instead of moving to another port when the requested one is taken.
+    pub fn strict_port(mut self, enabled: bool) -> Self {
+        self.config.strict_port = enabled;
+        self
+    }
+
+    /// Ports tried, in order, when the requested one is taken. Without a range the server
+    /// counts up from the requested port.
+    pub fn port_range(mut self, first: u16, last: u16) -> Self {
+        self.config.port_range = Some(PortRange { first, last });
+        self
+    }
+
+    /// Address to bind, e.g. `0.0.0.0` to listen on every interface.
+    pub fn host(mut self, host: IpAddr) -> Self {
+        self.config.host = host;
+        self
+    }
+
+    /// Shares the server on a public URL through an external tunnel client; see
+    /// [`Application::tunnel_url`].
+    pub fn tunnel(mut self, provider: TunnelProvider) -> Self {
+        self.config.tunnel = Some(provider);
+        self
+    }
+
+    /// Listens on a Unix domain socket at `path` instead of TCP; `port` and `host` are ignored.
+    pub fn uds(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.uds = Some(path.into());
+        self
+    }
+
+    pub fn base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+        self.config.base_dir = base_dir.into();
+        self
+    }
+
+    pub fn diff_mode(mut self, enabled: bool) -> Self {
+        self.config.diff_mode = enabled;
+        self
+    }
+
+    pub fn sync(mut self, enabled: bool) -> Self {
+        self.config.sync = enabled;
+        self
+    }
+
+    pub fn preserve_state(mut self, enabled: bool) -> Self {
+        self.config.no_preserve_state = !enabled;
+        self
+    }
+
+    /// Live messages queued for each browser. A browser that falls further behind skips the
+    /// backlog and reloads instead. Defaults to 64.
+    pub fn broadcast_capacity(mut self, capacity: usize) -> Self {
+        self.config.broadcast_capacity = capacity;
+        self
+    }
+
+    pub fn render_markdown(mut self, enabled: bool) -> Self {
+        self.config.render_markdown = enabled;
+        self
+    }
+
+    pub fn clean_urls(mut self, enabled: bool) -> Self {
+        self.config.clean_urls = enabled;
+        self
+    }
+
+    /// File names served for directory URLs, tried in order. Defaults to `index.html`.
+    pub fn index_files<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.index_files = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn dotfiles(mut self, policy: DotfilePolicy) -> Self {
+        self.config.dotfiles = policy;
+        self
+    }
+
+    pub fn follow_symlinks(mut self, enabled: bool) -> Self {
+        self.config.follow_symlinks = enabled;
+        self
+    }
+
+    /// Keeps small assets and rendered pages in memory until the watcher sees them change.
+    /// Enabled by default.
+    pub fn memory_cache(mut self, enabled: bool) -> Self {
+        self.config.no_memory_cache = !enabled;
+        self
+    }
+
+    /// Serves HTML and Markdown files larger than `bytes` as they are on disk, without the live
+    /// client. `0` disables the limit.
+    pub fn inject_max_size(mut self, bytes: u64) -> Self {
+        self.config.inject_max_size = bytes;
+        self
+    }
+
+    /// Inserts the live client before `</body>` instead of `</head>`.
+    pub fn inject_position(mut self, position: InjectPosition) -> Self {
+        self.config.inject_position = position;
+        self
+    }
+
+    /// Serves pages whose request path matches `glob` without the live client; `*` matches
+    /// any run of characters. Can be called repeatedly.
+    pub fn no_inject(mut self, glob: impl Into<String>) -> Self {
+        self.config.no_inject.push(glob.into());
+        self
+    }
+
+    /// Inserts the contents of `path` into served pages after the live client.
+    pub fn inject_snippet(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.inject_snippet = Some(path.into());
+        self
+    }
+
+    /// Compiles Sass sources on request with the given executable, e.g. `"sass"`.
+    pub fn sass(mut self, command: impl Into<String>) -> Self {
+        self.config.sass = true;
+        self.config.sass_command = command.into();
+        self
+    }
+
+    /// Transpiles TypeScript and JSX on request with the given esbuild executable, e.g.
+    /// `"esbuild"`.
+    pub fn transpile(mut self, command: impl Into<String>) -> Self {
+        self.config.transpile = true;
+        self.config.esbuild_command = command.into();
+        self
+    }
+
+    pub fn includes(mut self, enabled: bool) -> Self {
+        self.config.includes = enabled;
+        self
+    }
+
+    pub fn compress(mut self, enabled: bool) -> Self {
+        self.config.compress = enabled;
+        self
+    }
+
+    /// Sends `Cache-Control: no-store` for every file, overriding any cache rules.
+    pub fn no_cache_all(mut self, enabled: bool) -> Self {
+        self.config.no_cache_all = enabled;
+        self
+    }
+
+    /// Appends a `Cache-Control` rule; the first matching rule applies.
+    pub fn cache_control(mut self, rule: CacheRuleConfig) -> Self {
+        self.config.file.cache_control.push(rule);
+        self
+    }
+
+    /// Accepts cleartext HTTP/2 connections that start with the HTTP/2 preface; HTTP/1.1
+    /// clients on the same port are unaffected.
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.config.http2 = enabled;
+        self
+    }
+
+    /// Lets clients create, replace, and delete files with `PUT` and `DELETE`.
+    pub fn writable(mut self, enabled: bool) -> Self {
+        self.config.writable = enabled;
+        self
+    }
+
+    /// Paces responses to the given bandwidth and latency.
+    pub fn throttle(mut self, throttle: Throttle) -> Self {
+        self.config.throttle = Some(throttle);
+        self
+    }
+
+    /// Answers API requests from fixture files, e.g. `mocks/GET/api/users.json`.
+    pub fn mocks(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.mocks = Some(dir.into());
+        self
+    }
+
+    /// Enables the `/_live` control endpoints for callers presenting this bearer token.
+    pub fn control_token(mut self, token: impl Into<String>) -> Self {
+        self.config.control_token = Some(token.into());
+        self
+    }
+
+    /// Requires HTTP basic authentication with these credentials.
+    pub fn basic_auth(mut self, user: &str, password: &str) -> Self {
+        self.config.auth = Some(format!("{user}:{password}"));
+        self
+    }
+
+    /// Accepts requests whose `Host` header names `host`; `.example.test` matches subdomains.
+    pub fn allow_host(mut self, host: impl Into<String>) -> Self {
+        self.config.allow_hosts.push(host.into());
+        self
+    }
+
+    /// Restricts non-loopback clients to the given address or range.
+    pub fn allow_ip(mut self, range: impl Into<IpRange>) -> Self {
+        self.config.allow_ips.push(range.into());
+        self
+    }
+
+    /// Sends CORS headers allowing `origin`, or any origin for `"*"`.
+    pub fn cors(mut self, origin: impl Into<String>) -> Self {
+        self.config.cors = Some(origin.into());
+        self
+    }
+
+    /// Serves the whole site under `prefix`, e.g. `"/my-repo/"`.
+    pub fn base_path(mut self, prefix: impl Into<String>) -> Self {
+        self.config.base_path = Some(prefix.into());
+        self
+    }
+
+    /// Serves `dir` under the URL prefix `prefix`, e.g. `("/assets", "../shared-assets")`.
+    pub fn mount(mut self, prefix: &str, dir: impl Into<PathBuf>) -> Self {
+        self.config.mounts.push(Mount::new(prefix, dir));
+        self
+    }
+
+    /// Watches an additional file or directory that is not served, reloading on changes.
+    pub fn watch(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.watch.push(path.into());
+        self
+    }
+
+    pub fn verbose_watch(mut self, enabled: bool) -> Self {
+        self.config.verbose_watch = enabled;
+        self
+    }
+
+    /// Appends every request to `path` in Apache `format`.
+    pub fn access_log(mut self, path: impl Into<PathBuf>, format: AccessLogFormat) -> Self {
+        self.config.access_log = Some(path.into());
+        self.config.access_log_format = format;
+        self
+    }
+
+    /// Sets the value substituted for `%%NAME%%` placeholders in served HTML.
+    pub fn var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.file.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Appends a redirect or rewrite rule; rules are tried in the order they are added.
+    pub fn rule(mut self, rule: RuleConfig) -> Self {
+        self.config.file.rules.push(rule);
+        self
+    }
+
+    /// Appends a fault injection rule; the first matching rule applies.
+    pub fn fault(mut self, fault: FaultConfig) -> Self {
+        self.config.file.faults.push(fault);
+        self
+    }
+
+    /// Runs `command` through the shell on `event`; see [`crate::hooks`].
+    pub fn hook(mut self, event: HookEvent, command: impl Into<String>) -> Self {
+        let hooks = &mut self.config.file.hooks;
+        let slot = match event {
+            HookEvent::Start => &mut hooks.on_start,
+            HookEvent::Change => &mut hooks.on_change,
+            HookEvent::ClientConnect => &mut hooks.on_client_connect,
+            HookEvent::Shutdown => &mut hooks.on_shutdown,
+        };
+        *slot = Some(command.into());
+        self
+    }
+
+    /// Registers a plugin. Plugins run in the order they are added.
+    pub fn plugin(mut self, plugin: impl Plugin) -> Self {
+        self.plugins.push(Arc::new(plugin));
+        self
+    }
+
+    pub fn config(&self) -> &DevServerConfig {
+        &self.config
+    }
+
+    pub async fn build(self) -> anyhow::Result<Application> {
+        Application::build_with_plugins(&self.config, self.plugins).await
+    }
+}
+
 impl Application {
+    pub fn builder() -> ApplicationBuilder {
+        ApplicationBuilder::default()
+    }
+
     pub async fn build(config: &DevServerConfig) -> anyhow::Result<Self> {
-        let allow_fallback = config.port == config::DEFAULT_PORT;
-        let (listener, port) = bind_listener(config.port, allow_fallback)?;
+        Self::build_with_plugins(config, Vec::new()).await
+    }
+
+    async fn build_with_plugins(
+        config: &DevServerConfig,
+        plugins: Vec<Arc<dyn Plugin>>,
+    ) -> anyhow::Result<Self> {
+        let (listener, address) = match &config.uds {
+            Some(path) => (
+                bind_uds(path)?,
+                // Unix sockets have no address; this only feeds URL helpers.
+                SocketAddr::new(config.host, 0),
+            ),
+            None => {
+                let (listener, port) = bind_listener(config)?;
+                if port != config.port && config.port != 0 {
+                    tracing::info!(requested = config.port, port, "port in use, switched");
+                }
+                let address = listener.local_addr()?;
+                (Listener::Tcp(listener), address)
+            }
+        };
 
-        if allow_fallback && port != config.port {
-            println!(
-                "[web-dev-server] port {} in use, switched to {}",
-                config.port, port
+        let archive = if archive::is_archive(&config.base_dir) {
+            Some(Arc::new(Extracted::open(&config.base_dir)?))
+        } else {
+            None
+        };
+        let served_dir = archive
+            .as_ref()
+            .map_or(config.base_dir.as_path(), |archive| archive.root());
+        let base_dir = resolve_base_dir(served_dir).with_context(|| {
+            format!(
+                "failed to resolve base directory {}",
+                config.base_dir.display()
+            )
+        })?;
+
+        let mocks_dir = config
+            .mocks
+            .as_deref()
+            .map(|dir| {
+                resolve_base_dir(dir)
+                    .with_context(|| format!("failed to resolve mocks directory {}", dir.display()))
+            })
+            .transpose()?;
+
+        let rules = config
+            .file
+            .rules
+            .iter()
+            .map(Rule::compile)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let faults = config
+            .file
+            .faults
+            .iter()
+            .map(Fault::compile)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let cache_rules = config
+            .file
+            .cache_control
+            .iter()
+            .map(CacheRule::compile)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut mounts = config
+            .mounts
+            .iter()
+            .map(|mount| {
+                let dir = resolve_base_dir(&mount.dir).with_context(|| {
+                    format!(
+                        "failed to resolve mount directory {} for {}",
+                        mount.dir.display(),
+                        mount.prefix
+                    )
+                })?;
+                Ok(Mount::new(&mount.prefix, dir))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        mounts.sort_by_key(|mount| std::cmp::Reverse(mount.prefix.len()));
+
+        let watch_paths = config
+            .watch
+            .iter()
+            .map(|path| {
+                std::fs::canonicalize(path)
+                    .with_context(|| format!("failed to resolve watch path {}", path.display()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let injection = Injection::load(
+            config.inject_position,
+            config.no_inject.clone(),
+            config.inject_snippet.as_deref(),
+        )?;
+        let access_log = config
+            .access_log
+            .as_deref()
+            .map(|path| AccessLog::open(path, config.access_log_format).map(Arc::new))
+            .transpose()?;
+
+        let tunnel = match &config.tunnel {
+            Some(provider) => start_tunnel(provider, address).await,
+            None => None,
+        };
+        if config.writable && tunnel.is_some() && config.auth.is_none() {
+            tracing::warn!(
+                "--writable with --tunnel and no --auth lets anyone with the public URL change files"
             );
         }
+        let mut allowed_hosts = config.allow_hosts.clone();
+        allowed_hosts.extend(tunnel.as_ref().map(|tunnel| tunnel.host().to_string()));
 
-        let base_dir = resolve_base_dir(&config.base_dir)
-            .with_context(|| format!("failed to resolve base directory {}", config.base_dir))?;
+        let base_path = config.base_path.as_deref().and_then(base_path::normalize);
+        let host = match &config.uds {
+            Some(_) => String::from("localhost"),
+            None => address.to_string(),
+        };
+        let suffix = base_path
+            .as_deref()
+            .map(|base_path| format!("{base_path}/"))
+            .unwrap_or_default();
+        let mut hook_env = vec![
+            ("WEB_DEV_SERVER_URL", format!("http://{host}{suffix}")),
+            ("WEB_DEV_SERVER_BASE_DIR", base_dir.display().to_string()),
+        ];
+        if let Some(tunnel) = &tunnel {
+            hook_env.push(("WEB_DEV_SERVER_PUBLIC_URL", tunnel.url().to_string()));
+        }
 
-        let (broadcaster, _) = broadcast::channel(64);
+        anyhow::ensure!(
+            config.broadcast_capacity > 0,
+            "--broadcast-capacity must be at least 1"
+        );
+        let (broadcaster, _) = broadcast::channel(config.broadcast_capacity);
 
         let state = AppState {
             base_dir: base_dir.clone(),
             broadcaster: broadcaster.clone(),
             diff_mode: config.diff_mode,
+            sync: config.sync,
+            preserve_state: !config.no_preserve_state,
+            render_markdown: config.render_markdown,
+            clean_urls: config.clean_urls,
+            index_files: Arc::new(config.index_files.clone()),
+            dotfiles: config.dotfiles,
+            follow_symlinks: config.follow_symlinks,
+            cache: (!config.no_memory_cache).then(|| Arc::new(AssetCache::default())),
+            inject_max_size: config.inject_max_size,
+            injection: Arc::new(injection),
+            sass: config
+                .sass
+                .then(|| Arc::new(SassCompiler::new(config.sass_command.clone()))),
+            transpiler: config
+                .transpile
+                .then(|| Arc::new(Transpiler::new(config.esbuild_command.clone()))),
+            includes: config.includes.then(|| Arc::new(IncludeGraph::default())),
+            vars: Arc::new(config.file.vars.clone()),
+            compress: config.compress,
+            no_cache_all: config.no_cache_all,
+            cache_rules: Arc::new(cache_rules),
+            http2: config.http2,
+            writable: config.writable,
+            throttle: config.throttle,
+            mocks_dir,
+            control_token: config.control_token.clone(),
+            basic_auth: config.auth.as_deref().map(auth::encode_credentials),
+            allowed_hosts: Arc::new(allowed_hosts),
+            allowed_ips: Arc::new(config.allow_ips.clone()),
+            cors_origin: config.cors.clone(),
+            rules: Arc::new(rules),
+            faults: Arc::new(faults),
+            base_path,
+            mounts: Arc::new(mounts),
+            watch_paths: Arc::new(watch_paths),
+            archive,
+            verbose_watch: config.verbose_watch,
+            clients: Arc::default(),
+            hooks: Arc::new(Hooks::new(config.file.hooks.clone(), hook_env)),
+            access_log,
+            hashes: Arc::default(),
+            plugins: Arc::new(plugins),
         };
 
         let (watcher, notify_rx) = create_watcher(&state)?;
@@ -87,14 +736,49 @@ impl Application {
 
         Ok(Self {
             server,
-            port,
-            _watcher: watcher,
+            address,
+            uds: config.uds.clone(),
+            tunnel,
+            watcher,
             state,
         })
     }
 
     pub fn port(&self) -> u16 {
-        self.port
+        self.address.port()
+    }
+
+    /// Socket address the server is listening on. With [`ApplicationBuilder::uds`] this is
+    /// the configured host with port `0`; see [`Application::uds_path`].
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Public URL reported by the `--tunnel` client, if one started.
+    pub fn tunnel_url(&self) -> Option<&str> {
+        self.tunnel.as_ref().map(Tunnel::url)
+    }
+
+    /// Archive given as the base directory, if any; [`Application::base_dir`] is then the
+    /// directory it was unpacked into.
+    pub fn archive(&self) -> Option<&Path> {
+        self.state.archive.as_deref().map(Extracted::archive)
+    }
+
+    /// Path of the Unix domain socket when listening on one instead of TCP.
+    pub fn uds_path(&self) -> Option<&Path> {
+        self.uds.as_deref()
+    }
+
+    /// Handle that can stop the server from another task.
+    pub fn handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            servers: vec![(
+                self.server.handle(),
+                self.state.broadcaster.clone(),
+                self.state.clients.clone(),
+            )],
+        }
     }
 
     pub fn base_dir(&self) -> &Path {
@@ -105,64 +789,420 @@ impl Application {
         self.state.diff_mode
     }
 
+    pub fn sync(&self) -> bool {
+        self.state.sync
+    }
+
+    pub fn render_markdown(&self) -> bool {
+        self.state.render_markdown
+    }
+
+    pub fn clean_urls(&self) -> bool {
+        self.state.clean_urls
+    }
+
+    pub fn index_files(&self) -> &[String] {
+        &self.state.index_files
+    }
+
+    pub fn dotfiles(&self) -> DotfilePolicy {
+        self.state.dotfiles
+    }
+
+    pub fn follow_symlinks(&self) -> bool {
+        self.state.follow_symlinks
+    }
+
+    pub fn memory_cache(&self) -> bool {
+        self.state.cache.is_some()
+    }
+
+    pub fn inject_max_size(&self) -> u64 {
+        self.state.inject_max_size
+    }
+
+    pub fn inject_position(&self) -> InjectPosition {
+        self.state.injection.position
+    }
+
+    pub fn injection_skips(&self) -> &[String] {
+        &self.state.injection.skip
+    }
+
+    pub fn inject_snippet(&self) -> bool {
+        self.state.injection.snippet.is_some()
+    }
+
+    pub fn sass(&self) -> bool {
+        self.state.sass.is_some()
+    }
+
+    pub fn transpile(&self) -> bool {
+        self.state.transpiler.is_some()
+    }
+
+    pub fn includes(&self) -> bool {
+        self.state.includes.is_some()
+    }
+
+    pub fn compress(&self) -> bool {
+        self.state.compress
+    }
+
+    pub fn no_cache_all(&self) -> bool {
+        self.state.no_cache_all
+    }
+
+    pub fn cache_rules(&self) -> usize {
+        self.state.cache_rules.len()
+    }
+
+    pub fn http2(&self) -> bool {
+        self.state.http2
+    }
+
+    pub fn writable(&self) -> bool {
+        self.state.writable
+    }
+
+    /// Number of fault injection rules in effect.
+    pub fn faults(&self) -> usize {
+        self.state.faults.len()
+    }
+
+    pub fn throttle(&self) -> Option<Throttle> {
+        self.state.throttle
+    }
+
+    pub fn mocks_dir(&self) -> Option<&Path> {
+        self.state.mocks_dir.as_deref()
+    }
+
+    /// Mounted directories with their resolved paths.
+    pub fn mounts(&self) -> &[Mount] {
+        &self.state.mounts
+    }
+
+    /// Extra paths watched with `--watch`, resolved.
+    pub fn watch_paths(&self) -> &[PathBuf] {
+        &self.state.watch_paths
+    }
+
+    pub fn verbose_watch(&self) -> bool {
+        self.state.verbose_watch
+    }
+
+    pub fn access_log(&self) -> Option<&AccessLog> {
+        self.state.access_log.as_deref()
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.state.hooks
+    }
+
+    pub fn control_api(&self) -> bool {
+        self.state.control_token.is_some()
+    }
+
+    pub fn basic_auth(&self) -> bool {
+        self.state.basic_auth.is_some()
+    }
+
+    pub fn cors_origin(&self) -> Option<&str> {
+        self.state.cors_origin.as_deref()
+    }
+
+    /// Whether non-loopback clients are limited by `--allow-ip`.
+    pub fn ip_allowlist(&self) -> bool {
+        !self.state.allowed_ips.is_empty()
+    }
+
+    pub fn base_path(&self) -> Option<&str> {
+        self.state.base_path.as_deref()
+    }
+
+    /// URL of the site root. Behind a Unix socket this is the `http://localhost/` URL clients
+    /// such as `curl --unix-socket` request.
     pub fn primary_url(&self) -> String {
-        format!("http://127.0.0.1:{}", self.port)
+        if self.uds.is_some() {
+            return format!("http://localhost{}", self.url_suffix());
+        }
+        format!("http://{}{}", self.address, self.url_suffix())
+    }
+
+    /// URL other devices on the network can use, or `None` when only bound to loopback.
+    /// A wildcard bind (`0.0.0.0` / `::`) is reported with the machine's outward-facing IP.
+    pub fn network_url(&self) -> Option<String> {
+        let ip = self.address.ip();
+        if ip.is_loopback() || self.uds.is_some() {
+            return None;
+        }
+        let ip = if ip.is_unspecified() {
+            outbound_ip(ip.is_ipv6())?
+        } else {
+            ip
+        };
+        Some(format!(
+            "http://{}{}",
+            SocketAddr::new(ip, self.address.port()),
+            self.url_suffix()
+        ))
+    }
+
+    /// Path appended to server URLs so they open the site root.
+    pub fn url_suffix(&self) -> String {
+        self.state
+            .base_path
+            .as_deref()
+            .map(|base_path| format!("{base_path}/"))
+            .unwrap_or_default()
     }
 
+    /// Serves until the server is stopped through [`Application::handle`] or the process
+    /// receives Ctrl+C / SIGTERM. On a signal the watcher is stopped, live clients are told the
+    /// server is going away, and in-flight requests are allowed to finish.
     pub async fn run_until_stopped(self) -> std::io::Result<()> {
-        self.server.await
+        let handle = self.handle();
+        let Self {
+            server,
+            watcher,
+            uds,
+            tunnel,
+            state,
+            ..
+        } = self;
+        let mut server = std::pin::pin!(server);
+        fire_hook(&state, HookEvent::Start, Vec::new());
+
+        let result = tokio::select! {
+            result = &mut server => result,
+            () = shutdown_signal() => {
+                tracing::info!("shutting down");
+                drop(watcher);
+                // The server future has to keep being polled for the stop command to be
+                // processed.
+                let ((), result) = tokio::join!(handle.stop(true), server);
+                result
+            }
+        };
+
+        state
+            .hooks
+            .run(
+                HookEvent::Shutdown,
+                vec![(
+                    "WEB_DEV_SERVER_CLIENTS",
+                    state.clients.len_at_shutdown().to_string(),
+                )],
+                hooks::SHUTDOWN_TIMEOUT,
+            )
+            .await;
+        drop(tunnel);
+        if let Some(archive) = &state.archive {
+            archive.remove();
+        }
+        if let Some(path) = uds {
+            let _ = std::fs::remove_file(path);
+        }
+        result
     }
 }
 
-fn bind_listener(preferred_port: u16, allow_fallback: bool) -> anyhow::Result<(TcpListener, u16)> {
-    let mut port = preferred_port;
+/// Resolves on Ctrl+C, or on SIGTERM where supported. A handler that cannot be installed
+/// never resolves rather than triggering a shutdown.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
 
-    loop {
-        match TcpListener::bind(("127.0.0.1", port)) {
-            Ok(listener) => return Ok((listener, port)),
-            Err(error) if allow_fallback && error.kind() == ErrorKind::AddrInUse => {
-                if port == u16::MAX {
-                    return Err(anyhow!(
-                        "failed to find an available port starting at {}",
-                        preferred_port
-                    ));
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
+/// Starts the `--tunnel` client. A tunnel that fails only costs the public URL, so the error
+/// is logged and the server starts without it.
+async fn start_tunnel(provider: &TunnelProvider, address: SocketAddr) -> Option<Tunnel> {
+    let local = match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::from(([127, 0, 0, 1], address.port())),
+        IpAddr::V6(ip) if ip.is_unspecified() => {
+            SocketAddr::new(std::net::Ipv6Addr::LOCALHOST.into(), address.port())
+        }
+        _ => address,
+    };
+    tracing::info!(?provider, "starting tunnel");
+    match tunnel::start(provider, local).await {
+        Ok(tunnel) => Some(tunnel),
+        Err(error) => {
+            tracing::error!("tunnel failed: {error:#}");
+            None
+        }
+    }
+}
+
+/// Local address the OS would route external traffic from. Connecting a UDP socket sends no
+/// packets, it only selects a route.
+fn outbound_ip(ipv6: bool) -> Option<IpAddr> {
+    let (bind, target) = if ipv6 {
+        ("[::]:0", "[2001:4860:4860::8888]:80")
+    } else {
+        ("0.0.0.0:0", "8.8.8.8:80")
+    };
+    let socket = std::net::UdpSocket::bind(bind).ok()?;
+    socket.connect(target).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// Socket the server accepts connections on.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+/// Binds a Unix domain socket, replacing a stale socket file left by a previous run.
+#[cfg(unix)]
+fn bind_uds(path: &Path) -> anyhow::Result<Listener> {
+    use std::os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    };
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(path).is_ok() {
+            anyhow::bail!("another server is already listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("failed to bind to {}", path.display()))?;
+    Ok(Listener::Unix(listener))
+}
+
+#[cfg(not(unix))]
+fn bind_uds(_: &Path) -> anyhow::Result<Listener> {
+    anyhow::bail!("--uds is only supported on Unix platforms")
+}
+
+/// Binds `--port`, falling back to the next free port in `--port-range` (or above the
+/// requested port) unless `--strict-port` is set.
+fn bind_listener(config: &DevServerConfig) -> anyhow::Result<(TcpListener, u16)> {
+    let host = config.host;
+    let requested = config.port;
+    let fallback: Box<dyn Iterator<Item = u16>> = match config.port_range {
+        _ if config.strict_port || requested == 0 => Box::new(std::iter::empty()),
+        Some(range) => Box::new(range.ports().filter(move |&port| port != requested)),
+        None => Box::new(requested.saturating_add(1)..=u16::MAX),
+    };
+
+    for port in std::iter::once(requested).chain(fallback) {
+        match TcpListener::bind((host, port)) {
+            Ok(listener) => {
+                let port = listener.local_addr()?.port();
+                return Ok((listener, port));
+            }
+            Err(error) if error.kind() == ErrorKind::AddrInUse => {
+                if config.strict_port {
+                    return Err(anyhow::Error::from(error)
+                        .context(format!("port {port} is in use (--strict-port is set)")));
                 }
-                port = port.checked_add(1).ok_or_else(|| {
-                    anyhow!(
-                        "failed to find an available port starting at {}",
-                        preferred_port
-                    )
-                })?;
             }
             Err(error) => {
                 return Err(anyhow::Error::from(error)
-                    .context(format!("failed to bind to 127.0.0.1:{port}")));
+                    .context(format!("failed to bind to {}", SocketAddr::new(host, port))));
             }
         }
     }
+
+    match config.port_range {
+        Some(range) => Err(anyhow!(
+            "port {requested} and every port in {}-{} are in use",
+            range.first,
+            range.last
+        )),
+        None => Err(anyhow!(
+            "failed to find an available port starting at {requested}"
+        )),
+    }
 }
 
-async fn run(listener: TcpListener, state: AppState) -> anyhow::Result<Server> {
+async fn run(listener: Listener, state: AppState) -> anyhow::Result<Server> {
+    let compress = state.compress;
+    let http2 = state.http2;
+    let writable = state.writable;
     let shared_state = web::Data::new(state);
 
     let server = actix_web::HttpServer::new(move || {
         actix_web::App::new()
             .app_data(shared_state.clone())
+            .wrap(from_fn(rewrites::middleware))
+            // Fixtures answer before rules so an SPA fallback such as `/* /index.html` does
+            // not shadow the mock API.
+            .wrap(from_fn(mocks::middleware))
+            .wrap(from_fn(faults::middleware))
+            .wrap(from_fn(cache_policy::middleware))
+            .wrap(from_fn(base_path::middleware))
+            .wrap(from_fn(plugin::middleware))
+            .wrap(Condition::new(compress, Compress::default()))
+            .wrap(from_fn(throttle::middleware))
+            .wrap(from_fn(auth::middleware))
+            .wrap(from_fn(cors::middleware))
+            .wrap(from_fn(access::middleware))
+            .wrap(from_fn(logging::middleware))
+            .wrap(from_fn(access_log::middleware))
             .service(build_internal_scope())
-            .service(web::resource("/{tail:.*}").route(web::to(serve_file)))
-    })
-    .listen(listener)?
-    .run();
+            .service(files_resource(writable))
+    });
+    let server = match listener {
+        Listener::Tcp(listener) if http2 => server.listen_auto_h2c(listener)?,
+        Listener::Tcp(listener) => server.listen(listener)?,
+        #[cfg(unix)]
+        Listener::Unix(listener) => server.listen_uds(listener)?,
+    };
 
-    Ok(server)
+    // Signals are handled by `Application::run_until_stopped` so clients hear about the stop.
+    Ok(server.disable_signals().run())
 }
 
-fn resolve_base_dir(base_dir: &str) -> anyhow::Result<PathBuf> {
-    let path = PathBuf::from(base_dir);
-    let absolute = if path.is_absolute() {
-        path
+/// Catch-all resource for served files, accepting `PUT` and `DELETE` with `--writable`.
+fn files_resource(writable: bool) -> actix_web::Resource {
+    let resource = web::resource("/{tail:.*}");
+    let resource = if writable {
+        resource
+            .route(web::put().to(writable::put))
+            .route(web::delete().to(writable::delete))
     } else {
-        std::env::current_dir()?.join(path)
+        resource
+    };
+    resource.route(web::to(serve_file))
+}
+
+fn resolve_base_dir(base_dir: &Path) -> anyhow::Result<PathBuf> {
+    let absolute = if base_dir.is_absolute() {
+        base_dir.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(base_dir)
     };
 
     let canonical = absolute.canonicalize()?;
@@ -180,17 +1220,42 @@ fn create_watcher(
     mpsc::UnboundedReceiver<notify::Result<notify::Event>>,
 )> {
     let (tx, rx) = mpsc::unbounded_channel();
-    let root = state.base_dir.clone();
 
-    let mut watcher = recommended_watcher(move |res| {
-        let _ = tx.send(res);
-    })?;
+    let mut watcher = RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default().with_follow_symlinks(state.follow_symlinks),
+    )?;
 
-    watcher.watch(&root, RecursiveMode::Recursive)?;
+    for root in watch_roots(state) {
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+    }
+    if let Some(archive) = &state.archive {
+        watcher.watch(archive.archive(), RecursiveMode::NonRecursive)?;
+    }
 
     Ok((watcher, rx))
 }
 
+/// Directories registered with the watcher; nested roots are covered by their parent.
+fn watch_roots(state: &AppState) -> Vec<PathBuf> {
+    let candidates = std::iter::once(&state.base_dir)
+        .chain(state.mocks_dir.as_ref())
+        .chain(state.mounts.iter().map(|mount| &mount.dir))
+        .chain(state.watch_paths.iter());
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        if roots.iter().any(|root| candidate.starts_with(root)) {
+            continue;
+        }
+        roots.retain(|root| !root.starts_with(candidate));
+        roots.push(candidate.clone());
+    }
+    roots
+}
+
 fn spawn_watcher_loop(
     state: AppState,
     mut rx: mpsc::UnboundedReceiver<notify::Result<notify::Event>>,
@@ -199,14 +1264,31 @@ fn spawn_watcher_loop(
         while let Some(event) = rx.recv().await {
             match event {
                 Ok(event) => {
+                    tracing::debug!(kind = ?event.kind, paths = ?event.paths, "file event");
                     let state_for_event = state.clone();
-                    tokio::spawn(async move {
-                        sleep(Duration::from_millis(120)).await;
-                        handle_fs_event(state_for_event, event);
-                    });
+                    // The span covers the whole way from the watcher to the browsers; reads
+                    // are too frequent and never reach them.
+                    let span = if should_ignore_event(&event.kind) {
+                        tracing::Span::none()
+                    } else {
+                        tracing::info_span!("file_change", kind = ?event.kind, paths = ?event.paths)
+                    };
+                    tokio::spawn(
+                        async move {
+                            sleep(Duration::from_millis(120)).await;
+                            changes::wait_until_stable(&event.paths)
+                                .instrument(tracing::info_span!("wait_until_stable"))
+                                .await;
+                            let unchanged = saved_without_changes(&state_for_event, &event)
+                                .instrument(tracing::info_span!("hash_contents"))
+                                .await;
+                            handle_fs_event(state_for_event, event, unchanged);
+                        }
+                        .instrument(span),
+                    );
                 }
                 Err(error) => {
-                    eprintln!("[web-dev-server] watcher error: {error}");
+                    tracing::error!(%error, "watcher error");
                     let _ = state.broadcaster.send(LiveMessage::Reload);
                 }
             }
@@ -214,46 +1296,302 @@ fn spawn_watcher_loop(
     });
 }
 
-fn handle_fs_event(state: AppState, event: notify::Event) {
+/// Whether every file `event` rewrote still holds the content recorded for it, in which
+/// case [`handle_fs_event`] drops the event. Hashing reads the files, so it runs here in the
+/// event task rather than in the synchronous handler.
+async fn saved_without_changes(state: &AppState, event: &notify::Event) -> bool {
+    if should_ignore_event(&event.kind) || !rewrites_content(&event.kind) {
+        return false;
+    }
+    let archive = state.archive.as_ref().map(|archive| archive.archive());
+    let mut files = Vec::with_capacity(event.paths.len());
+    for path in &event.paths {
+        if Some(path.as_path()) == archive {
+            return false;
+        }
+        if tokio::fs::metadata(path)
+            .await
+            .is_ok_and(|meta| meta.is_file())
+        {
+            files.push(path.clone());
+        }
+    }
+    state.hashes.unchanged(&files).await
+}
+
+#[tracing::instrument(skip_all)]
+fn handle_fs_event(state: AppState, event: notify::Event, unchanged: bool) {
+    for plugin in state.plugins.iter() {
+        plugin.on_fs_event(&event);
+    }
+
+    let mut change = Change::new(&state, &event);
+    let kind = event.kind;
+
+    // Removed and renamed-away paths no longer need their content remembered.
+    match kind {
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            state.hashes.forget(&event.paths);
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            // The first path is the old name.
+            state
+                .hashes
+                .forget(event.paths.get(..1).unwrap_or_default());
+        }
+        _ => {}
+    }
+
+    // Reads (including the server's own when serving a file) never change the page.
+    if should_ignore_event(&kind) {
+        change.ignore(&state);
+        return;
+    }
+
+    if let Some(archive) = state
+        .archive
+        .as_ref()
+        .filter(|archive| event.paths.iter().any(|path| path == archive.archive()))
+    {
+        // Unpacking touches the served files, which reloads pages through the usual events.
+        tracing::info!(archive = %archive.archive().display(), "archive changed, unpacking again");
+        if let Err(error) = archive.refresh() {
+            tracing::error!("{error:#}");
+        }
+        return;
+    }
+
+    if unchanged {
+        if state.verbose_watch {
+            tracing::info!(files = %change.files, kind = ?kind, "file saved without changes");
+        }
+        return;
+    }
+
+    if let Some(cache) = &state.cache {
+        // A changed partial alters every page that includes it, so drop all rendered pages.
+        if event.need_rescan() || state.includes.is_some() {
+            cache.clear();
+        } else {
+            cache.evict(&event.paths);
+        }
+    }
+
+    let sass_messages = invalidate_sass(&state, &event.paths);
+
+    if touches_mocks(&state, &event.paths) || touches_watch_paths(&state, &event.paths) {
+        change.send(&state, LiveMessage::Reload, "mock fixture or --watch path");
+        return;
+    }
+
+    if invalidate_scripts(&state, &event.paths) {
+        change.send(&state, LiveMessage::Reload, "transpiled script");
+        return;
+    }
+
     if !state.diff_mode {
-        let _ = state.broadcaster.send(LiveMessage::Reload);
+        change.send(&state, LiveMessage::Reload, "diff mode is off");
         return;
     }
 
     if event.need_rescan() {
-        let _ = state.broadcaster.send(LiveMessage::Reload);
+        change.send(&state, LiveMessage::Reload, "watcher asked for a rescan");
         return;
     }
 
-    let kind = event.kind;
+    let mut diff_messages = sass_messages;
+
+    for path in event.paths {
+        if let Some(message) = classify_path(&state, &path) {
+            diff_messages.push(message);
+        }
+        diff_messages.extend(include_dependents(&state, &path));
+    }
+
+    if !diff_messages.is_empty() {
+        if allows_diff(&kind) {
+            for message in diff_messages {
+                change.send(&state, message, "diffable change");
+            }
+            return;
+        } else {
+            change.send(&state, LiveMessage::Reload, "event kind cannot be diffed");
+            return;
+        }
+    }
+
+    if should_reload_when_no_diff(&kind) {
+        change.send(&state, LiveMessage::Reload, "no diff for this file type");
+    } else {
+        change.ignore(&state);
+    }
+}
+
+/// Events after which a file may hold the same bytes as before: plain saves, re-creations,
+/// and atomic saves that rename a temporary file over the original.
+fn rewrites_content(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any | ModifyKind::Name(_))
+    )
+}
+
+/// A watcher event as reported in the terminal activity feed, and to the `on_change` hook.
+struct Change {
+    files: String,
+    paths: Vec<PathBuf>,
+    kind: EventKind,
+    hook_fired: bool,
+}
+
+impl Change {
+    fn new(state: &AppState, event: &notify::Event) -> Self {
+        let files = event
+            .paths
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&state.base_dir)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            files,
+            paths: event.paths.clone(),
+            kind: event.kind,
+            hook_fired: false,
+        }
+    }
+
+    /// Broadcasts `message` and logs which file caused it. The `on_change` hook runs once
+    /// per event, however many messages it produces.
+    fn send(&mut self, state: &AppState, message: LiveMessage, reason: &str) {
+        if !self.hook_fired {
+            self.hook_fired = true;
+            let path = |path: &PathBuf| path.display().to_string();
+            let vars = vec![
+                (
+                    "WEB_DEV_SERVER_PATH",
+                    self.paths.first().map(path).unwrap_or_default(),
+                ),
+                (
+                    "WEB_DEV_SERVER_PATHS",
+                    self.paths.iter().map(path).collect::<Vec<_>>().join("\n"),
+                ),
+            ];
+            fire_hook(state, HookEvent::Change, vars);
+        }
+
+        let sent = match &message {
+            LiveMessage::Diff { path, resource } => format!("{resource:?} diff for {path}"),
+            _ => String::from("reload"),
+        }
+        .to_lowercase();
+        let span = tracing::info_span!("broadcast", message = sent, delivered = Empty).entered();
+        let delivered = state.broadcaster.send(message).unwrap_or(0);
+        span.record("delivered", delivered);
+        drop(span);
+
+        if state.verbose_watch {
+            tracing::info!(
+                files = %self.files,
+                sent,
+                kind = ?self.kind,
+                reason,
+                delivered,
+                "file changed"
+            );
+        } else {
+            tracing::info!(files = %self.files, sent, "file changed");
+        }
+    }
+
+    fn ignore(&self, state: &AppState) {
+        if state.verbose_watch {
+            tracing::info!(files = %self.files, kind = ?self.kind, "file event ignored");
+        }
+    }
+}
+
+/// Starts the hook for `event` with `vars` and the connected client count.
+pub(crate) fn fire_hook(state: &AppState, event: HookEvent, mut vars: Vec<(&'static str, String)>) {
+    if state.hooks.command(event).is_none() {
+        return;
+    }
+    vars.push(("WEB_DEV_SERVER_CLIENTS", state.clients.len().to_string()));
+    state.hooks.fire(event, vars);
+}
+
+/// Clears compiled Sass output touched by `paths` and returns CSS diffs for the stylesheets
+/// that need to be refetched.
+fn invalidate_sass(state: &AppState, paths: &[PathBuf]) -> Vec<LiveMessage> {
+    let Some(compiler) = &state.sass else {
+        return Vec::new();
+    };
+
+    let web_paths: BTreeSet<String> = paths
+        .iter()
+        .filter_map(|path| normalize_event_path(&state.base_dir, path))
+        .filter(|path| compiler.is_source(path))
+        .flat_map(|path| compiler.invalidate(&path))
+        .filter_map(|source| web_path(state, &source.with_extension("css"), &DiffResource::Css))
+        .collect();
+
+    web_paths
+        .into_iter()
+        .map(|path| LiveMessage::Diff {
+            path,
+            resource: DiffResource::Css,
+        })
+        .collect()
+}
+
+/// Clears transpiled output for changed TypeScript/JSX sources, returning whether there were
+/// any. Scripts cannot be swapped in place, so their pages reload.
+fn invalidate_scripts(state: &AppState, paths: &[PathBuf]) -> bool {
+    let Some(transpiler) = &state.transpiler else {
+        return false;
+    };
 
-    if should_ignore_event(&kind) {
-        return;
-    }
+    paths
+        .iter()
+        .filter_map(|path| normalize_event_path(&state.base_dir, path))
+        .fold(false, |any, path| transpiler.invalidate(&path) | any)
+}
 
-    let mut diff_messages = Vec::new();
+/// Pages fetch mock data at runtime, so any fixture change reloads them.
+fn touches_mocks(state: &AppState, paths: &[PathBuf]) -> bool {
+    let Some(mocks_dir) = &state.mocks_dir else {
+        return false;
+    };
 
-    for path in event.paths {
-        if let Some(message) = classify_path(&state, &path) {
-            diff_messages.push(message);
-        }
-    }
+    paths
+        .iter()
+        .filter_map(|path| normalize_event_path(&state.base_dir, path))
+        .any(|path| path.starts_with(mocks_dir))
+}
 
-    if !diff_messages.is_empty() {
-        if allows_diff(&kind) {
-            for message in diff_messages {
-                let _ = state.broadcaster.send(message);
-            }
-            return;
-        } else {
-            let _ = state.broadcaster.send(LiveMessage::Reload);
-            return;
-        }
+/// Extra watch paths are not served, so there is nothing to diff; reload instead. Paths
+/// that are also served are left to the regular classification.
+fn touches_watch_paths(state: &AppState, paths: &[PathBuf]) -> bool {
+    if state.watch_paths.is_empty() {
+        return false;
     }
 
-    if should_reload_when_no_diff(&kind) {
-        let _ = state.broadcaster.send(LiveMessage::Reload);
-    }
+    paths
+        .iter()
+        .filter_map(|path| normalize_event_path(&state.base_dir, path))
+        .any(|path| {
+            let served = path.starts_with(&state.base_dir)
+                || state
+                    .mounts
+                    .iter()
+                    .any(|mount| path.starts_with(&mount.dir));
+            !served && state.watch_paths.iter().any(|root| path.starts_with(root))
+        })
 }
 
 fn should_ignore_event(kind: &EventKind) -> bool {
@@ -290,18 +1628,37 @@ fn classify_path(state: &AppState, path: &Path) -> Option<LiveMessage> {
     let ext = normalized.extension()?.to_str()?.to_ascii_lowercase();
     let resource = match ext.as_str() {
         "html" | "htm" => DiffResource::Html,
+        "md" | "markdown" if state.render_markdown => DiffResource::Html,
         "css" => DiffResource::Css,
         _ => return None,
     };
 
-    let web_path = to_web_path(&state.base_dir, &normalized, &resource)?;
-
     Some(LiveMessage::Diff {
-        path: web_path,
+        path: web_path(state, &normalized, &resource)?,
         resource,
     })
 }
 
+/// HTML diffs for every page that included `path` the last time it was served.
+fn include_dependents(state: &AppState, path: &Path) -> Vec<LiveMessage> {
+    let Some(graph) = &state.includes else {
+        return Vec::new();
+    };
+    let Some(normalized) = normalize_event_path(&state.base_dir, path) else {
+        return Vec::new();
+    };
+
+    graph
+        .dependents_of(&normalized)
+        .iter()
+        .filter_map(|page| web_path(state, page, &DiffResource::Html))
+        .map(|path| LiveMessage::Diff {
+            path,
+            resource: DiffResource::Html,
+        })
+        .collect()
+}
+
 fn normalize_event_path(base_dir: &Path, path: &Path) -> Option<PathBuf> {
     if let Ok(canonical) = std::fs::canonicalize(path) {
         return Some(canonical);
@@ -320,125 +1677,520 @@ fn normalize_event_path(base_dir: &Path, path: &Path) -> Option<PathBuf> {
     }
 }
 
-fn to_web_path(base_dir: &Path, path: &Path, resource: &DiffResource) -> Option<String> {
+/// URL under which `path` is served, checking mounts before the base directory.
+fn web_path(state: &AppState, path: &Path, resource: &DiffResource) -> Option<String> {
+    let web_path = state
+        .mounts
+        .iter()
+        .find_map(|mount| {
+            to_web_path(&mount.dir, path, resource, &state.index_files)
+                .map(|relative| format!("{}{relative}", mount.prefix))
+        })
+        .or_else(|| to_web_path(&state.base_dir, path, resource, &state.index_files))?;
+
+    let web_path = if state.clean_urls && matches!(resource, DiffResource::Html) {
+        strip_html_extension(&web_path).to_owned()
+    } else {
+        web_path
+    };
+    match &state.base_path {
+        Some(base_path) => Some(format!("{base_path}{web_path}")),
+        None => Some(web_path),
+    }
+}
+
+/// `/about.html` -> `/about`, matching how `--clean-urls` serves the page.
+fn strip_html_extension(web_path: &str) -> &str {
+    [".html", ".htm"]
+        .iter()
+        .find_map(|ext| web_path.strip_suffix(ext))
+        .filter(|stem| !stem.ends_with('/'))
+        .unwrap_or(web_path)
+}
+
+/// Maps a changed file to the URL it is served at; index files collapse to their directory URL.
+fn to_web_path(
+    base_dir: &Path,
+    path: &Path,
+    resource: &DiffResource,
+    index_files: &[String],
+) -> Option<String> {
     let relative = path.strip_prefix(base_dir).ok()?;
-    let mut rel_str = relative.to_string_lossy().replace('\\', "/");
+    let rel_str = relative.to_string_lossy().replace('\\', "/");
+    let rel_str = rel_str.trim_start_matches('/');
     if rel_str.is_empty() {
         return Some(String::from("/"));
     }
 
-    rel_str = rel_str.trim_start_matches('/').to_owned();
-
-    match resource {
-        DiffResource::Html => {
-            if rel_str.ends_with("index.html") {
-                let prefix = rel_str.trim_end_matches("index.html");
-                if prefix.is_empty() {
-                    Some(String::from("/"))
-                } else {
-                    let trimmed = prefix.trim_end_matches('/');
-                    let mut path = format!("/{}", trimmed);
-                    if !path.ends_with('/') {
-                        path.push('/');
-                    }
-                    Some(path)
-                }
-            } else if rel_str.ends_with("index.htm") {
-                let prefix = rel_str.trim_end_matches("index.htm");
-                if prefix.is_empty() {
-                    Some(String::from("/"))
-                } else {
-                    let trimmed = prefix.trim_end_matches('/');
-                    let mut path = format!("/{}", trimmed);
-                    if !path.ends_with('/') {
-                        path.push('/');
-                    }
-                    Some(path)
-                }
-            } else {
-                Some(format!("/{}", rel_str))
-            }
+    if matches!(resource, DiffResource::Html) {
+        let (dir, file) = match rel_str.rsplit_once('/') {
+            Some((dir, file)) => (format!("/{dir}/"), file),
+            None => (String::from("/"), rel_str),
+        };
+        if index_files.iter().any(|index| index == file) {
+            return Some(dir);
         }
-        DiffResource::Css => Some(format!("/{}", rel_str)),
     }
+    Some(format!("/{rel_str}"))
 }
 
+#[tracing::instrument(skip_all, fields(path = tail.as_str()))]
 async fn serve_file(
     req: HttpRequest,
     tail: web::Path<String>,
     state: web::Data<AppState>,
 ) -> ActixResult<HttpResponse> {
-    let target = locate_file(&state.base_dir, tail.as_str())
-        .await
-        .map_err(|_| ErrorNotFound("Not Found"))?;
+    if let Some(response) = state.dotfiles.check(tail.as_str()) {
+        return Ok(response);
+    }
+    let compilers = [
+        state.sass.as_deref().map(|sass| &**sass),
+        state.transpiler.as_deref().map(|transpiler| &**transpiler),
+    ];
+    for compiler in compilers.into_iter().flatten() {
+        if let Some(response) = serve_compiled(&state, compiler, tail.as_str()).await? {
+            return Ok(response);
+        }
+    }
 
-    if is_html(&target) {
-        let raw = fs::read_to_string(&target)
+    let target = locate_file(&state, tail.as_str()).await.map_err(|error| {
+        if error.is::<SymlinkEscape>() {
+            tracing::warn!(path = tail.as_str(), "{error}");
+            ErrorForbidden("symlink target is outside the served directories")
+        } else {
+            ErrorNotFound("Not Found")
+        }
+    })?;
+
+    let is_page = (state.render_markdown && is_markdown(&target)) || is_html(&target);
+    if is_page && exceeds_inject_limit(&state, &target).await {
+        tracing::debug!(
+            path = %target.display(),
+            limit = state.inject_max_size,
+            "page is too large for live reload, serving it untouched"
+        );
+        serve_static(&req, &state, &target).await
+    } else if is_page {
+        let modified = modified_time(&target).await;
+        let inject = !state.injection.skips(req.path());
+        // Plugins may transform pages differently between requests, so their output is not
+        // kept. Pages skipping the client are rare enough to render every time, which keeps
+        // the cache keyed by file alone.
+        let cache = state
+            .cache
+            .as_ref()
+            .filter(|_| state.plugins.is_empty() && inject);
+        if let Some(body) = cache.zip(modified).and_then(|(c, m)| c.get(&target, m)) {
+            return Ok(html_response(&req, body, modified));
+        }
+
+        let body = Bytes::from(render_page(&state, &target, inject).await?);
+        if let Some((cache, modified)) = cache.zip(modified) {
+            cache.insert(&target, modified, body.clone());
+        }
+        Ok(html_response(&req, body, modified))
+    } else if !state.plugins.is_empty() {
+        let raw = fs::read(&target)
             .await
-            .map_err(ErrorInternalServerError)?;
-        let injected =
-            inject_live_client(&raw, state.diff_mode).map_err(ErrorInternalServerError)?;
+            .map_err(|_| ErrorNotFound("Not Found"))?;
+        let body =
+            plugin::transform(&state.plugins, &target, raw).map_err(ErrorInternalServerError)?;
+        let mime = mime_guess::from_path(&target).first_or_octet_stream();
 
-        Ok(HttpResponse::Ok()
-            .append_header(("Cache-Control", "no-cache, no-store, must-revalidate"))
-            .content_type("text/html; charset=utf-8")
-            .body(injected))
+        Ok(HttpResponse::Ok().content_type(mime.as_ref()).body(body))
+    } else {
+        serve_static(&req, &state, &target).await
+    }
+}
+
+/// Whether a page is too large to read into memory for injection (`--inject-max-size`).
+async fn exceeds_inject_limit(state: &AppState, target: &Path) -> bool {
+    if state.inject_max_size == 0 {
+        return false;
+    }
+    fs::metadata(target)
+        .await
+        .is_ok_and(|metadata| metadata.len() > state.inject_max_size)
+}
+
+/// Reads an HTML or Markdown page and produces the body sent to browsers, with includes,
+/// variables, and, when `inject` is set, the live client applied.
+#[tracing::instrument(skip_all, fields(path = %target.display(), inject))]
+async fn render_page(state: &AppState, target: &Path, inject: bool) -> ActixResult<String> {
+    let raw = fs::read(target).await.map_err(ErrorInternalServerError)?;
+    state.hashes.record(target, &raw);
+    let raw = plugin::transform(&state.plugins, target, raw).map_err(ErrorInternalServerError)?;
+    let raw = String::from_utf8(raw).map_err(ErrorInternalServerError)?;
+
+    let html = if is_markdown(target) {
+        markdown::render_document(&raw, target)
     } else {
-        let file = NamedFile::open_async(&target)
+        let raw = expand_includes(state, target, raw);
+        template::substitute(&raw, &state.vars)
+    };
+    if !inject {
+        return Ok(html);
+    }
+    inject_live_client(&html, state).map_err(ErrorInternalServerError)
+}
+
+/// Precompressed variants looked up next to a file, in order of preference.
+const SIDECARS: &[(&str, &str, ContentEncoding)] = &[
+    ("br", "br", ContentEncoding::Brotli),
+    ("gzip", "gz", ContentEncoding::Gzip),
+];
+
+/// Serves a file from disk, preferring a precompressed `.br`/`.gz` sidecar when the client
+/// accepts that encoding.
+#[tracing::instrument(skip_all, fields(path = %target.display()))]
+async fn serve_static(
+    req: &HttpRequest,
+    state: &AppState,
+    target: &Path,
+) -> ActixResult<HttpResponse> {
+    let mut has_sidecar = false;
+
+    for (encoding, suffix, content_encoding) in SIDECARS {
+        let mut sidecar = target.as_os_str().to_owned();
+        sidecar.push(".");
+        sidecar.push(suffix);
+        let sidecar = PathBuf::from(sidecar);
+
+        if fs::metadata(&sidecar).await.is_err() {
+            continue;
+        }
+        if !state.follow_symlinks {
+            let resolved = fs::canonicalize(&sidecar).await.ok();
+            if let Some(resolved) = resolved.filter(|path| !is_inside_served_dirs(state, path)) {
+                tracing::warn!("skipped sidecar: {}", SymlinkEscape(resolved));
+                continue;
+            }
+        }
+        has_sidecar = true;
+
+        if accepts_encoding(req, encoding) {
+            let file = NamedFile::open_async(&sidecar)
+                .await
+                .map_err(|_| ErrorNotFound("Not Found"))?
+                .set_content_type(mime_guess::from_path(target).first_or_octet_stream())
+                .set_content_encoding(*content_encoding);
+
+            let mut response = file.into_response(req);
+            add_vary_accept_encoding(&mut response);
+            return Ok(response);
+        }
+    }
+
+    // Range requests go to `NamedFile`, which knows how to answer them.
+    let cached = match &state.cache {
+        Some(cache) if !req.headers().contains_key(header::RANGE) => {
+            serve_cached(req, cache, &state.hashes, target).await?
+        }
+        _ => None,
+    };
+    let mut response = match cached {
+        Some(response) => response,
+        None => NamedFile::open_async(target)
             .await
-            .map_err(|_| ErrorNotFound("Not Found"))?;
+            .map_err(|_| ErrorNotFound("Not Found"))?
+            .into_response(req),
+    };
+    if has_sidecar {
+        add_vary_accept_encoding(&mut response);
+    }
+    Ok(response)
+}
+
+/// Serves a small file from the memory cache, reading it into the cache first if needed.
+/// Returns `None` for files that are too large to cache.
+async fn serve_cached(
+    req: &HttpRequest,
+    cache: &AssetCache,
+    hashes: &ContentHashes,
+    target: &Path,
+) -> ActixResult<Option<HttpResponse>> {
+    let metadata = fs::metadata(target)
+        .await
+        .map_err(|_| ErrorNotFound("Not Found"))?;
+    let Ok(modified) = metadata.modified() else {
+        return Ok(None);
+    };
+    if metadata.len() > cache::MAX_ENTRY_BYTES {
+        return Ok(None);
+    }
+
+    let body = match cache.get(target, modified) {
+        Some(body) => body,
+        None => {
+            let body = Bytes::from(
+                fs::read(target)
+                    .await
+                    .map_err(|_| ErrorNotFound("Not Found"))?,
+            );
+            hashes.record(target, &body);
+            cache.insert(target, modified, body.clone());
+            body
+        }
+    };
+    let mime = mime_guess::from_path(target).first_or_octet_stream();
+    Ok(Some(validated_response(
+        req,
+        body,
+        Some(modified),
+        mime.as_ref(),
+    )))
+}
+
+fn accepts_encoding(req: &HttpRequest, encoding: &str) -> bool {
+    req.headers()
+        .get_all(header::ACCEPT_ENCODING)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let rejected = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case(encoding) || name == "*") && !rejected
+        })
+}
+
+fn add_vary_accept_encoding(response: &mut HttpResponse) {
+    response.headers_mut().append(
+        header::VARY,
+        header::HeaderValue::from_static("accept-encoding"),
+    );
+}
+
+/// Builds the response for generated HTML with an `ETag` over the final body and a
+/// `Last-Modified` from the source file, answering revalidation requests with `304`.
+fn html_response(req: &HttpRequest, body: Bytes, modified: Option<SystemTime>) -> HttpResponse {
+    let mut response = validated_response(req, body, modified, "text/html; charset=utf-8");
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static("no-cache"),
+    );
+    response
+}
+
+/// `200` with `ETag` and `Last-Modified`, or `304` when the request already has this body.
+fn validated_response(
+    req: &HttpRequest,
+    body: Bytes,
+    modified: Option<SystemTime>,
+    content_type: &str,
+) -> HttpResponse {
+    let etag = EntityTag::new_strong(content_hash(&body));
+    let modified = modified.map(truncate_to_seconds);
+
+    let not_modified = is_not_modified(req, &etag, modified);
+
+    let mut builder = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    builder.insert_header(ETag(etag));
+    if let Some(modified) = modified {
+        builder.insert_header(LastModified(HttpDate::from(modified)));
+    }
+
+    if not_modified {
+        builder.finish()
+    } else {
+        builder.content_type(content_type).body(body)
+    }
+}
+
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag, modified: Option<SystemTime>) -> bool {
+    // If-None-Match takes precedence over If-Modified-Since (RFC 9110 §13.1.3).
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        return match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        };
+    }
+
+    match (req.get_header::<IfModifiedSince>(), modified) {
+        (Some(IfModifiedSince(since)), Some(modified)) => HttpDate::from(modified) <= since,
+        _ => false,
+    }
+}
+
+fn content_hash(bytes: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:x}-{:016x}", bytes.len(), hasher.finish())
+}
+
+/// HTTP dates have one-second resolution, so compare mtimes at that granularity.
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// Serves compiled output for a Sass or script source, or `None` when `tail` is not one.
+#[tracing::instrument(skip_all, fields(path = tail))]
+async fn serve_compiled(
+    state: &AppState,
+    compiler: &ExternalCompiler,
+    tail: &str,
+) -> ActixResult<Option<HttpResponse>> {
+    let Ok(requested) = resolve_request_path(state, tail) else {
+        return Ok(None);
+    };
+    let Some(source) = compiler.resolve_source(&requested).await else {
+        return Ok(None);
+    };
 
-        Ok(file.into_response(&req))
+    if !state.follow_symlinks {
+        let resolved = fs::canonicalize(&source)
+            .await
+            .map_err(ErrorInternalServerError)?;
+        if !is_inside_served_dirs(state, &resolved) {
+            tracing::warn!(path = tail, "{}", SymlinkEscape(resolved));
+            return Err(ErrorForbidden(
+                "symlink target is outside the served directories",
+            ));
+        }
     }
+
+    let output = compiler.compile(&source).await.map_err(|error| {
+        tracing::error!("{error:#}");
+        ErrorInternalServerError(error)
+    })?;
+    let output = plugin::transform(&state.plugins, &source, output.into_bytes())
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(Some(
+        HttpResponse::Ok()
+            .append_header(("Cache-Control", "no-cache, no-store, must-revalidate"))
+            .content_type(compiler.content_type())
+            .body(output),
+    ))
 }
 
-async fn locate_file(base_dir: &Path, tail: &str) -> anyhow::Result<PathBuf> {
-    let mut full_path = sanitize_path(base_dir, tail)?;
+pub(crate) async fn locate_file(state: &AppState, tail: &str) -> anyhow::Result<PathBuf> {
+    let mut full_path = resolve_request_path(state, tail)?;
 
-    if let Ok(metadata) = fs::metadata(&full_path).await {
+    if state.clean_urls && !tail.ends_with('/') && fs::metadata(&full_path).await.is_err() {
+        let mut with_html = full_path.clone().into_os_string();
+        with_html.push(".html");
+        let with_html = PathBuf::from(with_html);
+        if fs::metadata(&with_html).await.is_ok() {
+            full_path = with_html;
+        }
+    }
+
+    let target = if let Ok(metadata) = fs::metadata(&full_path).await {
         if metadata.is_dir() {
-            let index_html = full_path.join("index.html");
-            if fs::metadata(&index_html).await.is_ok() {
-                full_path = index_html;
-            } else {
-                anyhow::bail!("directory has no index.html");
+            let mut index = None;
+            for candidate in index_candidates(state) {
+                let path = full_path.join(candidate);
+                if fs::metadata(&path).await.is_ok() {
+                    index = Some(path);
+                    break;
+                }
             }
+            index.context("directory has no index file")?
+        } else {
+            full_path
         }
-        Ok(full_path)
     } else {
         anyhow::bail!("file not found")
+    };
+
+    if !state.follow_symlinks {
+        let resolved = fs::canonicalize(&target).await?;
+        if !is_inside_served_dirs(state, &resolved) {
+            return Err(SymlinkEscape(resolved).into());
+        }
     }
+    Ok(target)
 }
 
-fn sanitize_path(base_dir: &Path, tail: &str) -> anyhow::Result<PathBuf> {
-    let trimmed = tail.trim_start_matches('/');
-    let mut target = PathBuf::from(base_dir);
+/// Whether a canonical path lies under the base directory or one of the mounts.
+pub(crate) fn is_inside_served_dirs(state: &AppState, resolved: &Path) -> bool {
+    std::iter::once(&state.base_dir)
+        .chain(state.mounts.iter().map(|mount| &mount.dir))
+        .any(|root| resolved.starts_with(root))
+}
+
+/// A requested file resolves, through a symlink, outside every served directory.
+#[derive(Debug)]
+pub(crate) struct SymlinkEscape(PathBuf);
+
+impl std::fmt::Display for SymlinkEscape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "blocked symlink to {} outside the served directories (pass --follow-symlinks to allow)",
+            self.0.display()
+        )
+    }
+}
+
+impl std::error::Error for SymlinkEscape {}
+
+fn index_candidates(state: &AppState) -> impl Iterator<Item = &str> {
+    let markdown: &[&str] = if state.render_markdown {
+        &["index.md", "README.md"]
+    } else {
+        &[]
+    };
+    state
+        .index_files
+        .iter()
+        .map(String::as_str)
+        .chain(markdown.iter().copied())
+}
 
-    if trimmed.is_empty() {
-        target.push("index.html");
-        return Ok(target);
+/// Maps a request path onto disk, using the longest matching mount or the base directory.
+pub(crate) fn resolve_request_path(state: &AppState, tail: &str) -> anyhow::Result<PathBuf> {
+    let path = format!("/{}", tail.trim_start_matches('/'));
+    for mount in state.mounts.iter() {
+        if let Some(rest) = path.strip_prefix(&mount.prefix)
+            && (rest.is_empty() || rest.starts_with('/'))
+        {
+            return sanitize_path(&mount.dir, rest);
+        }
     }
+    sanitize_path(&state.base_dir, tail)
+}
 
-    let mut has_component = false;
+pub(crate) fn sanitize_path(base_dir: &Path, tail: &str) -> anyhow::Result<PathBuf> {
+    let trimmed = tail.trim_start_matches('/');
+    let mut target = PathBuf::from(base_dir);
 
     for component in Path::new(trimmed).components() {
         match component {
-            Component::Normal(part) => {
-                target.push(part);
-                has_component = true;
-            }
+            Component::Normal(part) => target.push(part),
             Component::CurDir => {}
             _ => anyhow::bail!("invalid path"),
         }
     }
 
-    if !has_component && tail.ends_with('/') {
-        target.push("index.html");
-    }
-
     Ok(target)
 }
 
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "md" | "markdown"))
+        .unwrap_or(false)
+}
+
 fn is_html(path: &Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
@@ -446,43 +2198,58 @@ fn is_html(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
-fn inject_live_client(original: &str, diff_mode: bool) -> anyhow::Result<String> {
+fn expand_includes(state: &AppState, page: &Path, html: String) -> String {
+    let Some(graph) = &state.includes else {
+        return html;
+    };
+
+    let served_dirs = std::iter::once(&state.base_dir)
+        .chain(state.mounts.iter().map(|mount| &mount.dir))
+        .map(PathBuf::as_path);
+    // A page under a mount includes from that mount, the most specific one if they nest.
+    let root = served_dirs
+        .clone()
+        .filter(|dir| page.starts_with(dir))
+        .max_by_key(|dir| dir.as_os_str().len())
+        .unwrap_or(&state.base_dir);
+    let scope = includes::IncludeScope {
+        root,
+        dotfiles: state.dotfiles,
+        served_dirs: (!state.follow_symlinks).then(|| served_dirs.collect()),
+    };
+    let (expanded, partials) = includes::expand(&html, page, &scope);
+    graph.record(page, &partials);
+    expanded
+}
+
+#[tracing::instrument(skip_all, fields(bytes = original.len()))]
+fn inject_live_client(original: &str, state: &AppState) -> anyhow::Result<String> {
     if original.contains("__web_dev_server_client") {
         return Ok(original.to_string());
     }
 
     let config = serde_json::json!({
         "wsPath": "/_live/ws",
-        "diffMode": diff_mode,
+        "eventsPath": "/_live/events",
+        "diffMode": state.diff_mode,
+        "sync": state.sync,
+        "preserveState": state.preserve_state,
+        "cleanUrls": state.clean_urls,
+        "indexFiles": state.index_files.as_slice(),
     });
 
-    let snippet = format!(
+    let tags = format!(
         r#"<script id="__web_dev_server_config">window.__WEB_DEV_SERVER_CONFIG__ = {};</script><script id="__web_dev_server_client" defer src="/_live/script.js"></script>"#,
         serde_json::to_string(&config)?
     );
-
-    if let Some(idx) = original.rfind("</head>") {
-        let mut result = String::with_capacity(original.len() + snippet.len() + 2);
-        result.push_str(&original[..idx]);
-        result.push('\n');
-        result.push_str(&snippet);
-        result.push('\n');
-        result.push_str(&original[idx..]);
-        Ok(result)
-    } else {
-        let mut result = original.to_string();
-        if !result.ends_with('\n') {
-            result.push('\n');
-        }
-        result.push_str(&snippet);
-        Ok(result)
-    }
+    Ok(state.injection.apply(original, &tags))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use notify::event::{AccessKind, DataChange, ModifyKind, RemoveKind, RenameMode};
+    use actix_web::{http::StatusCode, test::TestRequest};
+    use notify::event::{AccessKind, CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode};
 
     #[test]
     fn diff_message_serializes_resource_lowercase() {
@@ -497,6 +2264,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sync_message_serializes_event_kind() {
+        let message = LiveMessage::Sync {
+            origin: 7,
+            event: SyncEvent::Scroll { x: 0.0, y: 0.5 },
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(
+            json.contains(r#""type":"sync""#),
+            "serialized json was {json}"
+        );
+        assert!(
+            json.contains(r#""event":{"kind":"scroll""#),
+            "serialized json was {json}"
+        );
+    }
+
+    #[test]
+    fn html_responses_revalidate_by_etag() {
+        let body = Bytes::from_static(b"<html></html>");
+        let first = html_response(
+            &TestRequest::default().to_http_request(),
+            body.clone(),
+            None,
+        );
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let req = TestRequest::default()
+            .insert_header(("If-None-Match", etag))
+            .to_http_request();
+        assert_eq!(
+            html_response(&req, body.clone(), None).status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        let req = TestRequest::default()
+            .insert_header(("If-None-Match", "\"stale\""))
+            .to_http_request();
+        assert_eq!(html_response(&req, body, None).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn html_responses_revalidate_by_modified_time() {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let req = TestRequest::default()
+            .insert_header(("If-Modified-Since", HttpDate::from(modified).to_string()))
+            .to_http_request();
+        let response = html_response(
+            &req,
+            Bytes::new(),
+            Some(modified + Duration::from_millis(400)),
+        );
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let newer = modified + Duration::from_secs(5);
+        let response = html_response(&req, Bytes::new(), Some(newer));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn accept_encoding_respects_zero_quality() {
+        let req = TestRequest::default()
+            .insert_header(("Accept-Encoding", "gzip;q=0, br;q=0.8"))
+            .to_http_request();
+        assert!(accepts_encoding(&req, "br"));
+        assert!(!accepts_encoding(&req, "gzip"));
+    }
+
     #[test]
     fn access_events_are_ignored_for_diff_mode() {
         assert!(should_ignore_event(&EventKind::Access(AccessKind::Read)));
@@ -526,18 +2368,60 @@ mod tests {
         assert!(should_reload_when_no_diff(&event));
     }
 
+    fn test_state(base_dir: PathBuf) -> AppState {
+        let (tx, _) = broadcast::channel(1);
+        AppState {
+            base_dir,
+            broadcaster: tx,
+            diff_mode: true,
+            sync: false,
+            preserve_state: true,
+            render_markdown: false,
+            clean_urls: false,
+            index_files: Arc::new(vec![String::from("index.html")]),
+            dotfiles: DotfilePolicy::Deny,
+            follow_symlinks: false,
+            cache: None,
+            inject_max_size: 0,
+            injection: Arc::default(),
+            sass: None,
+            transpiler: None,
+            includes: None,
+            vars: Arc::default(),
+            compress: false,
+            no_cache_all: false,
+            cache_rules: Arc::default(),
+            http2: false,
+            writable: false,
+            throttle: None,
+            mocks_dir: None,
+            control_token: None,
+            basic_auth: None,
+            allowed_hosts: Arc::default(),
+            allowed_ips: Arc::default(),
+            cors_origin: None,
+            rules: Arc::default(),
+            faults: Arc::default(),
+            base_path: None,
+            mounts: Arc::default(),
+            watch_paths: Arc::default(),
+            archive: None,
+            verbose_watch: false,
+            clients: Arc::default(),
+            hooks: Arc::default(),
+            access_log: None,
+            hashes: Arc::default(),
+            plugins: Arc::default(),
+        }
+    }
+
     #[test]
     fn relative_paths_are_classified_within_base_dir() {
         let base_dir =
             std::env::temp_dir().join(format!("web_dev_server_test_{}", std::process::id()));
         std::fs::create_dir_all(&base_dir).unwrap();
         let canonical = std::fs::canonicalize(&base_dir).unwrap();
-        let (tx, _) = broadcast::channel(1);
-        let state = AppState {
-            base_dir: canonical,
-            broadcaster: tx,
-            diff_mode: true,
-        };
+        let state = test_state(canonical);
 
         let message = classify_path(&state, Path::new("index.html"))
             .expect("expected diff message for html file");
@@ -549,4 +2433,190 @@ mod tests {
             panic!("expected diff message");
         }
     }
+
+    #[test]
+    fn mounts_map_requests_and_changes_by_prefix() {
+        let mut state = test_state(PathBuf::from("/site"));
+        state.mounts = Arc::new(vec![
+            Mount::new("/assets/icons", "/icons"),
+            Mount::new("/assets", "/shared"),
+        ]);
+
+        assert_eq!(
+            resolve_request_path(&state, "assets/app.css").unwrap(),
+            PathBuf::from("/shared/app.css")
+        );
+        assert_eq!(
+            resolve_request_path(&state, "assets/icons/logo.svg").unwrap(),
+            PathBuf::from("/icons/logo.svg")
+        );
+        assert_eq!(
+            resolve_request_path(&state, "assetsx/app.css").unwrap(),
+            PathBuf::from("/site/assetsx/app.css")
+        );
+        assert_eq!(
+            web_path(&state, Path::new("/shared/app.css"), &DiffResource::Css).as_deref(),
+            Some("/assets/app.css")
+        );
+        assert_eq!(
+            web_path(
+                &state,
+                Path::new("/shared/docs/index.html"),
+                &DiffResource::Html
+            )
+            .as_deref(),
+            Some("/assets/docs/")
+        );
+    }
+
+    #[test]
+    fn clean_urls_drop_the_html_extension_from_diff_paths() {
+        let mut state = test_state(PathBuf::from("/site"));
+        state.clean_urls = true;
+
+        let html = |path: &str| web_path(&state, Path::new(path), &DiffResource::Html);
+        assert_eq!(html("/site/about.html").as_deref(), Some("/about"));
+        assert_eq!(html("/site/docs/intro.htm").as_deref(), Some("/docs/intro"));
+        assert_eq!(html("/site/docs/index.html").as_deref(), Some("/docs/"));
+        assert_eq!(html("/site/index.html").as_deref(), Some("/"));
+        assert_eq!(
+            web_path(&state, Path::new("/site/a.html.css"), &DiffResource::Css).as_deref(),
+            Some("/a.html.css")
+        );
+    }
+
+    #[test]
+    fn configured_index_files_collapse_to_directory_urls() {
+        let mut state = test_state(PathBuf::from("/site"));
+        state.index_files = Arc::new(vec![
+            String::from("index.html"),
+            String::from("default.htm"),
+        ]);
+
+        let html = |path: &str| web_path(&state, Path::new(path), &DiffResource::Html);
+        assert_eq!(
+            html("/site/legacy/default.htm").as_deref(),
+            Some("/legacy/")
+        );
+        assert_eq!(html("/site/default.htm").as_deref(), Some("/"));
+        assert_eq!(html("/site/myindex.html").as_deref(), Some("/myindex.html"));
+        assert_eq!(html("/site/index.htm").as_deref(), Some("/index.htm"));
+    }
+
+    #[test]
+    fn nested_watch_roots_are_collapsed() {
+        let mut state = test_state(PathBuf::from("/repo/site"));
+        state.mocks_dir = Some(PathBuf::from("/repo/site/mocks"));
+        state.mounts = Arc::new(vec![
+            Mount::new("/a", "/repo/packages/ui/dist"),
+            Mount::new("/b", "/repo/packages"),
+        ]);
+
+        assert_eq!(
+            watch_roots(&state),
+            vec![PathBuf::from("/repo/site"), PathBuf::from("/repo/packages")]
+        );
+    }
+
+    #[test]
+    fn changes_under_extra_watch_paths_reload() {
+        let mut state = test_state(PathBuf::from("/repo/dist"));
+        state.watch_paths = Arc::new(vec![PathBuf::from("/repo/src"), PathBuf::from("/repo")]);
+
+        assert!(touches_watch_paths(
+            &state,
+            &[PathBuf::from("/repo/src/main.ts")]
+        ));
+        assert!(!touches_watch_paths(
+            &state,
+            &[PathBuf::from("/repo/dist/main.js")]
+        ));
+        assert_eq!(watch_roots(&state), vec![PathBuf::from("/repo")]);
+    }
+
+    #[test]
+    fn reads_do_not_reload_without_diff_mode() {
+        let mut state = test_state(PathBuf::from("/site"));
+        state.diff_mode = false;
+        let mut rx = state.broadcaster.subscribe();
+
+        let read = notify::Event::new(EventKind::Access(AccessKind::Read))
+            .add_path(PathBuf::from("/site/index.html"));
+        handle_fs_event(state.clone(), read, false);
+        assert!(rx.try_recv().is_err());
+
+        let write = notify::Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(PathBuf::from("/site/index.html"));
+        handle_fs_event(state, write, false);
+        assert!(matches!(rx.try_recv(), Ok(LiveMessage::Reload)));
+    }
+
+    #[tokio::test]
+    async fn saves_that_change_nothing_do_not_reload() {
+        let dir = std::env::temp_dir().join(format!(
+            "web_dev_server_unchanged_save_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let page = dir.join("index.html");
+        std::fs::write(&page, "<p>hi</p>").unwrap();
+
+        let mut state = test_state(dir.clone());
+        state.diff_mode = false;
+        state.hashes.record(&page, b"<p>hi</p>");
+        let mut rx = state.broadcaster.subscribe();
+        let save = || {
+            notify::Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+                .add_path(page.clone())
+        };
+
+        let unchanged = saved_without_changes(&state, &save()).await;
+        handle_fs_event(state.clone(), save(), unchanged);
+        assert!(rx.try_recv().is_err());
+
+        std::fs::write(&page, "<p>bye</p>").unwrap();
+        let unchanged = saved_without_changes(&state, &save()).await;
+        handle_fs_event(state, save(), unchanged);
+        assert!(matches!(rx.try_recv(), Ok(LiveMessage::Reload)));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn removed_files_are_forgotten() {
+        let dir = std::env::temp_dir().join(format!(
+            "web_dev_server_removed_forgotten_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let page = dir.join("index.html");
+        std::fs::write(&page, "<p>hi</p>").unwrap();
+
+        let state = test_state(dir.clone());
+        state.hashes.record(&page, b"<p>hi</p>");
+        let remove = notify::Event::new(EventKind::Remove(RemoveKind::File)).add_path(page.clone());
+        handle_fs_event(state.clone(), remove, false);
+
+        // Coming back with the same content is a new file, not an unchanged save.
+        let create = notify::Event::new(EventKind::Create(CreateKind::File)).add_path(page.clone());
+        assert!(!saved_without_changes(&state, &create).await);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn transpiled_sources_reload_in_diff_mode() {
+        let mut state = test_state(PathBuf::from("/site"));
+        state.diff_mode = true;
+        let mut rx = state.broadcaster.subscribe();
+        let edit = || {
+            notify::Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+                .add_path(PathBuf::from("/site/app.tsx"))
+        };
+
+        handle_fs_event(state.clone(), edit(), false);
+        assert!(rx.try_recv().is_err());
+
+        state.transpiler = Some(Arc::new(Transpiler::new("esbuild")));
+        handle_fs_event(state, edit(), false);
+        assert!(matches!(rx.try_recv(), Ok(LiveMessage::Reload)));
+    }
 }
@@ -0,0 +1,138 @@
+//! Placeholder substitution for served HTML.
+//!
+//! `%%NAME%%` resolves from the `vars` section of the config file and falls back to the
+//! environment; `{{env:NAME}}` always reads the environment. Only variables starting with
+//! [`ENV_PREFIX`] are read, since anyone who can load a page (through `--tunnel`, or after a
+//! `--writable` upload) would otherwise see the server's secrets. Unknown placeholders are
+//! left untouched so they stay visible in the page.
+
+use std::collections::BTreeMap;
+
+/// Prefix of the environment variables pages may read.
+pub const ENV_PREFIX: &str = "PUBLIC_";
+
+/// Replaces placeholders in `html`.
+pub fn substitute(html: &str, vars: &BTreeMap<String, String>) -> String {
+    substitute_from(html, vars, |name| std::env::var(name).ok())
+}
+
+/// [`substitute`] with `env` standing in for the process environment.
+fn substitute_from(
+    html: &str,
+    vars: &BTreeMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> String {
+    substitute_with(html, |name, env_only| {
+        let public_env = || name.starts_with(ENV_PREFIX).then(|| env(name)).flatten();
+        if env_only {
+            public_env()
+        } else {
+            vars.get(name).cloned().or_else(public_env)
+        }
+    })
+}
+
+fn substitute_with(html: &str, lookup: impl Fn(&str, bool) -> Option<String>) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    loop {
+        let percent = rest.find("%%");
+        let braces = rest.find("{{env:");
+        let (start, open, close, env_only) = match (percent, braces) {
+            (Some(p), Some(b)) if b < p => (b, "{{env:", "}}", true),
+            (Some(p), _) => (p, "%%", "%%", false),
+            (None, Some(b)) => (b, "{{env:", "}}", true),
+            (None, None) => break,
+        };
+
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + open.len()..];
+        let value = after_open
+            .find(close)
+            .map(|end| &after_open[..end])
+            .filter(|name| is_var_name(name))
+            .and_then(|name| lookup(name, env_only).map(|value| (name.len(), value)));
+
+        match value {
+            Some((name_len, value)) => {
+                output.push_str(&value);
+                rest = &after_open[name_len + close.len()..];
+            }
+            None => {
+                output.push_str(open);
+                rest = after_open;
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn is_var_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str, env_only: bool) -> Option<String> {
+        match (name, env_only) {
+            ("API_URL", false) => Some("http://localhost:8080".into()),
+            ("HOME_DIR", true) => Some("/home/dev".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn replaces_both_placeholder_styles() {
+        let html = r#"<meta content="%%API_URL%%"><p>{{env:HOME_DIR}}</p>"#;
+        assert_eq!(
+            substitute_with(html, lookup),
+            r#"<meta content="http://localhost:8080"><p>/home/dev</p>"#
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_and_malformed_placeholders() {
+        let html = "100%% sure %%MISSING%% {{env:API_URL}} %%API_URL%%";
+        assert_eq!(
+            substitute_with(html, lookup),
+            "100%% sure %%MISSING%% {{env:API_URL}} http://localhost:8080"
+        );
+    }
+
+    #[test]
+    fn reads_public_env_only() {
+        let env = |name: &str| match name {
+            "PUBLIC_API_URL" => Some(String::from("https://api.test")),
+            "PATH" => Some(String::from("/usr/bin")),
+            _ => None,
+        };
+        // No config file: the environment still fills in public variables.
+        assert_eq!(
+            substitute_from(
+                "{{env:PUBLIC_API_URL}} %%PUBLIC_API_URL%% 50%% off %%PATH%% {{env:PATH}}",
+                &BTreeMap::new(),
+                env
+            ),
+            "https://api.test https://api.test 50%% off %%PATH%% {{env:PATH}}"
+        );
+
+        let mut vars = BTreeMap::new();
+        vars.insert("TITLE".to_owned(), "Demo".to_owned());
+        assert_eq!(
+            substitute_from("%%TITLE%% %%PATH%% {{env:PATH}}", &vars, env),
+            "Demo %%PATH%% {{env:PATH}}"
+        );
+    }
+
+    #[test]
+    fn config_vars_take_precedence() {
+        let mut vars = BTreeMap::new();
+        vars.insert("PATH".to_owned(), "from-config".to_owned());
+        assert_eq!(substitute("%%PATH%%", &vars), "from-config");
+    }
+}
@@ -0,0 +1,152 @@
+//! Slow-network simulation behind `--throttle`.
+//!
+//! Each request waits for the profile's latency before it is handled, and the response body
+//! is paced to the profile's bandwidth. Live-reload traffic under `/_live` is left alone so
+//! the page still refreshes promptly.
+
+use std::{
+    pin::Pin,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    Error,
+    body::{BodySize, BoxBody, MessageBody, SizedStream},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::{self, Bytes},
+};
+use futures_util::{StreamExt, future::poll_fn, stream};
+
+use crate::startup::AppState;
+
+/// Bandwidth and per-request latency to emulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttle {
+    pub kbps: u32,
+    pub latency: Duration,
+}
+
+impl Throttle {
+    /// Chrome DevTools' "Fast 3G" preset.
+    pub const FAST_3G: Self = Self::new(1440, 563);
+    /// Chrome DevTools' "Slow 3G" preset.
+    pub const SLOW_3G: Self = Self::new(400, 2000);
+
+    pub const fn new(kbps: u32, latency_ms: u64) -> Self {
+        Self {
+            kbps,
+            latency: Duration::from_millis(latency_ms),
+        }
+    }
+
+    fn bytes_per_second(&self) -> f64 {
+        f64::from(self.kbps) * 1000.0 / 8.0
+    }
+
+    /// Slice size that takes roughly 50ms to send.
+    fn slice_len(&self) -> usize {
+        ((self.bytes_per_second() / 20.0) as usize).max(64)
+    }
+}
+
+impl FromStr for Throttle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "3g" => return Ok(Self::FAST_3G),
+            "slow" | "slow-3g" => return Ok(Self::SLOW_3G),
+            _ => {}
+        }
+
+        let invalid = || format!("expected 3g, slow, or <kbps>,<latency-ms>, got `{value}`");
+        let (kbps, latency) = value.split_once(',').ok_or_else(invalid)?;
+        let kbps: u32 = kbps.trim().parse().map_err(|_| invalid())?;
+        let latency: u64 = latency.trim().parse().map_err(|_| invalid())?;
+        if kbps == 0 {
+            return Err(String::from("throttle bandwidth must be at least 1 kbps"));
+        }
+        Ok(Self::new(kbps, latency))
+    }
+}
+
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let throttle = req
+        .app_data::<web::Data<AppState>>()
+        .and_then(|state| state.throttle);
+    let Some(throttle) = throttle else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    if req.path().starts_with("/_live/") {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    tokio::time::sleep(throttle.latency).await;
+    let response = next.call(req).await?.map_into_boxed_body();
+
+    Ok(response.map_body(|_, body| match body.size() {
+        BodySize::Sized(size) => BoxBody::new(SizedStream::new(size, paced(body, throttle))),
+        BodySize::Stream => BoxBody::new(actix_web::body::BodyStream::new(paced(body, throttle))),
+        BodySize::None => body,
+    }))
+}
+
+/// Re-emits `body` in small slices, sleeping so the cumulative rate matches `throttle`.
+fn paced(
+    body: BoxBody,
+    throttle: Throttle,
+) -> impl futures_util::Stream<Item = Result<Bytes, Error>> + 'static {
+    struct Pacer {
+        body: BoxBody,
+        pending: Bytes,
+        started: Instant,
+        sent: usize,
+    }
+
+    let pacer = Pacer {
+        body,
+        pending: Bytes::new(),
+        started: Instant::now(),
+        sent: 0,
+    };
+
+    stream::unfold(pacer, move |mut pacer| async move {
+        if pacer.pending.is_empty() {
+            let next = poll_fn(|cx| Pin::new(&mut pacer.body).poll_next(cx)).await;
+            match next? {
+                Ok(chunk) => pacer.pending = chunk,
+                Err(error) => return Some((Err(Error::from(error)), pacer)),
+            }
+        }
+
+        let len = pacer.pending.len().min(throttle.slice_len());
+        let slice = pacer.pending.split_to(len);
+        pacer.sent += len;
+        let due = Duration::from_secs_f64(pacer.sent as f64 / throttle.bytes_per_second());
+        if let Some(wait) = due.checked_sub(pacer.started.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+
+        Some((Ok(slice), pacer))
+    })
+    .boxed_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_parse_presets_and_custom_values() {
+        assert_eq!("3G".parse(), Ok(Throttle::FAST_3G));
+        assert_eq!("slow".parse(), Ok(Throttle::SLOW_3G));
+        assert_eq!("256, 300".parse(), Ok(Throttle::new(256, 300)));
+        assert!("fast".parse::<Throttle>().is_err());
+        assert!("0,100".parse::<Throttle>().is_err());
+    }
+}
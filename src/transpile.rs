@@ -0,0 +1,93 @@
+//! On-the-fly TypeScript and JSX transpilation for `--transpile`.
+//!
+//! Requests for `.ts`, `.tsx`, and `.jsx` files are passed through an external esbuild
+//! executable and served as JavaScript. Imports are left alone, so sources reference each
+//! other by their real names (`./util.ts`) or, as TypeScript recommends for ES modules, by
+//! the `.js` name, which falls back to the source when no such file exists.
+
+use std::{ops::Deref, path::Path};
+
+use crate::compiler::{ExternalCompiler, Language};
+
+/// Transpiles script sources with esbuild and caches the output by modification time.
+pub struct Transpiler(ExternalCompiler);
+
+impl Transpiler {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self(ExternalCompiler::new(
+            command,
+            Language {
+                sources: &["ts", "tsx", "jsx"],
+                output: "js",
+                content_type: "text/javascript; charset=utf-8",
+                // Without `--bundle` esbuild transforms the one file and keeps its imports
+                // as written.
+                args: |command, source| {
+                    command
+                        .arg(source)
+                        .arg("--format=esm")
+                        .arg("--sourcemap=inline")
+                        .arg("--log-level=error");
+                },
+            },
+        ))
+    }
+
+    /// Drops cached output for `changed`, returning whether it was a script source.
+    pub fn invalidate(&self, changed: &Path) -> bool {
+        if !self.is_source(changed) {
+            return false;
+        }
+        self.0.evict(changed);
+        true
+    }
+}
+
+impl Deref for Transpiler {
+    type Target = ExternalCompiler;
+
+    fn deref(&self) -> &ExternalCompiler {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_typescript_and_jsx_are_sources() {
+        let transpiler = Transpiler::new("esbuild");
+        assert!(transpiler.is_source(Path::new("/site/app.ts")));
+        assert!(transpiler.is_source(Path::new("/site/App.TSX")));
+        assert!(transpiler.is_source(Path::new("/site/widget.jsx")));
+        assert!(!transpiler.is_source(Path::new("/site/app.js")));
+        assert!(!transpiler.is_source(Path::new("/site/ts")));
+    }
+
+    #[tokio::test]
+    async fn missing_js_files_fall_back_to_their_source() {
+        let dir =
+            std::env::temp_dir().join(format!("web_dev_server_transpile_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.tsx"), "export const x = <p/>;").unwrap();
+        std::fs::write(dir.join("vendor.js"), "").unwrap();
+        std::fs::write(dir.join("vendor.ts"), "").unwrap();
+
+        let transpiler = Transpiler::new("esbuild");
+        assert_eq!(
+            transpiler.resolve_source(&dir.join("app.js")).await,
+            Some(dir.join("app.tsx"))
+        );
+        assert_eq!(
+            transpiler.resolve_source(&dir.join("app.tsx")).await,
+            Some(dir.join("app.tsx"))
+        );
+        assert_eq!(
+            transpiler.resolve_source(&dir.join("vendor.js")).await,
+            None
+        );
+        assert_eq!(transpiler.resolve_source(&dir.join("gone.ts")).await, None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
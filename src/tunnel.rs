@@ -0,0 +1,232 @@
+//! Public URLs through an external tunnel client (`--tunnel`).
+//!
+//! The client is started after the port is bound and its output is scanned for the public
+//! URL it prints. The process lives as long as the [`Tunnel`] and is killed with it.
+
+use std::{net::SocketAddr, process::Stdio, str::FromStr, time::Duration};
+
+use anyhow::Context;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    sync::mpsc,
+};
+
+/// How long to wait for the client to report its public URL.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TunnelProvider {
+    /// `cloudflared tunnel --url`, a free `trycloudflare.com` quick tunnel.
+    Cloudflared,
+    /// `ngrok http`, using the account configured for ngrok.
+    Ngrok,
+    /// `npx localtunnel`, a `loca.lt` URL.
+    Localtunnel,
+    /// `ssh -R` to localhost.run; only needs an SSH client.
+    LocalhostRun,
+    /// Any command that prints an `https://` URL, with `{port}` and `{url}` substituted.
+    Command(String),
+}
+
+impl FromStr for TunnelProvider {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cloudflared" | "cloudflare" => Ok(Self::Cloudflared),
+            "ngrok" => Ok(Self::Ngrok),
+            "localtunnel" | "lt" => Ok(Self::Localtunnel),
+            "localhost.run" | "ssh" => Ok(Self::LocalhostRun),
+            _ if value.contains(char::is_whitespace) => Ok(Self::Command(value.trim().into())),
+            _ => Err(format!(
+                "unknown tunnel `{value}`; use cloudflared, ngrok, localtunnel, localhost.run, or a command such as \"bore local {{port}} --to bore.pub\""
+            )),
+        }
+    }
+}
+
+impl TunnelProvider {
+    /// Program and arguments that expose `local` publicly.
+    fn command(&self, local: SocketAddr) -> Vec<String> {
+        let port = local.port().to_string();
+        let url = format!("http://{local}");
+        match self {
+            Self::Cloudflared => vec!["cloudflared".into(), "tunnel".into(), "--url".into(), url],
+            Self::Ngrok => ["ngrok", "http", &url, "--log", "stdout"]
+                .map(String::from)
+                .into(),
+            Self::Localtunnel => vec![
+                "npx".into(),
+                "--yes".into(),
+                "localtunnel".into(),
+                "--port".into(),
+                port,
+                "--local-host".into(),
+                local.ip().to_string(),
+            ],
+            Self::LocalhostRun => vec![
+                "ssh".into(),
+                "-o".into(),
+                "StrictHostKeyChecking=accept-new".into(),
+                "-o".into(),
+                "ServerAliveInterval=30".into(),
+                "-R".into(),
+                format!("80:{local}"),
+                "nokey@localhost.run".into(),
+            ],
+            Self::Command(command) => command
+                .split_whitespace()
+                .map(|word| word.replace("{port}", &port).replace("{url}", &url))
+                .collect(),
+        }
+    }
+
+    /// Whether `url` from the client's output is the public URL rather than, say, a link to
+    /// documentation in its banner.
+    fn is_public_url(&self, url: &str) -> bool {
+        let host = url
+            .trim_start_matches("https://")
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default();
+        match self {
+            Self::Cloudflared => host.ends_with(".trycloudflare.com"),
+            Self::Ngrok => host.contains(".ngrok"),
+            Self::Localtunnel => host.ends_with(".loca.lt"),
+            Self::LocalhostRun => host.ends_with(".lhr.life") || host.ends_with(".localhost.run"),
+            Self::Command(_) => !host.is_empty(),
+        }
+    }
+}
+
+/// A running tunnel client.
+pub struct Tunnel {
+    url: String,
+    _child: Child,
+}
+
+impl Tunnel {
+    /// Public URL that forwards to the server.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Host name of [`Tunnel::url`], accepted by the `Host` header check.
+    pub fn host(&self) -> &str {
+        self.url
+            .trim_start_matches("https://")
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// Starts the client for `provider` and waits until it reports a public URL for `local`.
+pub async fn start(provider: &TunnelProvider, local: SocketAddr) -> anyhow::Result<Tunnel> {
+    let words = provider.command(local);
+    let (program, args) = words.split_first().context("tunnel command is empty")?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run `{program}`"))?;
+
+    // Both streams are drained for the life of the process so the client never blocks on a
+    // full pipe.
+    let (lines_tx, mut lines) = mpsc::unbounded_channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, lines_tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, lines_tx);
+    }
+
+    let found = tokio::time::timeout(STARTUP_TIMEOUT, async {
+        while let Some(line) = lines.recv().await {
+            tracing::debug!(line, "tunnel output");
+            if let Some(url) = find_url(&line).filter(|url| provider.is_public_url(url)) {
+                return Some(url.to_string());
+            }
+        }
+        None
+    })
+    .await;
+
+    match found {
+        Ok(Some(url)) => {
+            tokio::spawn(async move {
+                while let Some(line) = lines.recv().await {
+                    tracing::debug!(line, "tunnel output");
+                }
+            });
+            Ok(Tunnel { url, _child: child })
+        }
+        Ok(None) => anyhow::bail!("`{program}` exited without printing a public URL"),
+        Err(_) => anyhow::bail!(
+            "`{program}` did not print a public URL within {}s",
+            STARTUP_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+fn forward_lines(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    tx: mpsc::UnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// First `https://` URL in a line of output, without surrounding quotes or punctuation.
+fn find_url(line: &str) -> Option<&str> {
+    let start = line.find("https://")?;
+    let rest = &line[start..];
+    let end = rest
+        .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '|' | '<' | '>'))
+        .unwrap_or(rest.len());
+    Some(rest[..end].trim_end_matches(['.', ',']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_urls_are_found_in_client_output() {
+        let cloudflared = TunnelProvider::Cloudflared;
+        let banner = "2024-05-01 INF |  https://www.cloudflare.com/website-terms/  |";
+        assert!(!find_url(banner).is_some_and(|url| cloudflared.is_public_url(url)));
+        let line = "2024-05-01 INF |  https://quiet-river-1234.trycloudflare.com                |";
+        assert_eq!(
+            find_url(line).filter(|url| cloudflared.is_public_url(url)),
+            Some("https://quiet-river-1234.trycloudflare.com")
+        );
+
+        let ngrok = "t=2024 lvl=info msg=\"started tunnel\" url=https://ab12.ngrok-free.app";
+        assert!(find_url(ngrok).is_some_and(|url| TunnelProvider::Ngrok.is_public_url(url)));
+
+        let localhost_run =
+            "a1b2c3.lhr.life tunneled with tls termination, https://a1b2c3.lhr.life";
+        assert_eq!(find_url(localhost_run), Some("https://a1b2c3.lhr.life"));
+    }
+
+    #[test]
+    fn commands_substitute_the_port() {
+        let provider: TunnelProvider = "bore local {port} --to bore.pub".parse().unwrap();
+        assert_eq!(
+            provider.command("127.0.0.1:4000".parse().unwrap()),
+            ["bore", "local", "4000", "--to", "bore.pub"]
+        );
+        assert!("nonsense".parse::<TunnelProvider>().is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Several sites in one process, from the `sites` section of the config file:
+//!
+//! ```json
+//! { "sites": [
+//!     { "name": "docs", "base_dir": "./docs", "port": 4000 },
+//!     { "name": "app", "base_dir": "./app/dist", "base_path": "/app/" }
+//! ] }
+//! ```
+//!
+//! Each site is its own [`Application`] built from the command-line settings with the site's
+//! base directory, port, and base path. A site without a port takes the one after the
+//! previous site's. Settings that name a single resource get one per site: the `--uds`
+//! socket and `--access-log` file are suffixed with the site name (`dev.docs.sock`), and
+//! only the first site opens the `--tunnel`. All sites stop together on Ctrl+C or through
+//! [`Workspace::handle`].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::{
+    config::DevServerConfig,
+    startup::{Application, ShutdownHandle},
+};
+
+pub struct Site {
+    pub name: String,
+    pub app: Application,
+}
+
+pub struct Workspace {
+    sites: Vec<Site>,
+}
+
+impl Workspace {
+    /// Starts every site in `config.file.sites`, in order.
+    pub async fn build(config: &DevServerConfig) -> anyhow::Result<Self> {
+        // Site directories are relative to the config file that lists them.
+        let config_dir = config
+            .config
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+
+        let mut sites = Vec::new();
+        let mut next_port = config.port;
+        for site in &config.file.sites {
+            let mut site_config = config.clone();
+            site_config.file.sites.clear();
+            site_config.base_dir = config_dir.join(&site.base_dir);
+            site_config.port = site.port.unwrap_or(next_port);
+            if site.base_path.is_some() {
+                site_config.base_path = site.base_path.clone();
+            }
+
+            let name = site.name.clone().unwrap_or_else(|| {
+                site.base_dir.file_name().map_or_else(
+                    || site.base_dir.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                )
+            });
+            site_config.uds = config.uds.as_deref().map(|path| per_site(path, &name));
+            site_config.access_log = config
+                .access_log
+                .as_deref()
+                .map(|path| per_site(path, &name));
+            if !sites.is_empty() {
+                site_config.tunnel = None;
+            }
+            let app = Application::build(&site_config)
+                .await
+                .with_context(|| format!("failed to start site {name}"))?;
+            if site_config.port != 0 {
+                next_port = app.port().saturating_add(1);
+            }
+            sites.push(Site { name, app });
+        }
+
+        if sites.is_empty() {
+            anyhow::bail!("the config file lists no sites");
+        }
+        Ok(Self { sites })
+    }
+
+    pub fn sites(&self) -> &[Site] {
+        &self.sites
+    }
+
+    /// Handle that stops every site at once.
+    pub fn handle(&self) -> ShutdownHandle {
+        self.sites.iter().map(|site| site.app.handle()).collect()
+    }
+
+    /// Serves every site until they are stopped; see [`Application::run_until_stopped`].
+    pub async fn run_until_stopped(self) -> std::io::Result<()> {
+        let results = futures_util::future::join_all(
+            self.sites
+                .into_iter()
+                .map(|site| site.app.run_until_stopped()),
+        )
+        .await;
+        results.into_iter().collect()
+    }
+}
+
+/// `path` with the site name before its extension: `logs/access.log` → `logs/access.docs.log`.
+fn per_site(path: &Path, site: &str) -> PathBuf {
+    let site: String = site
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}.{site}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{site}"),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_site_paths_keep_the_extension() {
+        assert_eq!(
+            per_site(Path::new("logs/access.log"), "docs"),
+            PathBuf::from("logs/access.docs.log")
+        );
+        assert_eq!(
+            per_site(Path::new("/tmp/dev"), "my app"),
+            PathBuf::from("/tmp/dev.my-app")
+        );
+    }
+}
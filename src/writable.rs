@@ -0,0 +1,163 @@
+//! `PUT` and `DELETE` behind `--writable`, for design tools and scripts that push files into
+//! the live preview.
+//!
+//! `PUT /img/logo.svg` writes the request body to `<base>/img/logo.svg`, creating missing
+//! directories, and `DELETE` removes the file. Paths go through the same sanitization,
+//! mounts, dotfile policy, and symlink rules as reads; the watcher then reloads pages as it
+//! would for an edit made in an editor.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use actix_web::{
+    HttpResponse, Result as ActixResult,
+    error::{
+        ErrorBadRequest, ErrorConflict, ErrorForbidden, ErrorInternalServerError,
+        ErrorPayloadTooLarge,
+    },
+    web,
+};
+use futures_util::StreamExt;
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::startup::{AppState, is_inside_served_dirs, resolve_request_path};
+
+/// Largest body `PUT` accepts, so a runaway client cannot fill the disk.
+pub const MAX_UPLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Tells concurrent uploads of the same file apart.
+static UPLOAD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Writes the body to the requested path: `201 Created` for a new file, `204 No Content`
+/// when one was replaced, `413` for bodies over [`MAX_UPLOAD_SIZE`].
+///
+/// The body is streamed into a temporary file next to the target and renamed over it, so
+/// the watcher never reloads pages with a half-written file.
+pub async fn put(
+    tail: web::Path<String>,
+    payload: web::Payload,
+    state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    let target = writable_target(&state, tail.as_str()).await?;
+    if fs::metadata(&target).await.is_ok_and(|meta| meta.is_dir()) {
+        return Err(ErrorConflict("a directory exists at this path"));
+    }
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        return Err(ErrorBadRequest("a file path is required"));
+    };
+    fs::create_dir_all(parent)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    let upload = parent.join(format!(
+        ".{}.upload-{}-{}",
+        name.to_string_lossy(),
+        std::process::id(),
+        UPLOAD_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let bytes = match receive(payload, &upload).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            let _ = fs::remove_file(&upload).await;
+            return Err(error);
+        }
+    };
+
+    let existed = fs::metadata(&target).await.is_ok();
+    if let Err(error) = fs::rename(&upload, &target).await {
+        let _ = fs::remove_file(&upload).await;
+        return Err(ErrorInternalServerError(error));
+    }
+
+    tracing::info!(path = %target.display(), bytes, "file written");
+    Ok(if existed {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::Created().finish()
+    })
+}
+
+/// Streams the request body into `path`, returning its size.
+async fn receive(mut payload: web::Payload, path: &Path) -> ActixResult<u64> {
+    let mut file = fs::File::create(path)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let mut bytes = 0;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        bytes += chunk.len() as u64;
+        if bytes > MAX_UPLOAD_SIZE {
+            return Err(ErrorPayloadTooLarge("upload is too large"));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(ErrorInternalServerError)?;
+    }
+    file.flush().await.map_err(ErrorInternalServerError)?;
+    Ok(bytes)
+}
+
+/// Removes the requested file: `204 No Content`, or `404` when there is none.
+pub async fn delete(
+    tail: web::Path<String>,
+    state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    let target = writable_target(&state, tail.as_str()).await?;
+    match fs::metadata(&target).await {
+        Ok(meta) if meta.is_dir() => return Err(ErrorConflict("cannot delete a directory")),
+        Ok(_) => {}
+        Err(_) => return Ok(HttpResponse::NotFound().body("Not Found")),
+    }
+
+    fs::remove_file(&target)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    tracing::info!(path = %target.display(), "file deleted");
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Maps a request path to the file it may write, rejecting hidden paths (per `--dotfiles`)
+/// and locations that resolve outside the served directories.
+async fn writable_target(state: &AppState, tail: &str) -> ActixResult<PathBuf> {
+    if tail.is_empty() || tail.ends_with('/') {
+        return Err(ErrorBadRequest("a file path is required"));
+    }
+    if state.dotfiles.check(tail).is_some() {
+        return Err(ErrorForbidden("hidden paths cannot be written"));
+    }
+    let target = resolve_request_path(state, tail).map_err(|_| ErrorBadRequest("invalid path"))?;
+
+    if !state.follow_symlinks {
+        let resolved = canonicalize_existing(&target).await;
+        if !is_inside_served_dirs(state, &resolved) {
+            return Err(ErrorForbidden(
+                "path resolves outside the served directories",
+            ));
+        }
+    }
+    Ok(target)
+}
+
+/// Canonicalizes the deepest existing ancestor of `path` and appends the rest, so symlinks
+/// are resolved for files and directories that do not exist yet.
+async fn canonicalize_existing(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(resolved) = fs::canonicalize(current).await {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |resolved, part| resolved.join(part));
+        }
+        match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                current = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+use web_dev_server::startup::Application;
+
+fn get_with_host(address: std::net::SocketAddr, host: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[tokio::test]
+async fn unknown_host_headers_are_rejected() {
+    let site = common::temp_site("access");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .allow_host("dev.test")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (rebound, allowed, local) = tokio::task::spawn_blocking(move || {
+        (
+            get_with_host(address, "attacker.example:3000"),
+            get_with_host(address, "dev.test:3000"),
+            get_with_host(address, &format!("localhost:{}", address.port())),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(rebound.starts_with("HTTP/1.1 403"), "got {rebound}");
+    assert!(allowed.starts_with("HTTP/1.1 200"), "got {allowed}");
+    assert!(local.starts_with("HTTP/1.1 200"), "got {local}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use web_dev_server::{access_log::AccessLogFormat, config::RuleConfig, startup::Application};
+
+#[tokio::test]
+async fn requests_are_written_in_combined_format() {
+    let site = common::temp_site("access_log");
+    // The log lives outside the site so writing it does not trigger the watcher.
+    let log = common::temp_site("access_log_out").join("access.log");
+    let _ = std::fs::remove_file(&log);
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .rule(RuleConfig {
+            from: String::from("/old"),
+            to: String::from("/index.html"),
+            status: 200,
+            force: false,
+        })
+        .access_log(&log, AccessLogFormat::Combined)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.access_log().unwrap().path(), log);
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    tokio::task::spawn_blocking(move || {
+        common::request(
+            address,
+            "GET",
+            "/index.html?v=1",
+            &[
+                ("Referer", "http://localhost/start"),
+                ("User-Agent", "curl/8.0 \"test\""),
+            ],
+        );
+        common::get(address, "/old");
+        common::get(address, "/missing.png");
+    })
+    .await
+    .unwrap();
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 3, "got {contents}");
+
+    let page = lines[0];
+    assert!(page.starts_with("127.0.0.1 - - ["), "got {page}");
+    assert!(page.contains(" +0000] \"GET /index.html?v=1 HTTP/1.1\" 200 "));
+    assert!(page.ends_with(" \"http://localhost/start\" \"curl/8.0 \\\"test\\\"\""));
+
+    // The original path is logged, not what the rule rewrote it to.
+    assert!(
+        lines[1].contains("\"GET /old HTTP/1.1\" 200 "),
+        "got {}",
+        lines[1]
+    );
+    assert!(lines[2].contains("\"GET /missing.png HTTP/1.1\" 404 "));
+    assert!(lines[2].ends_with(" \"-\" \"-\""), "got {}", lines[2]);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+/// Builds a zip of stored (uncompressed) entries. CRCs are left at zero; the server does not
+/// check them.
+fn zip(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for (name, body) in entries {
+        let offset = out.len() as u32;
+        let (name_len, size) = (name.len() as u16, body.len() as u32);
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&name_len.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(body.as_bytes());
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        directory.extend_from_slice(&size.to_le_bytes());
+        directory.extend_from_slice(&size.to_le_bytes());
+        directory.extend_from_slice(&name_len.to_le_bytes());
+        directory.extend_from_slice(&[0; 12]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = out.len() as u32;
+    let count = entries.len() as u16;
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&directory_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+#[tokio::test]
+async fn serves_zip_entries_with_their_mime_types() {
+    let dir = common::temp_site("archive_zip");
+    let bundle = dir.join("demo.zip");
+    std::fs::write(
+        &bundle,
+        zip(&[
+            (
+                "demo/index.html",
+                "<html><head></head><body>bundled</body></html>",
+            ),
+            ("demo/js/app.js", "console.log('bundled')"),
+        ]),
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&bundle)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(
+        app.archive(),
+        Some(bundle.canonicalize().unwrap().as_path())
+    );
+    let unpacked = app.base_dir().to_path_buf();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (page, script) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/"),
+            common::request(address, "GET", "/js/app.js", &[]),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(page.contains("bundled"), "got {page}");
+    assert!(page.contains("/_live/script.js"), "got {page}");
+    let headers = common::header_block(&script);
+    assert!(
+        headers.contains("content-type: text/javascript"),
+        "got {headers}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+    assert!(!unpacked.exists(), "{} was left behind", unpacked.display());
+}
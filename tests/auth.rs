@@ -0,0 +1,47 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn basic_auth_guards_everything_but_health() {
+    let site = common::temp_site("auth");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .basic_auth("dev", "s3cret")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (anonymous, wrong, authorized, health) = tokio::task::spawn_blocking(move || {
+        let request = |headers: &[(&str, &str)], path| {
+            String::from_utf8_lossy(&common::request(address, "GET", path, headers)).into_owned()
+        };
+        (
+            request(&[], "/"),
+            request(&[("Authorization", "Basic ZGV2Ondyb25n")], "/"),
+            request(&[("Authorization", "Basic ZGV2OnMzY3JldA==")], "/"),
+            request(&[], "/_live/health"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(anonymous.starts_with("HTTP/1.1 401"), "got {anonymous}");
+    assert!(
+        anonymous
+            .to_ascii_lowercase()
+            .contains("www-authenticate: basic realm="),
+        "got {anonymous}"
+    );
+    assert!(wrong.starts_with("HTTP/1.1 401"), "got {wrong}");
+    assert!(authorized.starts_with("HTTP/1.1 200"), "got {authorized}");
+    assert!(health.starts_with("HTTP/1.1 200"), "got {health}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
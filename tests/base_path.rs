@@ -0,0 +1,47 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn site_is_served_under_the_base_path() {
+    let site = common::temp_site("base_path");
+    std::fs::write(site.join("app.css"), "body {}").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .base_path("/my-repo/")
+        .build()
+        .await
+        .unwrap();
+
+    assert!(app.primary_url().ends_with("/my-repo/"));
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (root, page, asset, outside, health) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/"),
+            common::get(address, "/my-repo/"),
+            common::get(address, "/my-repo/app.css"),
+            common::get(address, "/app.css"),
+            common::get(address, "/_live/health"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(root.starts_with("HTTP/1.1 302"), "got {root}");
+    assert!(
+        root.to_ascii_lowercase().contains("location: /my-repo/"),
+        "got {root}"
+    );
+    assert!(page.contains("<title>t</title>"), "got {page}");
+    assert!(asset.contains("body {}"), "got {asset}");
+    assert!(outside.starts_with("HTTP/1.1 404"), "got {outside}");
+    assert!(health.starts_with("HTTP/1.1 200"), "got {health}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn builder_serves_without_cli_arguments() {
+    let site = common::temp_site("builder");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .diff_mode(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    assert_ne!(address.port(), 0);
+    assert!(app.diff_mode());
+
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let health = tokio::task::spawn_blocking(move || common::get(address, "/_live/health"))
+        .await
+        .unwrap();
+    assert!(health.starts_with("HTTP/1.1 200"), "response was {health}");
+
+    let index = tokio::task::spawn_blocking(move || common::get(address, "/"))
+        .await
+        .unwrap();
+    assert!(
+        index.contains("__web_dev_server_client"),
+        "response was {index}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn zero_broadcast_capacity_is_rejected() {
+    let site = common::temp_site("builder_capacity");
+    let result = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .broadcast_capacity(0)
+        .build()
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use web_dev_server::{config::CacheRuleConfig, startup::Application};
+
+fn rule(path: Option<&str>, extensions: &[&str], value: &str) -> CacheRuleConfig {
+    CacheRuleConfig {
+        path: path.map(str::to_owned),
+        extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        value: value.to_owned(),
+    }
+}
+
+fn cache_control(response: &str) -> Option<String> {
+    response
+        .lines()
+        .find_map(|line| line.strip_prefix("cache-control: "))
+        .map(str::to_owned)
+}
+
+#[tokio::test]
+async fn rules_set_cache_control_for_matching_files() {
+    let site = common::temp_site("cache_policy");
+    std::fs::create_dir_all(site.join("assets")).unwrap();
+    std::fs::write(site.join("assets/app.js"), "console.log(1)").unwrap();
+    std::fs::write(site.join("logo.svg"), "<svg/>").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .cache_control(rule(
+            Some("/assets/*"),
+            &[],
+            "public, max-age=31536000, immutable",
+        ))
+        .cache_control(rule(None, &["svg"], "max-age=60"))
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.cache_rules(), 2);
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (asset, image, page, missing) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/assets/app.js"),
+            common::get(address, "/logo.svg"),
+            common::get(address, "/index.html"),
+            common::get(address, "/assets/gone.js"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+        cache_control(&asset).as_deref(),
+        Some("public, max-age=31536000, immutable")
+    );
+    assert_eq!(cache_control(&image).as_deref(), Some("max-age=60"));
+    assert_eq!(cache_control(&page).as_deref(), Some("no-cache"));
+    assert!(missing.starts_with("HTTP/1.1 404"), "got {missing}");
+    assert_eq!(cache_control(&missing), None);
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn no_cache_all_overrides_every_rule() {
+    let site = common::temp_site("cache_policy_all");
+    std::fs::write(site.join("logo.svg"), "<svg/>").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .cache_control(rule(None, &["svg"], "max-age=60"))
+        .no_cache_all(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (image, page) = tokio::task::spawn_blocking(move || {
+        (common::get(address, "/logo.svg"), common::get(address, "/"))
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(cache_control(&image).as_deref(), Some("no-store"));
+    assert_eq!(cache_control(&page).as_deref(), Some("no-store"));
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use web_dev_server::startup::Application;
+
+fn read_until(stream: &mut TcpStream, needle: &str) -> String {
+    let mut received = Vec::new();
+    let mut buf = [0; 1024];
+    while !String::from_utf8_lossy(&received).contains(needle) {
+        let read = stream.read(&mut buf).expect("event stream ended early");
+        assert!(read > 0, "event stream closed before {needle:?}");
+        received.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8_lossy(&received).into_owned()
+}
+
+#[tokio::test]
+async fn saves_without_changes_do_not_reload_through_the_watcher() {
+    let site = common::temp_site("changes");
+    let style = site.join("style.css");
+    std::fs::write(&style, "body { color: red }").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let received = tokio::task::spawn_blocking(move || {
+        // Serving the stylesheet records its content.
+        let served = common::get(address, "/style.css");
+        assert!(served.ends_with("body { color: red }"), "got {served}");
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nAccept: text/event-stream\r\n\r\n"
+        )
+        .unwrap();
+        let mut received = read_until(&mut stream, "clientId");
+
+        std::fs::write(&style, "body { color: red }").unwrap();
+        // Long enough for the watcher to deliver the event and wait for the file to settle.
+        stream
+            .set_read_timeout(Some(Duration::from_millis(1500)))
+            .unwrap();
+        let mut buf = [0; 1024];
+        if let Ok(read) = stream.read(&mut buf) {
+            received.push_str(&String::from_utf8_lossy(&buf[..read]));
+        }
+        assert!(!received.contains("reload"), "got {received}");
+
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        std::fs::write(&style, "body { color: blue }").unwrap();
+        read_until(&mut stream, r#"data: {"type":"reload"}"#)
+    })
+    .await
+    .unwrap();
+    assert!(received.contains("reload"), "got {received}");
+
+    handle.stop(false).await;
+    server.await.unwrap().unwrap();
+}
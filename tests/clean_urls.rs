@@ -0,0 +1,47 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn extensionless_paths_resolve_to_html_files() {
+    let site = common::temp_site("clean_urls");
+    std::fs::create_dir_all(site.join("docs")).unwrap();
+    std::fs::write(site.join("about.html"), "<html><body>about</body></html>").unwrap();
+    std::fs::write(
+        site.join("docs/index.html"),
+        "<html><body>docs</body></html>",
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .clean_urls(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (about, docs, missing) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/about"),
+            common::get(address, "/docs/"),
+            common::get(address, "/about/"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(
+        about.starts_with("HTTP/1.1 200") && about.contains("about"),
+        "got {about}"
+    );
+    assert!(docs.contains("docs"), "got {docs}");
+    assert!(missing.starts_with("HTTP/1.1 404"), "got {missing}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
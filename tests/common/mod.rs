@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+};
+
+pub fn temp_site(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("web_dev_server_{name}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("index.html"),
+        "<html><head><title>t</title></head><body>hi</body></html>",
+    )
+    .unwrap();
+    dir
+}
+
+/// Sends a raw HTTP/1.1 request and returns the full response, headers included.
+pub fn request(address: SocketAddr, method: &str, path: &str, headers: &[(&str, &str)]) -> Vec<u8> {
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut head = format!("{method} {path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    response
+}
+
+pub fn get(address: SocketAddr, path: &str) -> String {
+    String::from_utf8_lossy(&request(address, "GET", path, &[])).into_owned()
+}
+
+/// Response headers as lowercase `name: value` lines.
+pub fn header_block(response: &[u8]) -> String {
+    let end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap_or(response.len());
+    String::from_utf8_lossy(&response[..end]).to_ascii_lowercase()
+}
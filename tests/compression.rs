@@ -0,0 +1,84 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn injected_html_is_compressed_when_enabled() {
+    let site = common::temp_site("compression");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .compress(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let response = tokio::task::spawn_blocking(move || {
+        common::request(address, "GET", "/", &[("Accept-Encoding", "gzip")])
+    })
+    .await
+    .unwrap();
+    let headers = common::header_block(&response);
+    assert!(
+        headers.contains("content-encoding: gzip"),
+        "headers were {headers}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn precompressed_sidecars_are_served_when_accepted() {
+    let site = common::temp_site("sidecar");
+    std::fs::write(site.join("app.js"), "console.log('plain');").unwrap();
+    std::fs::write(site.join("app.js.gz"), b"\x1f\x8bnot-really-gzip").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (accepted, plain) = tokio::task::spawn_blocking(move || {
+        (
+            common::request(address, "GET", "/app.js", &[("Accept-Encoding", "gzip")]),
+            common::request(address, "GET", "/app.js", &[]),
+        )
+    })
+    .await
+    .unwrap();
+
+    let headers = common::header_block(&accepted);
+    assert!(
+        headers.contains("content-encoding: gzip"),
+        "headers were {headers}"
+    );
+    assert!(headers.contains("javascript"), "headers were {headers}");
+    assert!(
+        headers.contains("vary: accept-encoding"),
+        "headers were {headers}"
+    );
+
+    let headers = common::header_block(&plain);
+    assert!(
+        !headers.contains("content-encoding: gzip"),
+        "headers were {headers}"
+    );
+    assert!(
+        headers.contains("vary: accept-encoding"),
+        "headers were {headers}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
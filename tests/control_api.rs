@@ -0,0 +1,154 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn control_endpoints_require_the_configured_token() {
+    let site = common::temp_site("control_api");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .control_token("secret")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (missing, wrong, ok, diff, empty_diff) = tokio::task::spawn_blocking(move || {
+        (
+            common::request(address, "POST", "/_live/reload", &[]),
+            common::request(
+                address,
+                "POST",
+                "/_live/reload",
+                &[("Authorization", "Bearer nope")],
+            ),
+            common::request(
+                address,
+                "POST",
+                "/_live/reload",
+                &[("Authorization", "Bearer secret")],
+            ),
+            common::request(address, "POST", "/_live/diff", &[]),
+            common::request(
+                address,
+                "POST",
+                "/_live/diff",
+                &[("Authorization", "Bearer secret")],
+            ),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(missing.starts_with(b"HTTP/1.1 401"));
+    // The body is not looked at before the token is checked.
+    assert!(diff.starts_with(b"HTTP/1.1 401"));
+    assert!(empty_diff.starts_with(b"HTTP/1.1 400"));
+    assert!(wrong.starts_with(b"HTTP/1.1 401"));
+    assert!(ok.starts_with(b"HTTP/1.1 200"));
+    assert!(String::from_utf8_lossy(&ok).contains(r#"{"delivered":0}"#));
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn control_endpoints_are_hidden_without_a_token() {
+    let site = common::temp_site("control_api_disabled");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (response, diff) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/_live/clients"),
+            common::request(address, "POST", "/_live/diff", &[]),
+        )
+    })
+    .await
+    .unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 404"),
+        "response was {response}"
+    );
+    assert!(diff.starts_with(b"HTTP/1.1 404"));
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn clients_endpoint_lists_connected_sessions() {
+    let site = common::temp_site("control_api_clients");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .control_token("secret")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let connected = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /_live/events?page=%2Fdocs%2F HTTP/1.1\r\nHost: {address}\r\nUser-Agent: test-agent\r\n\r\n"
+        )
+        .unwrap();
+        let mut buf = [0; 1024];
+        let mut received = String::new();
+        while !received.contains("clientId") {
+            let read = stream.read(&mut buf).unwrap();
+            assert!(read > 0, "event stream closed early");
+            received.push_str(&String::from_utf8_lossy(&buf[..read]));
+        }
+        common::request(
+            address,
+            "GET",
+            "/_live/clients",
+            &[("Authorization", "Bearer secret")],
+        )
+    })
+    .await
+    .unwrap();
+
+    let connected = String::from_utf8_lossy(&connected);
+    assert!(connected.contains(r#""count":1"#), "got {connected}");
+    assert!(connected.contains(r#""page":"/docs/""#), "got {connected}");
+    assert!(
+        connected.contains(r#""transport":"sse""#),
+        "got {connected}"
+    );
+    assert!(
+        connected.contains(r#""userAgent":"test-agent""#),
+        "got {connected}"
+    );
+    assert!(connected.contains(r#""ip":"127.0.0.1""#), "got {connected}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn cors_headers_and_preflight() {
+    let site = common::temp_site("cors");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .cors("http://localhost:5173")
+        .basic_auth("dev", "s3cret")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (preflight, simple) = tokio::task::spawn_blocking(move || {
+        (
+            common::request(
+                address,
+                "OPTIONS",
+                "/index.html",
+                &[
+                    ("Origin", "http://localhost:5173"),
+                    ("Access-Control-Request-Method", "GET"),
+                    ("Access-Control-Request-Headers", "x-requested-with"),
+                ],
+            ),
+            common::request(
+                address,
+                "GET",
+                "/index.html",
+                &[
+                    ("Origin", "http://localhost:5173"),
+                    ("Authorization", "Basic ZGV2OnMzY3JldA=="),
+                ],
+            ),
+        )
+    })
+    .await
+    .unwrap();
+
+    let preflight = common::header_block(&preflight);
+    assert!(preflight.starts_with("http/1.1 204"), "got {preflight}");
+    assert!(
+        preflight.contains("access-control-allow-origin: http://localhost:5173"),
+        "got {preflight}"
+    );
+    assert!(
+        preflight.contains("access-control-allow-headers: x-requested-with"),
+        "got {preflight}"
+    );
+
+    let simple = common::header_block(&simple);
+    assert!(simple.starts_with("http/1.1 200"), "got {simple}");
+    assert!(
+        simple.contains("access-control-allow-credentials: true"),
+        "got {simple}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use web_dev_server::{dotfiles::DotfilePolicy, startup::Application};
+
+async fn fetch_env(name: &str, policy: Option<DotfilePolicy>) -> (String, String) {
+    let site = common::temp_site(name);
+    std::fs::write(site.join(".env"), "SECRET=hunter2").unwrap();
+    std::fs::create_dir_all(site.join(".well-known")).unwrap();
+    std::fs::write(site.join(".well-known/security.txt"), "Contact: me").unwrap();
+
+    let mut builder = Application::builder().port(0).base_dir(&site);
+    if let Some(policy) = policy {
+        builder = builder.dotfiles(policy);
+    }
+    let app = builder.build().await.unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let responses = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/.env"),
+            common::get(address, "/.well-known/security.txt"),
+        )
+    })
+    .await
+    .unwrap();
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+    responses
+}
+
+#[tokio::test]
+async fn dotfiles_are_denied_by_default() {
+    let (env, well_known) = fetch_env("dotfiles_deny", None).await;
+    assert!(env.starts_with("HTTP/1.1 403"), "got {env}");
+    assert!(!env.contains("hunter2"));
+    assert!(well_known.contains("Contact: me"), "got {well_known}");
+}
+
+#[tokio::test]
+async fn dotfile_policy_can_hide_or_allow() {
+    let (ignored, _) = fetch_env("dotfiles_ignore", Some(DotfilePolicy::Ignore)).await;
+    assert!(ignored.starts_with("HTTP/1.1 404"), "got {ignored}");
+
+    let (allowed, _) = fetch_env("dotfiles_allow", Some(DotfilePolicy::Allow)).await;
+    assert!(allowed.contains("SECRET=hunter2"), "got {allowed}");
+}
@@ -0,0 +1,247 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use web_dev_server::startup::Application;
+
+fn read_until(stream: &mut TcpStream, needle: &str) -> String {
+    let mut received = Vec::new();
+    let mut buf = [0; 1024];
+    while !String::from_utf8_lossy(&received).contains(needle) {
+        let read = stream.read(&mut buf).expect("event stream ended early");
+        assert!(read > 0, "event stream closed before {needle:?}");
+        received.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8_lossy(&received).into_owned()
+}
+
+#[tokio::test]
+async fn event_stream_announces_client_and_relays_reloads() {
+    let site = common::temp_site("events");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .control_token("secret")
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let received = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nAccept: text/event-stream\r\n\r\n"
+        )
+        .unwrap();
+        let hello = read_until(&mut stream, "clientId");
+
+        let reload = common::request(
+            address,
+            "POST",
+            "/_live/reload",
+            &[("Authorization", "Bearer secret")],
+        );
+        assert!(String::from_utf8_lossy(&reload).contains(r#"{"delivered":1}"#));
+
+        (hello, read_until(&mut stream, r#"data: {"type":"reload"}"#))
+    })
+    .await
+    .unwrap();
+
+    assert!(
+        received.0.contains("text/event-stream"),
+        "got {}",
+        received.0
+    );
+    assert!(received.0.contains("event: hello"), "got {}", received.0);
+
+    handle.stop(false).await;
+    server.await.unwrap().unwrap();
+}
+
+/// Posts a client message to `/_live/events` and returns the status line.
+fn post_message(address: std::net::SocketAddr, client: &str, content_type: &str) -> String {
+    let body = r#"{"type":"ping"}"#;
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "POST /_live/events?client={client} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n\
+         Content-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response.lines().next().unwrap_or_default().to_owned()
+}
+
+#[tokio::test]
+async fn client_messages_need_json_and_a_connected_stream() {
+    let site = common::temp_site("events_messages");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let statuses = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nAccept: text/event-stream\r\n\r\n"
+        )
+        .unwrap();
+        let hello = read_until(&mut stream, "}");
+        let id = hello
+            .split("\"clientId\":")
+            .nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .unwrap()
+            .to_owned();
+
+        [
+            post_message(address, &id, "application/json"),
+            // A form or `text/plain` post needs no preflight, so any site could send it.
+            post_message(address, &id, "text/plain"),
+            post_message(address, "999999", "application/json"),
+        ]
+    })
+    .await
+    .unwrap();
+
+    assert!(statuses[0].starts_with("HTTP/1.1 204"), "got {statuses:?}");
+    assert!(statuses[1].starts_with("HTTP/1.1 415"), "got {statuses:?}");
+    assert!(statuses[2].starts_with("HTTP/1.1 403"), "got {statuses:?}");
+
+    handle.stop(false).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn stopping_the_server_notifies_event_streams() {
+    let site = common::temp_site("events_shutdown");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (connected_tx, connected_rx) = tokio::sync::oneshot::channel();
+    let reader = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nAccept: text/event-stream\r\n\r\n"
+        )
+        .unwrap();
+        read_until(&mut stream, "clientId");
+        connected_tx.send(()).unwrap();
+        read_until(&mut stream, "0\r\n\r\n")
+    });
+
+    connected_rx.await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), handle.stop(true))
+        .await
+        .expect("graceful stop waited on the event stream");
+
+    let received = reader.await.unwrap();
+    assert!(
+        received.contains(r#"data: {"type":"shutdown"}"#),
+        "got {received}"
+    );
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn live_channels_refuse_foreign_origins() {
+    let site = common::temp_site("events_origin");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (socket, stream, post, own) = tokio::task::spawn_blocking(move || {
+        let status = |path: &str, method: &str, headers: &[(&str, &str)]| {
+            let response = common::request(address, method, path, headers);
+            let response = String::from_utf8_lossy(&response).into_owned();
+            response.lines().next().unwrap_or_default().to_owned()
+        };
+        let evil = ("Origin", "http://evil.example");
+        let own_origin = format!("http://{address}");
+        (
+            status(
+                "/_live/ws",
+                "GET",
+                &[
+                    evil,
+                    ("Connection", "Upgrade"),
+                    ("Upgrade", "websocket"),
+                    ("Sec-WebSocket-Version", "13"),
+                    ("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="),
+                ],
+            ),
+            status("/_live/events", "GET", &[evil]),
+            status(
+                "/_live/events?client=1",
+                "POST",
+                &[evil, ("Content-Type", "application/json")],
+            ),
+            {
+                // The page's own origin still gets its event stream.
+                let mut stream = TcpStream::connect(address).unwrap();
+                stream
+                    .set_read_timeout(Some(Duration::from_secs(5)))
+                    .unwrap();
+                write!(
+                    stream,
+                    "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nOrigin: {own_origin}\r\n\r\n"
+                )
+                .unwrap();
+                read_until(&mut stream, "clientId")
+            },
+        )
+    })
+    .await
+    .unwrap();
+
+    for refused in [&socket, &stream, &post] {
+        assert!(refused.starts_with("HTTP/1.1 403"), "got {refused}");
+    }
+    assert!(own.starts_with("HTTP/1.1 200"), "got {own}");
+
+    handle.stop(false).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use web_dev_server::{config::FaultConfig, startup::Application};
+
+fn fault(path: &str, status: Option<u16>, drop: bool) -> FaultConfig {
+    FaultConfig {
+        path: path.to_owned(),
+        method: None,
+        probability: 1.0,
+        status,
+        timeout: false,
+        drop,
+    }
+}
+
+#[tokio::test]
+async fn matching_requests_fail_as_configured() {
+    let site = common::temp_site("faults");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .fault(fault("/api/*", Some(503), false))
+        .fault(fault("/flaky", None, true))
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (failed, dropped, page) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/api/users"),
+            common::get(address, "/flaky"),
+            common::get(address, "/"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(failed.starts_with("HTTP/1.1 503"), "got {failed}");
+    assert!(!dropped.ends_with("0\r\n\r\n"), "got {dropped:?}");
+    assert!(page.starts_with("HTTP/1.1 200"), "got {page}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
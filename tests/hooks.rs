@@ -0,0 +1,110 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use web_dev_server::{hooks::HookEvent, startup::Application};
+
+/// Polls until a hook has written `path`, returning its contents.
+fn wait_for_file(path: &Path) -> String {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Ok(contents) = std::fs::read_to_string(path)
+            && contents.ends_with('\n')
+        {
+            return contents;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "{} never appeared",
+            path.display()
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[tokio::test]
+async fn hooks_run_with_event_details() {
+    let site = common::temp_site("hooks");
+    // Hook output lives outside the site so it does not trigger the watcher itself.
+    let out = common::temp_site("hooks_out");
+    let sh = |name: &str, line: &str| format!("echo \"{line}\" > '{}'", out.join(name).display());
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .hook(
+            HookEvent::Start,
+            sh("start", "$WEB_DEV_SERVER_EVENT $WEB_DEV_SERVER_URL"),
+        )
+        .hook(
+            HookEvent::ClientConnect,
+            sh(
+                "client",
+                "$WEB_DEV_SERVER_CLIENT_TRANSPORT $WEB_DEV_SERVER_CLIENTS",
+            ),
+        )
+        .hook(HookEvent::Change, sh("change", "$WEB_DEV_SERVER_PATH"))
+        .hook(
+            HookEvent::Shutdown,
+            sh("shutdown", "$WEB_DEV_SERVER_EVENT $WEB_DEV_SERVER_CLIENTS"),
+        )
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.hooks().events().count(), 4);
+
+    let address = app.address();
+    let url = app.primary_url();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let watched = site.join("style.css");
+    let (start, client, change, stream) = tokio::task::spawn_blocking({
+        let out = out.clone();
+        move || {
+            let start = wait_for_file(&out.join("start"));
+
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            write!(
+                stream,
+                "GET /_live/events HTTP/1.1\r\nHost: {address}\r\nAccept: text/event-stream\r\n\r\n"
+            )
+            .unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            let client = wait_for_file(&out.join("client"));
+
+            std::fs::write(&watched, "body { color: red }").unwrap();
+            let change = wait_for_file(&out.join("change"));
+            (start, client, change, stream)
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(start.trim(), format!("start {url}"));
+    assert_eq!(client.trim(), "sse 1");
+    assert!(change.trim().ends_with("style.css"), "got {change}");
+
+    // Not graceful, since the open event stream would hold a graceful stop back.
+    handle.stop(false).await;
+    server.await.unwrap().unwrap();
+    drop(stream);
+    // The shutdown hook is waited for before the server returns, and counts the client that
+    // was still connected when the stop began.
+    assert_eq!(
+        std::fs::read_to_string(out.join("shutdown"))
+            .unwrap()
+            .trim(),
+        "shutdown 1"
+    );
+}
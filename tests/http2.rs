@@ -0,0 +1,69 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use web_dev_server::startup::Application;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+/// An empty SETTINGS frame: 3-byte length, type 0x4, no flags, stream 0.
+const EMPTY_SETTINGS: &[u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
+
+/// Opens an HTTP/2 connection with prior knowledge and returns the first bytes the server sends.
+fn h2_handshake(address: SocketAddr) -> Vec<u8> {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream.write_all(PREFACE).unwrap();
+    stream.write_all(EMPTY_SETTINGS).unwrap();
+
+    let mut buf = [0; 64];
+    let read = stream.read(&mut buf).unwrap_or(0);
+    buf[..read].to_vec()
+}
+
+async fn handshake(name: &str, http2: bool) -> (Vec<u8>, String) {
+    let site = common::temp_site(name);
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .http2(http2)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let result = tokio::task::spawn_blocking(move || {
+        (h2_handshake(address), common::get(address, "/index.html"))
+    })
+    .await
+    .unwrap();
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+    result
+}
+
+#[tokio::test]
+async fn http2_prior_knowledge_is_accepted_with_the_flag() {
+    let (frame, http1) = handshake("http2_enabled", true).await;
+    assert!(frame.len() >= 9, "got {frame:?}");
+    assert_eq!(frame[3], 4, "expected a SETTINGS frame, got {frame:?}");
+    assert!(http1.starts_with("HTTP/1.1 200"), "got {http1}");
+}
+
+#[tokio::test]
+async fn http2_is_not_spoken_by_default() {
+    let (frame, _) = handshake("http2_disabled", false).await;
+    assert!(
+        frame.is_empty() || frame.starts_with(b"HTTP/1.1"),
+        "got {frame:?}"
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn directory_urls_try_each_index_file_in_order() {
+    let site = common::temp_site("index_files");
+    std::fs::remove_file(site.join("index.html")).unwrap();
+    std::fs::create_dir_all(site.join("legacy")).unwrap();
+    std::fs::write(site.join("index.htm"), "<html><body>root htm</body></html>").unwrap();
+    std::fs::write(
+        site.join("legacy/default.htm"),
+        "<html><body>legacy default</body></html>",
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .index_files(["index.html", "index.htm", "default.htm"])
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (root, legacy) = tokio::task::spawn_blocking(move || {
+        (common::get(address, "/"), common::get(address, "/legacy/"))
+    })
+    .await
+    .unwrap();
+
+    assert!(root.contains("root htm"), "got {root}");
+    assert!(
+        legacy.starts_with("HTTP/1.1 200") && legacy.contains("legacy default"),
+        "got {legacy}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
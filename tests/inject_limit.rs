@@ -0,0 +1,39 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn pages_over_the_limit_are_served_untouched() {
+    let site = common::temp_site("inject_limit");
+    let report = format!("<html><body>{}</body></html>", "<p>row</p>".repeat(100));
+    std::fs::write(site.join("report.html"), &report).unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .inject_max_size(512)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (small, large) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/index.html"),
+            common::get(address, "/report.html"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(small.contains("/_live/script.js"), "got {small}");
+    assert!(large.starts_with("HTTP/1.1 200"), "got {large}");
+    assert!(!large.contains("/_live/script.js"));
+    assert!(large.ends_with(&report));
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
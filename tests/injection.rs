@@ -0,0 +1,66 @@
+mod common;
+
+use web_dev_server::{inject::InjectPosition, startup::Application};
+
+#[tokio::test]
+async fn client_placement_skips_and_snippets_are_configurable() {
+    let site = common::temp_site("injection");
+    std::fs::write(
+        site.join("page.html"),
+        "<html><head><title>t</title></head><body><main>hi</main></body></html>",
+    )
+    .unwrap();
+    std::fs::create_dir_all(site.join("embed")).unwrap();
+    std::fs::write(site.join("embed/widget.html"), "<div>widget</div>").unwrap();
+    let snippet = site.join("snippet.html");
+    std::fs::write(&snippet, "<script src=\"/overlay.js\"></script>\n").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .inject_position(InjectPosition::BodyEnd)
+        .no_inject("/embed/*")
+        .inject_snippet(&snippet)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.inject_position(), InjectPosition::BodyEnd);
+    assert!(app.inject_snippet());
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (page, widget) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/page.html"),
+            common::get(address, "/embed/widget.html"),
+        )
+    })
+    .await
+    .unwrap();
+
+    let body = page.split("\r\n\r\n").nth(1).unwrap();
+    let client = body.find("/_live/script.js").expect("client injected");
+    let overlay = body.find("/overlay.js").expect("snippet injected");
+    assert!(body.find("</main>").unwrap() < client, "got {body}");
+    assert!(client < overlay && overlay < body.find("</body>").unwrap());
+
+    assert!(widget.starts_with("HTTP/1.1 200"), "got {widget}");
+    assert!(widget.ends_with("<div>widget</div>"), "got {widget}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn missing_snippet_fails_at_startup() {
+    let site = common::temp_site("injection_missing");
+    let result = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .inject_snippet(site.join("nope.html"))
+        .build()
+        .await;
+    assert!(result.is_err());
+}
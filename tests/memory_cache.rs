@@ -0,0 +1,73 @@
+mod common;
+
+use std::{
+    fs::File,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use web_dev_server::startup::Application;
+
+/// Rewrites `path` and moves its modification time forward, so the change is visible even on
+/// filesystems with coarse timestamps.
+fn rewrite(path: &Path, contents: &str) {
+    std::fs::write(path, contents).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(5))
+        .unwrap();
+}
+
+#[tokio::test]
+async fn changed_files_are_not_served_from_the_cache() {
+    let site = common::temp_site("memory_cache");
+    std::fs::write(site.join("app.js"), "console.log('first')").unwrap();
+    std::fs::write(site.join("page.html"), "<p>first page</p>").unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+    assert!(app.memory_cache());
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (before, after) = tokio::task::spawn_blocking(move || {
+        let before = (
+            common::get(address, "/app.js"),
+            common::get(address, "/page.html"),
+        );
+        // Served twice so the second response comes from memory.
+        let cached = common::get(address, "/app.js");
+        assert!(cached.contains("console.log('first')"), "got {cached}");
+
+        rewrite(&site.join("app.js"), "console.log('second')");
+        rewrite(&site.join("page.html"), "<p>second page</p>");
+        let after = (
+            common::get(address, "/app.js"),
+            common::get(address, "/page.html"),
+        );
+        (before, after)
+    })
+    .await
+    .unwrap();
+
+    assert!(
+        before.0.contains("console.log('first')"),
+        "got {}",
+        before.0
+    );
+    assert!(before.0.contains("etag:"), "got {}", before.0);
+    assert!(before.1.contains("first page"), "got {}", before.1);
+    assert!(after.0.contains("console.log('second')"), "got {}", after.0);
+    assert!(after.1.contains("second page"), "got {}", after.1);
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
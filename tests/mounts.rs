@@ -0,0 +1,95 @@
+mod common;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn mounted_directories_are_served_under_their_prefix() {
+    let site = common::temp_site("mounts");
+    let shared = site.with_file_name(format!(
+        "web_dev_server_mounts_shared_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(shared.join("docs")).unwrap();
+    std::fs::write(shared.join("app.css"), "body { color: red; }").unwrap();
+    std::fs::write(
+        shared.join("docs/index.html"),
+        "<html><head></head><body>docs</body></html>",
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .mount("/assets/", &shared)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (css, page, root) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/assets/app.css"),
+            common::get(address, "/assets/docs/"),
+            common::get(address, "/"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(css.contains("body { color: red; }"), "got {css}");
+    assert!(
+        page.contains("docs") && page.contains("/_live/script.js"),
+        "got {page}"
+    );
+    assert!(root.contains("<title>t</title>"), "got {root}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn mounted_pages_include_from_their_own_directory() {
+    let site = common::temp_site("mounts_includes");
+    std::fs::create_dir_all(site.join("partials")).unwrap();
+    std::fs::write(site.join("partials/nav.html"), "<nav>site</nav>").unwrap();
+    let shared = site.with_file_name(format!(
+        "web_dev_server_mounts_includes_shared_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(shared.join("partials")).unwrap();
+    std::fs::write(shared.join("partials/nav.html"), "<nav>docs</nav>").unwrap();
+    std::fs::write(shared.join("partials/footer.html"), "<footer>docs</footer>").unwrap();
+    std::fs::write(
+        shared.join("index.html"),
+        "<html><head></head><body><!--#include virtual=\"/partials/nav.html\" -->\
+         <!--#include file=\"partials/footer.html\" --></body></html>",
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .mount("/docs", &shared)
+        .includes(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let page = tokio::task::spawn_blocking(move || common::get(address, "/docs/"))
+        .await
+        .unwrap();
+    assert!(
+        page.contains("<nav>docs</nav><footer>docs</footer>"),
+        "got {page}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
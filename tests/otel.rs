@@ -0,0 +1,115 @@
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    time::Duration,
+};
+
+use web_dev_server::{
+    logging::{self, LogFormat},
+    otel::{self, OtlpEndpoint},
+    startup::Application,
+};
+
+/// Accepts OTLP posts and forwards each JSON body.
+fn fake_collector() -> (OtlpEndpoint, mpsc::Receiver<serde_json::Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap())
+        .parse()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut length = 0;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let _ = reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+            let _ = tx.send(serde_json::from_slice(&body).unwrap());
+        }
+    });
+    (endpoint, rx)
+}
+
+fn spans(batch: &serde_json::Value) -> Vec<serde_json::Value> {
+    batch["resourceSpans"][0]["scopeSpans"][0]["spans"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn request_spans_are_exported_with_their_children() {
+    let (endpoint, batches) = fake_collector();
+    let log = std::env::temp_dir().join(format!("web_dev_server_otel_{}.log", std::process::id()));
+    logging::init(
+        LogFormat::Text,
+        tracing::Level::WARN,
+        Some(&log),
+        Some(&endpoint),
+    )
+    .unwrap();
+
+    let site = common::temp_site("otel");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let page = tokio::task::spawn_blocking(move || common::get(address, "/index.html"))
+        .await
+        .unwrap();
+    assert!(page.starts_with("HTTP/1.1 200"), "got {page}");
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+
+    tokio::task::spawn_blocking(otel::flush).await.unwrap();
+    let mut exported = Vec::new();
+    while let Ok(batch) = batches.recv_timeout(Duration::from_millis(500)) {
+        exported.extend(spans(&batch));
+    }
+
+    let find = |name: &str| {
+        exported
+            .iter()
+            .find(|span| span["name"] == name)
+            .unwrap_or_else(|| panic!("no {name} span in {exported:?}"))
+            .clone()
+    };
+    let request = find("request");
+    let serve = find("serve_file");
+    let render = find("render_page");
+    assert_eq!(request["kind"], 2);
+    assert_eq!(serve["traceId"], request["traceId"]);
+    assert_eq!(serve["parentSpanId"], request["spanId"]);
+    assert_eq!(render["parentSpanId"], serve["spanId"]);
+    assert!(
+        request["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|attribute| attribute["key"] == "http.response.status_code"
+                && attribute["value"]["intValue"] == "200")
+    );
+    let _ = std::fs::remove_file(log);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::net::TcpListener;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn taken_ports_fall_back_within_the_range() {
+    let site = common::temp_site("ports_range");
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+    let last = port.saturating_add(50);
+
+    let app = Application::builder()
+        .port(port)
+        .port_range(port, last)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    assert_ne!(app.port(), port);
+    assert!((port..=last).contains(&app.port()), "got {}", app.port());
+}
+
+#[tokio::test]
+async fn strict_port_fails_when_taken() {
+    let site = common::temp_site("ports_strict");
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let error = Application::builder()
+        .port(port)
+        .strict_port(true)
+        .base_dir(&site)
+        .build()
+        .await
+        .err()
+        .expect("a taken port should fail with --strict-port");
+    assert!(error.to_string().contains("--strict-port"), "got {error}");
+}
+
+#[tokio::test]
+async fn exhausted_ranges_report_an_error() {
+    let site = common::temp_site("ports_exhausted");
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let error = Application::builder()
+        .port(port)
+        .port_range(port, port)
+        .base_dir(&site)
+        .build()
+        .await
+        .err()
+        .expect("a range with no free port should fail");
+    assert!(error.to_string().contains("in use"), "got {error}");
+}
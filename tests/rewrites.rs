@@ -0,0 +1,100 @@
+mod common;
+
+use web_dev_server::{config::RuleConfig, startup::Application};
+
+fn rule(from: &str, to: &str, status: u16) -> RuleConfig {
+    RuleConfig {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        status,
+        force: false,
+    }
+}
+
+#[tokio::test]
+async fn rules_redirect_and_rewrite_missing_paths() {
+    let site = common::temp_site("rewrites");
+    std::fs::write(site.join("about.html"), "<html><body>about</body></html>").unwrap();
+    std::fs::write(
+        site.join("my page.html"),
+        "<html><body>spaced</body></html>",
+    )
+    .unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .rule(rule("/old/:page", "/new/:page", 301))
+        .rule(rule("/*", "/index.html", 200))
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (redirect, fallback, existing, encoded) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/old/contact?ref=nav"),
+            common::get(address, "/app/settings"),
+            common::get(address, "/about.html"),
+            common::get(address, "/my%20page.html"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(redirect.starts_with("HTTP/1.1 301"), "got {redirect}");
+    assert!(
+        redirect
+            .to_ascii_lowercase()
+            .contains("location: /new/contact?ref=nav"),
+        "got {redirect}"
+    );
+    assert!(fallback.contains("<title>t</title>"), "got {fallback}");
+    assert!(existing.contains("about"), "got {existing}");
+    assert!(encoded.contains("spaced"), "got {encoded}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn catch_all_rules_leave_mock_endpoints_alone() {
+    let site = common::temp_site("rewrites_mocks");
+    let mocks = site.with_file_name(format!(
+        "web_dev_server_rewrites_mocks_fixtures_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(mocks.join("GET/api")).unwrap();
+    std::fs::write(mocks.join("GET/api/users.json"), r#"[{"id":1}]"#).unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .mocks(&mocks)
+        .rule(rule("/*", "/index.html", 200))
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (api, route) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/api/users"),
+            common::get(address, "/dashboard"),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(api.ends_with(r#"[{"id":1}]"#), "got {api}");
+    assert!(route.contains("<title>t</title>"), "got {route}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,98 @@
+#![cfg(unix)]
+
+mod common;
+
+use web_dev_server::startup::Application;
+
+async fn fetch_through_links(name: &str, follow: bool) -> (String, String) {
+    let site = common::temp_site(name);
+    let outside = std::env::temp_dir().join(format!(
+        "web_dev_server_{name}_outside_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("secret.txt"), "outside the site").unwrap();
+    std::fs::write(site.join("real.txt"), "inside the site").unwrap();
+    for (link, target) in [
+        ("escape.txt", outside.join("secret.txt")),
+        ("alias.txt", site.join("real.txt")),
+    ] {
+        let _ = std::fs::remove_file(site.join(link));
+        std::os::unix::fs::symlink(target, site.join(link)).unwrap();
+    }
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .follow_symlinks(follow)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let responses = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/escape.txt"),
+            common::get(address, "/alias.txt"),
+        )
+    })
+    .await
+    .unwrap();
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+    responses
+}
+
+#[tokio::test]
+async fn symlinks_leaving_the_site_are_blocked() {
+    let (escape, alias) = fetch_through_links("symlinks_blocked", false).await;
+    assert!(escape.starts_with("HTTP/1.1 403"), "got {escape}");
+    assert!(!escape.contains("outside the site"));
+    assert!(alias.contains("inside the site"), "got {alias}");
+}
+
+#[tokio::test]
+async fn follow_symlinks_serves_outside_targets() {
+    let (escape, _) = fetch_through_links("symlinks_followed", true).await;
+    assert!(escape.contains("outside the site"), "got {escape}");
+}
+
+#[tokio::test]
+async fn sidecars_leaving_the_site_are_skipped() {
+    let site = common::temp_site("symlinks_sidecar");
+    let outside = common::temp_site("symlinks_sidecar_outside").join("secret.gz");
+    std::fs::write(&outside, "outside the site").unwrap();
+    std::fs::write(site.join("app.js"), "inside the site").unwrap();
+    let _ = std::fs::remove_file(site.join("app.js.gz"));
+    std::os::unix::fs::symlink(&outside, site.join("app.js.gz")).unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let response = tokio::task::spawn_blocking(move || {
+        common::request(address, "GET", "/app.js", &[("Accept-Encoding", "gzip")])
+    })
+    .await
+    .unwrap();
+    let response = String::from_utf8_lossy(&response);
+    assert!(response.ends_with("inside the site"), "got {response}");
+    assert!(
+        !common::header_block(response.as_bytes()).contains("content-encoding: gzip"),
+        "got {response}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
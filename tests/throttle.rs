@@ -0,0 +1,46 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use web_dev_server::{startup::Application, throttle::Throttle};
+
+#[tokio::test]
+async fn throttled_responses_are_delayed_and_paced() {
+    let site = common::temp_site("throttle");
+    std::fs::write(site.join("data.bin"), vec![b'x'; 2000]).unwrap();
+
+    // 16 kbps is 2000 bytes per second, so the body alone takes about a second.
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .throttle(Throttle::new(16, 200))
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (elapsed, response, health_elapsed) = tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+        let response = common::request(address, "GET", "/data.bin", &[]);
+        let elapsed = started.elapsed();
+        let started = Instant::now();
+        common::get(address, "/_live/health");
+        (elapsed, response, started.elapsed())
+    })
+    .await
+    .unwrap();
+
+    assert!(elapsed >= Duration::from_millis(1100), "took {elapsed:?}");
+    assert!(common::header_block(&response).contains("content-length: 2000"));
+    assert!(response.ends_with(&[b'x'; 2000]));
+    assert!(
+        health_elapsed < Duration::from_millis(200),
+        "took {health_elapsed:?}"
+    );
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn typescript_is_served_as_javascript() {
+    let site = common::temp_site("transpile");
+    std::fs::write(site.join("app.ts"), "export const answer: number = 42;\n").unwrap();
+    std::fs::write(site.join("broken.tsx"), "export const = ;\n").unwrap();
+    // Sources are held to the same symlink rules as every other file.
+    let outside = common::temp_site("transpile_outside").join("secret.ts");
+    std::fs::write(&outside, "export const secret: number = 1;\n").unwrap();
+    let _ = std::fs::remove_file(site.join("leak.ts"));
+    std::os::unix::fs::symlink(&outside, site.join("leak.ts")).unwrap();
+
+    // Stands in for esbuild: strips the type annotation, or fails like esbuild on bad syntax.
+    let tools = common::temp_site("transpile_tools");
+    let esbuild = tools.join("esbuild");
+    std::fs::write(
+        &esbuild,
+        "#!/bin/sh\ngrep -q 'const =' \"$1\" && { echo 'Expected identifier' >&2; exit 1; }\n\
+         sed 's/: number//' \"$1\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&esbuild, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .transpile(esbuild.to_string_lossy())
+        .build()
+        .await
+        .unwrap();
+    assert!(app.transpile());
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (source, fallback, broken, leaked) = tokio::task::spawn_blocking(move || {
+        (
+            common::get(address, "/app.ts"),
+            common::get(address, "/app.js"),
+            common::get(address, "/broken.tsx"),
+            common::get(address, "/leak.js"),
+        )
+    })
+    .await
+    .unwrap();
+
+    for response in [&source, &fallback] {
+        assert!(response.starts_with("HTTP/1.1 200"), "got {response}");
+        assert!(
+            response.contains("content-type: text/javascript; charset=utf-8"),
+            "got {response}"
+        );
+        assert!(
+            response.ends_with("export const answer = 42;\n"),
+            "got {response}"
+        );
+    }
+    assert!(broken.starts_with("HTTP/1.1 500"), "got {broken}");
+    assert!(leaked.starts_with("HTTP/1.1 403"), "got {leaked}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use web_dev_server::{startup::Application, tunnel::TunnelProvider};
+
+/// Requests `/` with the given `Host` header, as a tunnel would forward it.
+fn get_with_host(address: SocketAddr, host: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[tokio::test]
+async fn tunnel_urls_are_reported_and_their_host_is_allowed() {
+    let site = common::temp_site("tunnel");
+    let provider: TunnelProvider = "echo forwarding https://demo.example.test to {url}"
+        .parse()
+        .unwrap();
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .tunnel(provider)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.tunnel_url(), Some("https://demo.example.test"));
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let (tunneled, other) = tokio::task::spawn_blocking(move || {
+        (
+            get_with_host(address, "demo.example.test"),
+            get_with_host(address, "evil.example.test"),
+        )
+    })
+    .await
+    .unwrap();
+    assert!(tunneled.starts_with("HTTP/1.1 200"), "got {tunneled}");
+    assert!(other.starts_with("HTTP/1.1 403"), "got {other}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn failed_tunnels_do_not_stop_the_server() {
+    let site = common::temp_site("tunnel_failed");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .tunnel("true --no-url-here".parse().unwrap())
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.tunnel_url(), None);
+}
@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+};
+
+use web_dev_server::startup::Application;
+
+#[tokio::test]
+async fn serves_over_a_unix_socket() {
+    let site = common::temp_site("uds");
+    let socket = site.join("server.sock");
+    // A leftover socket file from a previous run is replaced.
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+
+    let app = Application::builder()
+        .base_dir(&site)
+        .uds(&socket)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.uds_path(), Some(socket.as_path()));
+    assert_eq!(app.network_url(), None);
+
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let path = socket.clone();
+    let response = tokio::task::spawn_blocking(move || {
+        let mut stream = UnixStream::connect(path).unwrap();
+        stream
+            .write_all(b"GET /index.html HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200"), "got {response}");
+    assert!(response.contains("/_live/script.js"), "got {response}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn refuses_to_replace_a_regular_file() {
+    let site = common::temp_site("uds_regular_file");
+    let path = site.join("server.sock");
+    std::fs::write(&path, "not a socket").unwrap();
+
+    let error = Application::builder()
+        .base_dir(&site)
+        .uds(&path)
+        .build()
+        .await
+        .err()
+        .expect("binding over a regular file should fail");
+    assert!(error.to_string().contains("not a socket"), "got {error}");
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use web_dev_server::{
+    config::{DevServerConfig, SiteConfig},
+    workspace::Workspace,
+};
+
+#[tokio::test]
+async fn sites_are_served_side_by_side_and_stop_together() {
+    let root = common::temp_site("workspace");
+    for (dir, body) in [("docs", "docs home"), ("app", "app home")] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("index.html"), body).unwrap();
+    }
+
+    // Outside the sites so writing the logs does not trigger their watchers.
+    let logs = common::temp_site("workspace_logs");
+    let mut config = DevServerConfig {
+        port: 0,
+        no_open_browser: true,
+        config: Some(root.join("web-dev-server.json")),
+        access_log: Some(logs.join("access.log")),
+        ..DevServerConfig::default()
+    };
+    config.file.sites = vec![
+        SiteConfig {
+            name: None,
+            base_dir: "docs".into(),
+            port: None,
+            base_path: None,
+        },
+        SiteConfig {
+            name: Some(String::from("frontend")),
+            base_dir: "app".into(),
+            port: None,
+            base_path: Some(String::from("/app/")),
+        },
+    ];
+
+    let workspace = Workspace::build(&config).await.unwrap();
+    let names: Vec<&str> = workspace
+        .sites()
+        .iter()
+        .map(|site| site.name.as_str())
+        .collect();
+    assert_eq!(names, ["docs", "frontend"]);
+
+    let docs = workspace.sites()[0].app.address();
+    let app = workspace.sites()[1].app.address();
+    assert_ne!(docs.port(), app.port());
+
+    let handle = workspace.handle();
+    let server = tokio::spawn(workspace.run_until_stopped());
+
+    let (docs_page, app_page) =
+        tokio::task::spawn_blocking(move || (common::get(docs, "/"), common::get(app, "/app/")))
+            .await
+            .unwrap();
+    assert!(docs_page.contains("docs home"), "got {docs_page}");
+    assert!(app_page.contains("app home"), "got {app_page}");
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+
+    // Each site writes its own access log.
+    let docs_log = std::fs::read_to_string(logs.join("access.docs.log")).unwrap();
+    let app_log = std::fs::read_to_string(logs.join("access.frontend.log")).unwrap();
+    assert!(docs_log.contains("\"GET / HTTP/1.1\""), "got {docs_log}");
+    assert!(!docs_log.contains("/app/"), "got {docs_log}");
+    assert!(app_log.contains("\"GET /app/ HTTP/1.1\""), "got {app_log}");
+    assert!(!logs.join("access.log").exists());
+}
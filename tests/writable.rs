@@ -0,0 +1,100 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use web_dev_server::startup::Application;
+
+fn send(address: SocketAddr, method: &str, path: &str, body: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[tokio::test]
+async fn put_and_delete_manage_files_under_the_base_dir() {
+    let site = common::temp_site("writable");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .writable(true)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    let check_site = site.clone();
+    tokio::task::spawn_blocking(move || {
+        let created = send(address, "PUT", "/assets/logo.svg", "<svg/>");
+        assert!(created.starts_with("HTTP/1.1 201"), "got {created}");
+        assert_eq!(
+            std::fs::read_to_string(check_site.join("assets/logo.svg")).unwrap(),
+            "<svg/>"
+        );
+        let served = common::get(address, "/assets/logo.svg");
+        assert!(served.ends_with("<svg/>"), "got {served}");
+
+        let replaced = send(address, "PUT", "/assets/logo.svg", "<svg></svg>");
+        assert!(replaced.starts_with("HTTP/1.1 204"), "got {replaced}");
+        // The temporary upload file was renamed over the target, not left behind.
+        let entries: Vec<_> = std::fs::read_dir(check_site.join("assets"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["logo.svg"]);
+
+        let hidden = send(address, "PUT", "/.env", "SECRET=1");
+        assert!(hidden.starts_with("HTTP/1.1 403"), "got {hidden}");
+        let escape = send(address, "PUT", "/../escape.txt", "nope");
+        assert!(escape.starts_with("HTTP/1.1 4"), "got {escape}");
+
+        let deleted = send(address, "DELETE", "/assets/logo.svg", "");
+        assert!(deleted.starts_with("HTTP/1.1 204"), "got {deleted}");
+        assert!(!check_site.join("assets/logo.svg").exists());
+        let missing = send(address, "DELETE", "/assets/logo.svg", "");
+        assert!(missing.starts_with("HTTP/1.1 404"), "got {missing}");
+    })
+    .await
+    .unwrap();
+
+    assert!(!site.join(".env").exists());
+    assert!(!site.parent().unwrap().join("escape.txt").exists());
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn files_are_read_only_by_default() {
+    let site = common::temp_site("writable_disabled");
+    let app = Application::builder()
+        .port(0)
+        .base_dir(&site)
+        .build()
+        .await
+        .unwrap();
+
+    let address = app.address();
+    let handle = app.handle();
+    let server = tokio::spawn(app.run_until_stopped());
+
+    tokio::task::spawn_blocking(move || send(address, "PUT", "/new.txt", "hello"))
+        .await
+        .unwrap();
+    assert!(!site.join("new.txt").exists());
+
+    handle.stop(true).await;
+    server.await.unwrap().unwrap();
+}